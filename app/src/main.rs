@@ -1,5 +1,7 @@
 use nalgebra::Vector2;
 use rrt::cpu::vanilla::VanillaRRT;
+#[cfg(feature = "fpga")]
+use rrt::fpga::verilated::VerilatedRRT;
 use rrt::shared::grid::OccupancyGrid;
 use rrt::{RRTAlgorithm, RRTParameters, RRTResult};
 use sfml::graphics::{
@@ -152,6 +154,20 @@ fn run_vis(result: &RRTResult, real_goal: &Vector2<f32>, grid: &OccupancyGrid) {
     }
 }
 
+#[cfg(feature = "fpga")]
+fn make_rrt() -> Box<dyn RRTAlgorithm> {
+    if std::env::args().any(|arg| arg == "--fpga") {
+        Box::new(VerilatedRRT::default())
+    } else {
+        Box::new(VanillaRRT::default())
+    }
+}
+
+#[cfg(not(feature = "fpga"))]
+fn make_rrt() -> Box<dyn RRTAlgorithm> {
+    Box::new(VanillaRRT::default())
+}
+
 fn main() {
     let diff = MAX_BOUND - MIN_BOUND;
     let x_cells = (diff.x / GRID_RESOLUTION).ceil() as usize;
@@ -162,7 +178,7 @@ fn main() {
     *grid.cell_mut(12, 10) = true;
     *grid.cell_mut(12, 11) = true;
 
-    let rrt = VanillaRRT;
+    let rrt = make_rrt();
     let params = RRTParameters {
         num_points: NUM_POINTS,
         move_dist: MOVE_DIST,
@@ -0,0 +1,69 @@
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use na::{Vector2, vector};
+use nalgebra as na;
+use rand::prelude::*;
+use rrt::cpu::raytrace::is_segment_occupied;
+use rrt::shared::grid::{CellStorage, OccupancyGrid};
+
+const SEGMENTS: usize = 1000;
+
+/// A square grid with `density` of its cells occupied, in the default bit-packed storage.
+fn random_grid(cells: usize, density: f64, rng: &mut StdRng) -> OccupancyGrid {
+    let mut grid = OccupancyGrid::new(cells, cells, vector![0.0, 0.0], 1.0);
+    for y in 0..cells {
+        for x in 0..cells {
+            *grid.cell_mut(x, y) = rng.random_bool(density);
+        }
+    }
+    grid
+}
+
+fn random_segments(cells: usize, rng: &mut StdRng) -> Vec<(Vector2<f32>, Vector2<f32>)> {
+    let size = cells as f32;
+    (0..SEGMENTS)
+        .map(|_| {
+            let a = vector![rng.random_range(0.0..size), rng.random_range(0.0..size)];
+            let b = vector![rng.random_range(0.0..size), rng.random_range(0.0..size)];
+            (a, b)
+        })
+        .collect()
+}
+
+fn run_segment_bench<S: CellStorage>(
+    group: &mut criterion::BenchmarkGroup<criterion::measurement::WallTime>,
+    name: &str,
+    cells: usize,
+    grid: &OccupancyGrid<S>,
+    segments: &[(Vector2<f32>, Vector2<f32>)],
+) {
+    group.bench_with_input(BenchmarkId::new(name, cells), segments, |b, segments| {
+        b.iter(|| {
+            segments
+                .iter()
+                .filter(|(a, b)| is_segment_occupied(a, b, grid))
+                .count()
+        });
+    });
+}
+
+fn bench_segment_occupied(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(10);
+
+    // Sparse enough that most segments walk a long way before hitting anything
+    for density in [0.0001, 0.01] {
+        let mut group = c.benchmark_group(format!("Segment_Occupied_{}", density));
+        group.throughput(Throughput::Elements(SEGMENTS as u64));
+
+        for cells in [1024, 4096, 16384] {
+            let bits = random_grid(cells, density, &mut rng);
+            let bytes: OccupancyGrid<Box<[bool]>> = bits.to_storage();
+            let segments = random_segments(cells, &mut rng);
+
+            run_segment_bench(&mut group, "Bits", cells, &bits, &segments);
+            run_segment_bench(&mut group, "Bytes", cells, &bytes, &segments);
+        }
+    }
+}
+
+criterion_group!(benches, bench_segment_occupied);
+criterion_main!(benches);
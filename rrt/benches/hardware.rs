@@ -0,0 +1,261 @@
+//! Cycle counts for collision checking in hardware: a `directed_energy_weapon` reading an
+//! `occupancy_grid` out of a `bram`, simulated in Verilator. Wall time doesn't mean much for a
+//! simulation, so this reports cycles instead of going through criterion.
+//!
+//! Every segment is checked on its own, so the counts are latencies, from the cycle the segment
+//! is accepted to the cycle its result comes out. The cost of a cell gets fit to them too, for
+//! calibrating `fpga::perf`.
+
+use std::path::Path;
+
+use marlin::verilator::{VerilatorRuntime, VerilatorRuntimeOptions};
+use marlin::verilog::prelude::*;
+use na::vector;
+use nalgebra as na;
+use rand::prelude::*;
+use rrt::fpga::fixed::{self, FixedPoint};
+use rrt::fpga::loader;
+use rrt::fpga::perf::CycleCosts;
+use rrt::shared::grid::OccupancyGrid;
+
+const SEGMENT_COUNT: usize = 2000;
+const DENSITIES: [f64; 3] = [0.0, 0.1, 0.3];
+const HISTOGRAM_BUCKETS: usize = 12;
+const HISTOGRAM_WIDTH: usize = 50;
+
+// A cluster with one unit is exactly the stack we want, with a port to load the grid through
+#[verilog(
+    src = "src/fpga/verilog/test/wrappers/collision_cluster_wrapper.sv",
+    name = "collision_cluster_wrapper",
+    params = {
+        NUM_UNITS: 1,
+        GRID_WIDTH_LOG2: 5,
+        GRID_HEIGHT_LOG2: 5,
+        POINT_BITS: 32,
+        TAG_WIDTH: 8,
+        ADDR_WIDTH: 5,
+        DATA_WIDTH: 32
+    },
+    includes = ["src/fpga/verilog/src/"]
+)]
+struct Grid32;
+
+#[verilog(
+    src = "src/fpga/verilog/test/wrappers/collision_cluster_wrapper.sv",
+    name = "collision_cluster_wrapper",
+    params = {
+        NUM_UNITS: 1,
+        GRID_WIDTH_LOG2: 6,
+        GRID_HEIGHT_LOG2: 6,
+        POINT_BITS: 32,
+        TAG_WIDTH: 8,
+        ADDR_WIDTH: 6,
+        DATA_WIDTH: 64
+    },
+    includes = ["src/fpga/verilog/src/"]
+)]
+struct Grid64;
+
+trait Stack {
+    const NAME: &'static str;
+    const GRID_LOG2: u32;
+    const DATA_WIDTH: usize;
+
+    fn tick(&mut self);
+
+    fn reset(&mut self);
+
+    fn write_word(&mut self, address: usize, word: u64);
+
+    /// Checks one segment. Returns whether it's occupied, and how many cycles it took.
+    fn check(&mut self, a: &FixedPoint, b: &FixedPoint) -> (bool, usize);
+}
+
+macro_rules! impl_stack {
+    ($model:ident, $grid_log2:expr, $data_width:expr) => {
+        impl<'ctx> Stack for $model<'ctx> {
+            const NAME: &'static str = stringify!($model);
+            const GRID_LOG2: u32 = $grid_log2;
+            const DATA_WIDTH: usize = $data_width;
+
+            fn tick(&mut self) {
+                self.clk = 1;
+                self.eval();
+                self.clk = 0;
+                self.eval();
+            }
+
+            fn reset(&mut self) {
+                self.rst_n = 0;
+                self.input_valid = 0;
+                self.load_request = 0;
+                self.load_write_enable = 0;
+                self.clk = 0;
+                self.eval();
+                self.tick();
+                self.rst_n = 1;
+                self.tick();
+            }
+
+            fn write_word(&mut self, address: usize, word: u64) {
+                self.load_request = 1;
+                self.load_address = address as _;
+                self.load_write_data = word as _;
+                self.load_write_enable = 1;
+                self.eval();
+                while self.load_grant == 0 {
+                    self.tick();
+                }
+                self.tick();
+                self.load_request = 0;
+                self.load_write_enable = 0;
+            }
+
+            fn check(&mut self, a: &FixedPoint, b: &FixedPoint) -> (bool, usize) {
+                self.a = a.to_bits();
+                self.b = b.to_bits();
+                self.tag = 0;
+                self.input_valid = 1;
+                self.eval();
+                while self.ready_for_input == 0 {
+                    self.tick();
+                }
+                self.tick();
+                self.input_valid = 0;
+
+                let mut cycles = 1;
+                while self.result_valid == 0 {
+                    self.tick();
+                    cycles += 1;
+                    assert!(cycles < 1_000_000, "result never came back");
+                }
+                (self.result_occupied != 0, cycles)
+            }
+        }
+    };
+}
+
+impl_stack!(Grid32, 5, 32);
+impl_stack!(Grid64, 6, 64);
+
+fn make_runtime() -> VerilatorRuntime {
+    VerilatorRuntime::new2(
+        concat!(env!("CARGO_MANIFEST_DIR"), "/build"),
+        &[concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/src/fpga/verilog/test/wrappers/collision_cluster_wrapper.sv"
+        )],
+        &[Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/src/fpga/verilog/src/"
+        ))],
+        [],
+        VerilatorRuntimeOptions::default(),
+    )
+    .expect("failed to set up Verilator")
+}
+
+fn random_grid(size: usize, p: f64, rng: &mut StdRng) -> OccupancyGrid {
+    let mut grid = OccupancyGrid::new(size, size, vector![0.0, 0.0], 1.0);
+    for y in 0..size {
+        for x in 0..size {
+            *grid.cell_mut(x, y) = rng.random_bool(p);
+        }
+    }
+    grid
+}
+
+/// Prints how many checks took each number of cycles, in evenly sized buckets.
+fn print_histogram(cycles: &[usize]) {
+    let min = *cycles.iter().min().unwrap();
+    let max = *cycles.iter().max().unwrap();
+    let bucket_size = (max - min + 1).div_ceil(HISTOGRAM_BUCKETS);
+
+    let mut counts = vec![0; (max - min) / bucket_size + 1];
+    for &c in cycles {
+        counts[(c - min) / bucket_size] += 1;
+    }
+
+    let most = *counts.iter().max().unwrap();
+    for (i, &count) in counts.iter().enumerate() {
+        let low = min + i * bucket_size;
+        println!(
+            "    {:>5}..{:<5} {:>6} {}",
+            low,
+            low + bucket_size,
+            count,
+            "#".repeat(count * HISTOGRAM_WIDTH / most)
+        );
+    }
+}
+
+fn run<S: Stack>(dut: &mut S, rng: &mut StdRng) {
+    let size = 1 << S::GRID_LOG2;
+
+    for p in DENSITIES {
+        let grid = random_grid(size, p, rng);
+
+        dut.reset();
+        let words = loader::pack_grid(&grid, S::GRID_LOG2, S::GRID_LOG2, S::DATA_WIDTH);
+        for (address, word) in words.into_iter().enumerate() {
+            dut.write_word(address, word);
+        }
+
+        let mut cycles = Vec::with_capacity(SEGMENT_COUNT);
+        let mut samples = Vec::with_capacity(SEGMENT_COUNT);
+        for _ in 0..SEGMENT_COUNT {
+            let a = FixedPoint::new(rng.random(), rng.random());
+            let b = FixedPoint::new(rng.random(), rng.random());
+
+            let (occupied, c) = dut.check(&a, &b);
+            assert_eq!(
+                occupied,
+                fixed::is_segment_occupied(&a, &b, &grid),
+                "hardware disagrees with the reference for {:?} to {:?}",
+                a,
+                b
+            );
+
+            cycles.push(c);
+            samples.push((fixed::cells_walked(&a, &b, &grid), c));
+        }
+
+        let total: usize = cycles.iter().sum();
+        let cells: usize = samples.iter().map(|&(cells, _)| cells).sum();
+        println!(
+            "{} ({}x{}, {:.0}% occupied): {:.2} cycles/check, {:.2} cycles/cell, {} to {} cycles",
+            S::NAME,
+            size,
+            size,
+            p * 100.0,
+            total as f64 / SEGMENT_COUNT as f64,
+            total as f64 / cells as f64,
+            cycles.iter().min().unwrap(),
+            cycles.iter().max().unwrap()
+        );
+        print_histogram(&cycles);
+
+        let mut costs = CycleCosts::default();
+        costs.calibrate_cell(&samples);
+        println!(
+            "    fit: {:.2} cycles/cell (model default {:.2})",
+            costs.cell,
+            CycleCosts::default().cell
+        );
+    }
+}
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(10);
+    let runtime = make_runtime();
+
+    let mut grid32 = runtime
+        .create_model_simple::<Grid32>()
+        .expect("failed to build the 32x32 stack");
+    run(&mut grid32, &mut rng);
+
+    let mut grid64 = runtime
+        .create_model_simple::<Grid64>()
+        .expect("failed to build the 64x64 stack");
+    run(&mut grid64, &mut rng);
+}
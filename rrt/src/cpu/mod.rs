@@ -1,3 +1,3 @@
 pub mod kdtree;
-mod raytrace;
+pub mod raytrace;
 pub mod vanilla;
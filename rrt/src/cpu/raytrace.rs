@@ -1,8 +1,12 @@
 use na::Vector2;
 
-use crate::shared::grid::OccupancyGrid;
+use crate::shared::grid::{CellStorage, OccupancyGrid};
 
-pub fn is_segment_occupied(a: &Vector2<f32>, b: &Vector2<f32>, grid: &OccupancyGrid) -> bool {
+pub fn is_segment_occupied<S: CellStorage>(
+    a: &Vector2<f32>,
+    b: &Vector2<f32>,
+    grid: &OccupancyGrid<S>,
+) -> bool {
     let (mut cell_x, mut cell_y) = grid.position_to_cell(a);
 
     // line can be reparameterized as f(t) = t * delta + a where t: [0, 1]
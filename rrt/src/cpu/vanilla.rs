@@ -1,16 +1,28 @@
 use na::Vector2;
 use nalgebra as na;
-use rand::Rng;
-use rand::distr::Uniform;
 
 use super::raytrace;
 use crate::cpu::kdtree::KdTree;
 use crate::shared::dfs;
 use crate::shared::grid::OccupancyGrid;
+use crate::shared::prng::Xoshiro256StarStar;
 use crate::{RRTAlgorithm, RRTParameters, RRTResult};
-use rand::prelude::*;
 
-pub struct VanillaRRT;
+/// Samples come from the same generator as the hardware's, and get mapped into the bounds the same
+/// way, so with the same seed (and no goal bias on the hardware), both go through the same samples.
+#[derive(Default)]
+pub struct VanillaRRT {
+    /// Seed for the samples. Each run gets a random one if this isn't set.
+    pub seed: Option<u64>,
+}
+
+/// Maps a random word into the bounds like `fpga::fixed::sample`, up to rounding: the upper half of
+/// the word goes to x, and the lower half to y.
+fn sample(word: u64, min_bound: &Vector2<f32>, max_bound: &Vector2<f32>) -> Vector2<f32> {
+    let unit = |r: u64| (r as f64 / (1u64 << 32) as f64) as f32;
+    let t = Vector2::new(unit(word >> 32), unit(word & 0xffff_ffff));
+    min_bound + (max_bound - min_bound).component_mul(&t)
+}
 
 impl RRTAlgorithm for VanillaRRT {
     fn run(
@@ -29,15 +41,10 @@ impl RRTAlgorithm for VanillaRRT {
         let mut found = false;
         let mut end_idx = 0;
 
-        let mut rng = rand::rng();
+        let mut rng = Xoshiro256StarStar::new(self.seed.unwrap_or_else(rand::random));
 
         while kd_tree.len() < params.num_points {
-            let mut conf = Vector2::<f32>::zeros();
-            for i in 0..conf.len() {
-                conf[i] = rng.sample(
-                    Uniform::new_inclusive(params.min_bound[i], params.max_bound[i]).unwrap(),
-                );
-            }
+            let conf = sample(rng.next_u64(), &params.min_bound, &params.max_bound);
 
             let nearest_idx = kd_tree.closest_point(conf).unwrap();
             let nearest = kd_tree[nearest_idx];
@@ -86,3 +93,33 @@ impl RRTAlgorithm for VanillaRRT {
         RRTResult { points, tree, path }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use na::vector;
+
+    use super::*;
+    use crate::fpga::fixed::{self, GridMapping};
+
+    #[test]
+    fn test_samples_match_hardware() {
+        let grid = OccupancyGrid::new(20, 10, vector![-1.0, 2.0], 0.5);
+        let mapping = GridMapping::new(&grid, 5);
+        let (min, max) = (vector![-0.5, 2.5], vector![8.0, 6.75]);
+        let (min_fixed, max_fixed) = (mapping.to_fixed(&min), mapping.to_fixed(&max));
+
+        let mut rng = Xoshiro256StarStar::new(1234);
+        for _ in 0..1000 {
+            let word = rng.next_u64();
+            let hardware = mapping.to_world(&fixed::sample(word, &min_fixed, &max_fixed));
+            let cpu = sample(word, &min, &max);
+            assert!(
+                (hardware - cpu).norm() < 1e-5,
+                "{:?} and {:?} from {:#x}",
+                hardware,
+                cpu,
+                word
+            );
+        }
+    }
+}
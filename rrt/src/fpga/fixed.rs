@@ -0,0 +1,618 @@
+//! Fixed-point geometry matching what the hardware does, so we have something to compare it against.
+//!
+//! Points in the hardware are a pair of unsigned `POINT_BITS`-bit integers (`point_t` in
+//! `point.sv`), covering the whole grid.
+
+use std::cmp::Ordering;
+
+use na::Vector2;
+
+use crate::shared::grid::OccupancyGrid;
+
+/// Number of bits in each coordinate of a `point_t`.
+pub const POINT_BITS: u32 = 32;
+
+/// A point in the hardware's fixed-point coordinate system.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FixedPoint {
+    pub x: u32,
+    pub y: u32,
+}
+
+impl FixedPoint {
+    pub fn new(x: u32, y: u32) -> FixedPoint {
+        FixedPoint { x, y }
+    }
+
+    /// Unpacks a `point_t`, which has `x` in the upper bits.
+    pub fn from_bits(bits: u64) -> FixedPoint {
+        FixedPoint {
+            x: (bits >> POINT_BITS) as u32,
+            y: bits as u32,
+        }
+    }
+
+    /// Packs into the same layout as `point_t`.
+    pub fn to_bits(self) -> u64 {
+        ((self.x as u64) << POINT_BITS) | self.y as u64
+    }
+
+    pub fn sq_dist(&self, other: &FixedPoint) -> u128 {
+        let dx = (self.x as i128 - other.x as i128).unsigned_abs();
+        let dy = (self.y as i128 - other.y as i128).unsigned_abs();
+        dx * dx + dy * dy
+    }
+}
+
+/// Converts between world coordinates and fixed points, for a hardware grid with `2^grid_log2` by
+/// `2^grid_log2` cells laid over an `OccupancyGrid`. The hardware grid starts at the same origin
+/// and has the same cell size, so it has to be at least as big as the `OccupancyGrid`.
+///
+/// The `OccupancyGrid` can be any size, and only covers part of the hardware grid when it isn't
+/// square with power of two sides. `loader::pack_grid` marks the rest occupied, and
+/// `bounds_to_fixed` keeps the planner from sampling there. The hardware grid is always square
+/// here, even though the hardware can have a different width and height, since its cells would
+/// then be stretched in point space and distances wouldn't match the world.
+pub struct GridMapping {
+    origin: Vector2<f32>,
+    // Fixed-point units per world unit
+    scale: f64,
+    // Where the OccupancyGrid ends, saturated to the point range
+    extent: FixedPoint,
+}
+
+impl GridMapping {
+    pub fn new(grid: &OccupancyGrid, grid_log2: u32) -> GridMapping {
+        let (x_cells, y_cells) = grid.size();
+        assert!(x_cells <= 1 << grid_log2 && y_cells <= 1 << grid_log2);
+
+        let cell_size_log2 = POINT_BITS - grid_log2;
+        let end = |cells: usize| ((cells as u64) << cell_size_log2).min(u32::MAX as u64) as u32;
+        GridMapping {
+            origin: grid.origin(),
+            scale: (1u64 << cell_size_log2) as f64 / grid.resolution() as f64,
+            extent: FixedPoint::new(end(x_cells), end(y_cells)),
+        }
+    }
+
+    /// The `grid_log2` of the smallest hardware grid `grid` fits in.
+    pub fn smallest_grid_log2(grid: &OccupancyGrid) -> u32 {
+        let (x_cells, y_cells) = grid.size();
+        x_cells.max(y_cells).next_power_of_two().trailing_zeros()
+    }
+
+    /// The corner of the `OccupancyGrid` opposite its origin. Points below it on both axes are
+    /// on the `OccupancyGrid`.
+    pub fn extent(&self) -> FixedPoint {
+        self.extent
+    }
+
+    /// Converts planner bounds to what `rrt_top` takes, cut down to the `OccupancyGrid`. New points
+    /// have to be below the max bound, so none of them end up in the padding, and neither do the
+    /// edges between them.
+    pub fn bounds_to_fixed(
+        &self,
+        min: &Vector2<f32>,
+        max: &Vector2<f32>,
+    ) -> (FixedPoint, FixedPoint) {
+        let min = self.to_fixed(min);
+        let max = self.to_fixed(max);
+        (
+            FixedPoint::new(min.x.min(self.extent.x), min.y.min(self.extent.y)),
+            FixedPoint::new(max.x.min(self.extent.x), max.y.min(self.extent.y)),
+        )
+    }
+
+    /// Converts a position to the nearest fixed point, clamping it to the point range.
+    pub fn to_fixed(&self, pos: &Vector2<f32>) -> FixedPoint {
+        let convert = |v: f32, origin: f32| {
+            ((v as f64 - origin as f64) * self.scale)
+                .round()
+                .clamp(0.0, u32::MAX as f64) as u32
+        };
+        FixedPoint::new(convert(pos.x, self.origin.x), convert(pos.y, self.origin.y))
+    }
+
+    pub fn to_world(&self, p: &FixedPoint) -> Vector2<f32> {
+        Vector2::new(
+            (p.x as f64 / self.scale + self.origin.x as f64) as f32,
+            (p.y as f64 / self.scale + self.origin.y as f64) as f32,
+        )
+    }
+
+    /// Converts a distance, saturating if it doesn't fit.
+    pub fn distance_to_fixed(&self, dist: f32) -> u32 {
+        (dist as f64 * self.scale)
+            .round()
+            .clamp(0.0, u32::MAX as f64) as u32
+    }
+
+    /// Converts a squared distance, saturating if it doesn't fit.
+    pub fn sq_distance_to_fixed(&self, sq_dist: f32) -> u64 {
+        (sq_dist as f64 * self.scale * self.scale)
+            .round()
+            .clamp(0.0, u64::MAX as f64) as u64
+    }
+}
+
+/// Moves `move_dist` from `from` towards `toward`, rounding to the nearest point. This is the exact
+/// version of what `steer.sv` approximates.
+///
+/// Returns `None` if `from == toward`, or if the result doesn't fit in a `FixedPoint`.
+pub fn steer(from: &FixedPoint, toward: &FixedPoint, move_dist: u32) -> Option<FixedPoint> {
+    if from == toward {
+        return None;
+    }
+
+    let dx = toward.x as f64 - from.x as f64;
+    let dy = toward.y as f64 - from.y as f64;
+    let scale = move_dist as f64 / dx.hypot(dy);
+
+    let x = (from.x as f64 + dx * scale).round();
+    let y = (from.y as f64 + dy * scale).round();
+
+    let range = 0.0..=u32::MAX as f64;
+    if !(range.contains(&x) && range.contains(&y)) {
+        return None;
+    }
+
+    Some(FixedPoint::new(x as u32, y as u32))
+}
+
+/// Turns a random word into a point in `[min, max)`, the same way `sampler.sv` does when it isn't
+/// sampling the goal. The upper half of `word` goes to x, and the lower half to y.
+pub fn sample(word: u64, min: &FixedPoint, max: &FixedPoint) -> FixedPoint {
+    let scale = |r: u32, min: u32, max: u32| {
+        let range = max as u64 - min as u64;
+        min + ((r as u128 * range as u128) >> POINT_BITS) as u32
+    };
+    FixedPoint::new(
+        scale((word >> POINT_BITS) as u32, min.x, max.x),
+        scale(word as u32, min.y, max.y),
+    )
+}
+
+/// The cell containing `p`, on a grid with `2^grid_width_log2` by `2^grid_height_log2` cells
+/// covering the whole point range. Matches `occupancy_grid_util::point_to_cell`.
+pub fn point_to_cell(
+    p: &FixedPoint,
+    grid_width_log2: u32,
+    grid_height_log2: u32,
+) -> (usize, usize) {
+    (
+        (p.x as u64 >> (POINT_BITS - grid_width_log2)) as usize,
+        (p.y as u64 >> (POINT_BITS - grid_height_log2)) as usize,
+    )
+}
+
+/// Exact version of the cell walk `directed_energy_weapon.sv` does, so there are no floating point
+/// rounding issues when comparing against it.
+///
+/// The cells of `grid` cover the whole point range, so its origin and resolution are ignored, and
+/// both of its dimensions must be powers of two. A point exactly on a cell boundary belongs to the
+/// cell above it, like in `point_to_cell`. When the segment passes exactly through a corner, only
+/// one of the two cells next to it needs to be free, the same as in the CPU raytracer.
+///
+/// The DEW tests (`verilog/test/directed_energy_weapon.rs`) check the hardware against this on
+/// every boundary and corner case, and `rrt_top`'s tests check its edges with it, so a change to
+/// either walk has to go in along with the same change to the other.
+pub fn is_segment_occupied(a: &FixedPoint, b: &FixedPoint, grid: &OccupancyGrid) -> bool {
+    walk_segment(a, b, grid, |_, _| ()).0
+}
+
+/// How many cells `is_segment_occupied` steps through before it has an answer, counting the
+/// occupied one it stops at. Peeking at the neighbour of a corner doesn't count, since the walk
+/// doesn't go there.
+pub fn cells_walked(a: &FixedPoint, b: &FixedPoint, grid: &OccupancyGrid) -> usize {
+    walk_segment(a, b, grid, |_, _| ()).1
+}
+
+/// Same as `is_segment_occupied`, but also calls `visit` with each cell it steps through, in order,
+/// the way `cells_walked` counts them.
+pub fn walk_cells(
+    a: &FixedPoint,
+    b: &FixedPoint,
+    grid: &OccupancyGrid,
+    visit: impl FnMut(usize, usize),
+) -> bool {
+    walk_segment(a, b, grid, visit).0
+}
+
+// Whether the segment is occupied, and how many cells it took to find out
+fn walk_segment(
+    a: &FixedPoint,
+    b: &FixedPoint,
+    grid: &OccupancyGrid,
+    mut visit: impl FnMut(usize, usize),
+) -> (bool, usize) {
+    let (x_cells, y_cells) = grid.size();
+    assert!(x_cells.is_power_of_two() && y_cells.is_power_of_two());
+
+    let cell_width_log2 = POINT_BITS - x_cells.trailing_zeros();
+    let cell_height_log2 = POINT_BITS - y_cells.trailing_zeros();
+
+    let (mut cell_x, mut cell_y) =
+        point_to_cell(a, x_cells.trailing_zeros(), y_cells.trailing_zeros());
+
+    // i64 since the boundary after the last cell is at 2^POINT_BITS
+    let (ax, ay) = (a.x as i64, a.y as i64);
+    let (bx, by) = (b.x as i64, b.y as i64);
+    let (dx, dy) = (bx - ax, by - ay);
+
+    let mut walked = 0;
+    loop {
+        walked += 1;
+        visit(cell_x, cell_y);
+        if *grid.cell(cell_x, cell_y) {
+            return (true, walked);
+        }
+
+        let next_x = if dx > 0 { cell_x + 1 } else { cell_x } as i64 * (1 << cell_width_log2);
+        let next_y = if dy > 0 { cell_y + 1 } else { cell_y } as i64 * (1 << cell_height_log2);
+
+        // Crossing a boundary past b, or onto b from the positive side, would leave b's cell
+        let x_beyond_end = if dx > 0 { next_x > bx } else { next_x <= bx };
+        let y_beyond_end = if dy > 0 { next_y > by } else { next_y <= by };
+
+        let x_increment: isize = if dx > 0 { 1 } else { -1 };
+        let y_increment: isize = if dy > 0 { 1 } else { -1 };
+        let next_cell_x = (cell_x as isize + x_increment) as usize;
+        let next_cell_y = (cell_y as isize + y_increment) as usize;
+
+        let step_x = match (x_beyond_end, y_beyond_end) {
+            (true, true) => return (false, walked),
+            (true, false) => false,
+            (false, true) => true,
+            (false, false) => {
+                // Compare the t to each boundary without dividing, see directed_energy_weapon.sv
+                let t_x = (next_x - ax).unsigned_abs() as u128 * dy.unsigned_abs() as u128;
+                let t_y = (next_y - ay).unsigned_abs() as u128 * dx.unsigned_abs() as u128;
+                match t_x.cmp(&t_y) {
+                    Ordering::Less => true,
+                    Ordering::Greater => false,
+                    // Through a corner, go around the x neighbour if it's occupied. If the y
+                    // neighbour is too, we'll hit it next.
+                    Ordering::Equal => !*grid.cell(next_cell_x, cell_y),
+                }
+            }
+        };
+
+        if step_x {
+            cell_x = next_cell_x;
+        } else {
+            cell_y = next_cell_y;
+        }
+    }
+}
+
+/// Exact version of what `directed_energy_weapon.sv` does in mark mode: every cell the segment from
+/// `a` to `b` goes through is cleared, and the cell `b` is in is marked occupied, the way a lidar
+/// beam would update the map. Cells are walked the same way as in `is_segment_occupied`, except that
+/// the segment always goes through the x neighbour of a corner, since what's in the grid shouldn't
+/// change which cells get written.
+///
+/// Matches `OccupancyGrid::apply_ray` when both ends are exactly representable.
+pub fn apply_ray(a: &FixedPoint, b: &FixedPoint, grid: &mut OccupancyGrid) {
+    let (x_cells, y_cells) = grid.size();
+    assert!(x_cells.is_power_of_two() && y_cells.is_power_of_two());
+
+    let cell_width_log2 = POINT_BITS - x_cells.trailing_zeros();
+    let cell_height_log2 = POINT_BITS - y_cells.trailing_zeros();
+
+    let (mut cell_x, mut cell_y) =
+        point_to_cell(a, x_cells.trailing_zeros(), y_cells.trailing_zeros());
+
+    let (ax, ay) = (a.x as i64, a.y as i64);
+    let (bx, by) = (b.x as i64, b.y as i64);
+    let (dx, dy) = (bx - ax, by - ay);
+
+    loop {
+        let next_x = if dx > 0 { cell_x + 1 } else { cell_x } as i64 * (1 << cell_width_log2);
+        let next_y = if dy > 0 { cell_y + 1 } else { cell_y } as i64 * (1 << cell_height_log2);
+
+        let x_beyond_end = if dx > 0 { next_x > bx } else { next_x <= bx };
+        let y_beyond_end = if dy > 0 { next_y > by } else { next_y <= by };
+
+        let step_x = match (x_beyond_end, y_beyond_end) {
+            (true, true) => {
+                *grid.cell_mut(cell_x, cell_y) = true;
+                return;
+            }
+            (true, false) => false,
+            (false, true) => true,
+            (false, false) => {
+                let t_x = (next_x - ax).unsigned_abs() as u128 * dy.unsigned_abs() as u128;
+                let t_y = (next_y - ay).unsigned_abs() as u128 * dx.unsigned_abs() as u128;
+                t_x <= t_y
+            }
+        };
+
+        *grid.cell_mut(cell_x, cell_y) = false;
+        if step_x {
+            cell_x = (cell_x as isize + if dx > 0 { 1 } else { -1 }) as usize;
+        } else {
+            cell_y = (cell_y as isize + if dy > 0 { 1 } else { -1 }) as usize;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use na::vector;
+    use rand::RngExt;
+
+    use super::*;
+
+    #[test]
+    fn test_bits_round_trip() {
+        let p = FixedPoint::new(0xdeadbeef, 0x12345678);
+        assert_eq!(p.to_bits(), 0xdeadbeef_12345678);
+        assert_eq!(FixedPoint::from_bits(p.to_bits()), p);
+    }
+
+    #[test]
+    fn test_steer_axis_aligned() {
+        let from = FixedPoint::new(1000, 1000);
+
+        assert_eq!(
+            steer(&from, &FixedPoint::new(5000, 1000), 100),
+            Some(FixedPoint::new(1100, 1000))
+        );
+        assert_eq!(
+            steer(&from, &FixedPoint::new(1000, 0), 100),
+            Some(FixedPoint::new(1000, 900))
+        );
+    }
+
+    #[test]
+    fn test_steer_out_of_bounds() {
+        let from = FixedPoint::new(50, 50);
+
+        assert_eq!(steer(&from, &from, 100), None);
+        assert_eq!(steer(&from, &FixedPoint::new(0, 50), 100), None);
+        assert_eq!(
+            steer(
+                &FixedPoint::new(u32::MAX - 10, 0),
+                &FixedPoint::new(u32::MAX, 0),
+                100
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_sample_below_max() {
+        let min = FixedPoint::new(10, 20);
+        let max = FixedPoint::new(20, 21);
+
+        assert_eq!(sample(0, &min, &max), min);
+        // The biggest word lands just below max, and y only has the one point
+        assert_eq!(sample(u64::MAX, &min, &max), FixedPoint::new(19, 20));
+        assert_eq!(sample(u64::MAX, &min, &min), min);
+    }
+
+    fn cells_grid(occupied: &[(usize, usize)]) -> OccupancyGrid {
+        let mut grid = OccupancyGrid::new(4, 4, vector![0.0, 0.0], 1.0);
+        for &(x, y) in occupied {
+            *grid.cell_mut(x, y) = true;
+        }
+        grid
+    }
+
+    const CELL: u32 = 1 << 30;
+
+    fn cell_point(x: f64, y: f64) -> FixedPoint {
+        FixedPoint::new((x * CELL as f64) as u32, (y * CELL as f64) as u32)
+    }
+
+    #[test]
+    fn test_point_to_cell() {
+        assert_eq!(point_to_cell(&cell_point(1.5, 3.5), 2, 2), (1, 3));
+        assert_eq!(point_to_cell(&cell_point(2.0, 0.0), 2, 2), (2, 0));
+        assert_eq!(point_to_cell(&FixedPoint::new(u32::MAX, 0), 2, 3), (3, 0));
+    }
+
+    #[test]
+    fn test_segment_negative_deltas() {
+        let grid = cells_grid(&[(0, 0), (2, 2)]);
+
+        assert!(!is_segment_occupied(
+            &cell_point(2.5, 0.5),
+            &cell_point(0.5, 2.5),
+            &grid
+        ));
+        assert!(!is_segment_occupied(
+            &cell_point(0.5, 2.5),
+            &cell_point(2.5, 0.5),
+            &grid
+        ));
+        assert!(is_segment_occupied(
+            &cell_point(3.5, 3.5),
+            &cell_point(1.5, 1.7),
+            &grid
+        ));
+    }
+
+    #[test]
+    fn test_segment_last_cell() {
+        let grid = cells_grid(&[(3, 1)]);
+
+        let a = FixedPoint::new(3 * CELL + 10, CELL / 2);
+        let b = FixedPoint::new(u32::MAX, 3 * CELL / 2);
+        assert!(is_segment_occupied(&a, &b, &grid));
+        assert!(!is_segment_occupied(
+            &a,
+            &FixedPoint::new(u32::MAX, CELL - 1),
+            &grid
+        ));
+    }
+
+    #[test]
+    fn test_segment_ends_on_boundary() {
+        let grid = cells_grid(&[(2, 1)]);
+
+        // (2.0, 1.5) is in cell (2, 1)
+        assert!(is_segment_occupied(
+            &cell_point(0.5, 1.5),
+            &cell_point(2.0, 1.5),
+            &grid
+        ));
+        // Coming from the other side, (3.0, 1.5) is in cell (3, 1), so we never enter (2, 1)
+        assert!(!is_segment_occupied(
+            &cell_point(3.5, 1.5),
+            &cell_point(3.0, 1.5),
+            &grid
+        ));
+    }
+
+    #[test]
+    fn test_segment_through_corner() {
+        let a = cell_point(0.5, 0.5);
+        let b = cell_point(1.5, 1.5);
+
+        assert!(!is_segment_occupied(&a, &b, &cells_grid(&[(1, 0)])));
+        assert!(!is_segment_occupied(&a, &b, &cells_grid(&[(0, 1)])));
+        assert!(is_segment_occupied(&a, &b, &cells_grid(&[(1, 0), (0, 1)])));
+    }
+
+    #[test]
+    fn test_cells_walked() {
+        let a = cell_point(0.5, 0.5);
+        let b = cell_point(3.5, 2.5);
+
+        // Every cell has to be stepped through, one axis at a time
+        assert_eq!(cells_walked(&a, &b, &cells_grid(&[])), 6);
+        assert_eq!(cells_walked(&a, &a, &cells_grid(&[])), 1);
+        // Stops at the first occupied one
+        assert_eq!(cells_walked(&a, &b, &cells_grid(&[(0, 0)])), 1);
+
+        let mut cells = Vec::new();
+        assert!(!walk_cells(&a, &b, &cells_grid(&[]), |x, y| cells.push((x, y))));
+        assert_eq!(cells.len(), 6);
+        assert_eq!(cells.first(), Some(&(0, 0)));
+        assert_eq!(cells.last(), Some(&(3, 2)));
+    }
+
+    #[test]
+    fn test_apply_ray() {
+        let mut grid = cells_grid(&[(1, 0), (0, 1), (2, 1), (3, 3)]);
+
+        // Through both corners by way of their x neighbours, so (0, 1) is never touched
+        apply_ray(&cell_point(0.5, 0.5), &cell_point(2.5, 2.5), &mut grid);
+        let expected = cells_grid(&[(0, 1), (2, 2), (3, 3)]);
+        for y in 0..4 {
+            for x in 0..4 {
+                assert_eq!(grid.cell(x, y), expected.cell(x, y), "cell ({}, {})", x, y);
+            }
+        }
+
+        // A zero-length ray just marks its cell
+        apply_ray(&cell_point(0.5, 3.5), &cell_point(0.5, 3.5), &mut grid);
+        assert!(*grid.cell(0, 3));
+    }
+
+    #[test]
+    fn test_apply_ray_matches_grid() {
+        let mut rng = rand::rng();
+        let lattice = 64;
+        let mapping = GridMapping::new(&cells_grid(&[]), 2);
+
+        for _ in 0..2000 {
+            let mut fixed_grid = cells_grid(&[]);
+            for y in 0..4 {
+                for x in 0..4 {
+                    *fixed_grid.cell_mut(x, y) = rng.random_bool(0.5);
+                }
+            }
+            let mut world_grid = cells_grid(&[]);
+            for y in 0..4 {
+                for x in 0..4 {
+                    *world_grid.cell_mut(x, y) = *fixed_grid.cell(x, y);
+                }
+            }
+
+            // Points on a coarse lattice are exact in both, and land on boundaries and corners a lot
+            let mut random_pos = || {
+                vector![
+                    rng.random_range(0..4 * lattice) as f32 / lattice as f32,
+                    rng.random_range(0..4 * lattice) as f32 / lattice as f32
+                ]
+            };
+            let (from, to) = (random_pos(), random_pos());
+
+            apply_ray(
+                &mapping.to_fixed(&from),
+                &mapping.to_fixed(&to),
+                &mut fixed_grid,
+            );
+            world_grid.apply_ray(&from, &to);
+
+            for y in 0..4 {
+                for x in 0..4 {
+                    assert_eq!(
+                        fixed_grid.cell(x, y),
+                        world_grid.cell(x, y),
+                        "cell ({}, {}) for ray from {:?} to {:?}",
+                        x,
+                        y,
+                        from,
+                        to
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_grid_mapping() {
+        let grid = OccupancyGrid::new(20, 10, vector![-1.0, 2.0], 0.5);
+        let mapping = GridMapping::new(&grid, 5);
+        let cell = 1 << (POINT_BITS - 5);
+
+        assert_eq!(mapping.to_fixed(&vector![-1.0, 2.0]), FixedPoint::new(0, 0));
+        assert_eq!(
+            mapping.to_fixed(&vector![0.25, 2.5]),
+            FixedPoint::new(cell * 5 / 2, cell)
+        );
+        assert_eq!(
+            mapping.to_world(&FixedPoint::new(cell * 4, cell * 3)),
+            vector![1.0, 3.5]
+        );
+
+        // Outside of the point range gets clamped
+        assert_eq!(
+            mapping.to_fixed(&vector![-5.0, 1000.0]),
+            FixedPoint::new(0, u32::MAX)
+        );
+
+        assert_eq!(mapping.distance_to_fixed(1.0), cell * 2);
+        assert_eq!(mapping.sq_distance_to_fixed(1.0), (cell as u64 * 2).pow(2));
+    }
+
+    #[test]
+    fn test_grid_mapping_bounds() {
+        let grid = OccupancyGrid::new(20, 10, vector![-1.0, 2.0], 0.5);
+        assert_eq!(GridMapping::smallest_grid_log2(&grid), 5);
+        let mapping = GridMapping::new(&grid, 5);
+        let cell = 1 << (POINT_BITS - 5);
+
+        assert_eq!(mapping.extent(), FixedPoint::new(cell * 20, cell * 10));
+        assert_eq!(
+            mapping.bounds_to_fixed(&vector![0.0, 0.0], &vector![100.0, 3.0]),
+            (
+                FixedPoint::new(cell * 2, 0),
+                FixedPoint::new(cell * 20, cell * 2)
+            )
+        );
+
+        // A grid that fills the hardware grid ends past the last point
+        let grid = OccupancyGrid::new(32, 7, vector![0.0, 0.0], 1.0);
+        let mapping = GridMapping::new(&grid, GridMapping::smallest_grid_log2(&grid));
+        assert_eq!(mapping.extent(), FixedPoint::new(u32::MAX, cell * 7));
+
+        for (x_cells, y_cells, grid_log2) in [(1, 1, 0), (8, 8, 3), (9, 1, 4), (3, 100, 7)] {
+            let grid = OccupancyGrid::new(x_cells, y_cells, vector![0.0, 0.0], 1.0);
+            assert_eq!(GridMapping::smallest_grid_log2(&grid), grid_log2);
+        }
+    }
+}
@@ -0,0 +1,90 @@
+//! Host side of getting an `OccupancyGrid` into the hardware's grid memory.
+//!
+//! `occupancy_grid.sv` stores cell `(x, y)` at bit `y * 2^grid_width_log2 + x` of memory, split
+//! into `data_width`-bit words. The hardware grid is always a power of two on each side, so any of
+//! it that isn't covered by the `OccupancyGrid` is marked occupied, so nothing can get planned
+//! through it.
+
+use crate::shared::grid::OccupancyGrid;
+
+/// Packs `grid` into memory words, in address order.
+pub fn pack_grid(
+    grid: &OccupancyGrid,
+    grid_width_log2: u32,
+    grid_height_log2: u32,
+    data_width: usize,
+) -> Vec<u64> {
+    let (x_cells, y_cells) = grid.size();
+    let hw_x_cells = 1 << grid_width_log2;
+    let hw_y_cells = 1 << grid_height_log2;
+    assert!(x_cells <= hw_x_cells && y_cells <= hw_y_cells);
+    assert!(data_width <= 64 && (hw_x_cells * hw_y_cells).is_multiple_of(data_width));
+
+    (0..hw_x_cells * hw_y_cells / data_width)
+        .map(|address| {
+            let mut word = 0;
+            for bit in 0..data_width {
+                let linear = address * data_width + bit;
+                let (x, y) = (linear % hw_x_cells, linear / hw_x_cells);
+                if x >= x_cells || y >= y_cells || *grid.cell(x, y) {
+                    word |= 1 << bit;
+                }
+            }
+            word
+        })
+        .collect()
+}
+
+/// Encodes `grid` as the byte stream `grid_loader.sv` expects: every word from `pack_grid`, least
+/// significant byte first.
+pub fn encode_grid(
+    grid: &OccupancyGrid,
+    grid_width_log2: u32,
+    grid_height_log2: u32,
+    data_width: usize,
+) -> Vec<u8> {
+    assert!(data_width.is_multiple_of(8));
+
+    pack_grid(grid, grid_width_log2, grid_height_log2, data_width)
+        .into_iter()
+        .flat_map(|word| word.to_le_bytes().into_iter().take(data_width / 8))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use na::vector;
+
+    use super::*;
+
+    #[test]
+    fn test_pack_grid_layout() {
+        let mut grid = OccupancyGrid::new(4, 4, vector![0.0, 0.0], 1.0);
+        *grid.cell_mut(0, 0) = true;
+        *grid.cell_mut(3, 1) = true;
+        *grid.cell_mut(1, 2) = true;
+
+        // (3, 1) is bit 7, and (1, 2) is bit 9, which is bit 1 of the second word
+        assert_eq!(pack_grid(&grid, 2, 2, 8), vec![0b1000_0001, 0b10]);
+        assert_eq!(pack_grid(&grid, 2, 2, 16), vec![0b10_1000_0001]);
+    }
+
+    #[test]
+    fn test_pack_grid_padding() {
+        let grid = OccupancyGrid::new(3, 1, vector![0.0, 0.0], 1.0);
+
+        // Everything outside of the 3x1 grid is occupied
+        assert_eq!(pack_grid(&grid, 2, 1, 8), vec![0b1111_1000]);
+    }
+
+    #[test]
+    fn test_encode_grid_byte_order() {
+        let mut grid = OccupancyGrid::new(8, 4, vector![0.0, 0.0], 1.0);
+        *grid.cell_mut(0, 0) = true;
+        *grid.cell_mut(0, 1) = true;
+        *grid.cell_mut(0, 3) = true;
+
+        assert_eq!(encode_grid(&grid, 3, 2, 16), vec![1, 1, 0, 1]);
+        assert_eq!(encode_grid(&grid, 3, 2, 32), vec![1, 1, 0, 1]);
+    }
+}
@@ -0,0 +1,348 @@
+//! Memory init files for the grid memory, so a bitstream or a simulation can start with a map
+//! already loaded instead of going through `grid_loader.sv`.
+//!
+//! The words are the ones `loader::pack_grid` makes, which are laid out the way `occupancy_grid.sv`
+//! reads them: cell `(x, y)` is bit `{y, x}` of memory. Addresses past the grid are filled with 0.
+//!
+//! | Format      | For                                                       |
+//! |-------------|-----------------------------------------------------------|
+//! | `ReadmemH`  | `$readmemh`, including `bram.sv`'s `INIT_FILE`            |
+//! | `ReadmemB`  | `$readmemb`, or `bram.sv` with `INIT_BINARY` set          |
+//! | `Coe`       | Xilinx block memory generator                             |
+//! | `Mif`       | Intel/Altera memory initialization files                  |
+
+use std::io::{self, Write};
+
+use snafu::{ResultExt, Snafu, ensure};
+
+use crate::fpga::loader;
+use crate::shared::grid::OccupancyGrid;
+
+/// Width and depth of one Xilinx 36Kb block RAM in each of its aspect ratios.
+const BRAM36_SHAPES: [(usize, usize); 7] = [
+    (1, 32768),
+    (2, 16384),
+    (4, 8192),
+    (9, 4096),
+    (18, 2048),
+    (36, 1024),
+    (72, 512),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InitFormat {
+    ReadmemH,
+    ReadmemB,
+    Coe,
+    Mif,
+}
+
+#[derive(Debug, Snafu)]
+pub enum LayoutError {
+    #[snafu(display("data width {data_width} isn't a power of two from 1 to 64"))]
+    DataWidth { data_width: usize },
+
+    #[snafu(display("{cells} cells don't split evenly into {data_width} bit words"))]
+    UnevenWords { cells: usize, data_width: usize },
+
+    #[snafu(display("{words} words don't fit in {addr_width} address bits"))]
+    TooManyWords { words: usize, addr_width: u32 },
+
+    #[snafu(display("{x_cells}x{y_cells} grid doesn't fit in {width}x{height} cells"))]
+    GridTooBig {
+        x_cells: usize,
+        y_cells: usize,
+        width: usize,
+        height: usize,
+    },
+}
+
+#[derive(Debug, Snafu)]
+pub enum InitError {
+    #[snafu(display("grid doesn't fit the layout"))]
+    Layout { source: LayoutError },
+
+    #[snafu(display("couldn't write init file"))]
+    Io { source: io::Error },
+}
+
+/// How a hardware grid sits in memory, matching the parameters `occupancy_grid` gets through its
+/// `memory_bus`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GridLayout {
+    pub grid_width_log2: u32,
+    pub grid_height_log2: u32,
+    pub data_width: usize,
+    pub addr_width: u32,
+}
+
+impl GridLayout {
+    pub fn new(
+        grid_width_log2: u32,
+        grid_height_log2: u32,
+        data_width: usize,
+        addr_width: u32,
+    ) -> Result<GridLayout, LayoutError> {
+        ensure!(
+            data_width.is_power_of_two() && data_width <= 64,
+            DataWidthSnafu { data_width }
+        );
+
+        let cells = 1usize << (grid_width_log2 + grid_height_log2);
+        ensure!(
+            cells.is_multiple_of(data_width),
+            UnevenWordsSnafu { cells, data_width }
+        );
+
+        let words = cells / data_width;
+        ensure!(
+            words <= 1 << addr_width,
+            TooManyWordsSnafu { words, addr_width }
+        );
+
+        Ok(GridLayout {
+            grid_width_log2,
+            grid_height_log2,
+            data_width,
+            addr_width,
+        })
+    }
+
+    /// The layout with just enough address bits for the grid.
+    pub fn minimal(
+        grid_width_log2: u32,
+        grid_height_log2: u32,
+        data_width: usize,
+    ) -> Result<GridLayout, LayoutError> {
+        let cells = 1usize << (grid_width_log2 + grid_height_log2);
+        let addr_width = (cells / data_width.max(1)).max(1).ilog2();
+        GridLayout::new(grid_width_log2, grid_height_log2, data_width, addr_width)
+    }
+
+    /// Words the grid takes up.
+    pub fn words(&self) -> usize {
+        (1 << (self.grid_width_log2 + self.grid_height_log2)) / self.data_width
+    }
+
+    /// Words in the memory, used or not.
+    pub fn depth(&self) -> usize {
+        1 << self.addr_width
+    }
+
+    /// How many Xilinx 36Kb block RAMs the memory needs, in whichever aspect ratio takes the
+    /// fewest. Tools can sometimes do better by mixing ratios or using 18Kb halves, so this is an
+    /// upper bound.
+    pub fn bram36_count(&self) -> usize {
+        BRAM36_SHAPES
+            .iter()
+            .map(|&(width, depth)| self.data_width.div_ceil(width) * self.depth().div_ceil(depth))
+            .min()
+            .unwrap()
+    }
+
+    /// Every word of the memory, in address order.
+    pub fn pack(&self, grid: &OccupancyGrid) -> Result<Vec<u64>, LayoutError> {
+        let (x_cells, y_cells) = grid.size();
+        let width = 1 << self.grid_width_log2;
+        let height = 1 << self.grid_height_log2;
+        ensure!(
+            x_cells <= width && y_cells <= height,
+            GridTooBigSnafu {
+                x_cells,
+                y_cells,
+                width,
+                height
+            }
+        );
+
+        let mut words = loader::pack_grid(
+            grid,
+            self.grid_width_log2,
+            self.grid_height_log2,
+            self.data_width,
+        );
+        words.resize(self.depth(), 0);
+        Ok(words)
+    }
+
+    /// Writes an init file for `grid` in `format`.
+    pub fn write_init(
+        &self,
+        grid: &OccupancyGrid,
+        format: InitFormat,
+        writer: &mut impl Write,
+    ) -> Result<(), InitError> {
+        let words = self.pack(grid).context(LayoutSnafu)?;
+        let (x_cells, y_cells) = grid.size();
+        let description = format!(
+            "{}x{} occupancy grid, {} words of {} bits",
+            x_cells,
+            y_cells,
+            self.depth(),
+            self.data_width
+        );
+        self.write_words(&words, &description, format, writer)
+            .context(IoSnafu)
+    }
+
+    fn write_words(
+        &self,
+        words: &[u64],
+        description: &str,
+        format: InitFormat,
+        writer: &mut impl Write,
+    ) -> io::Result<()> {
+        let hex_digits = self.data_width.div_ceil(4);
+        let hex = |word: u64| format!("{:0width$x}", word, width = hex_digits);
+
+        match format {
+            InitFormat::ReadmemH => {
+                writeln!(writer, "// {}", description)?;
+                for &word in words {
+                    writeln!(writer, "{}", hex(word))?;
+                }
+            }
+            InitFormat::ReadmemB => {
+                writeln!(writer, "// {}", description)?;
+                for &word in words {
+                    writeln!(writer, "{:0width$b}", word, width = self.data_width)?;
+                }
+            }
+            InitFormat::Coe => {
+                writeln!(writer, "; {}", description)?;
+                writeln!(writer, "memory_initialization_radix=16;")?;
+                writeln!(writer, "memory_initialization_vector=")?;
+                let last = words.len() - 1;
+                for (address, &word) in words.iter().enumerate() {
+                    let end = if address == last { ';' } else { ',' };
+                    writeln!(writer, "{}{}", hex(word), end)?;
+                }
+            }
+            InitFormat::Mif => {
+                writeln!(writer, "-- {}", description)?;
+                writeln!(writer, "WIDTH={};", self.data_width)?;
+                writeln!(writer, "DEPTH={};", self.depth())?;
+                writeln!(writer, "ADDRESS_RADIX=UNS;")?;
+                writeln!(writer, "DATA_RADIX=HEX;")?;
+                writeln!(writer, "CONTENT BEGIN")?;
+                for (address, &word) in words.iter().enumerate() {
+                    writeln!(writer, "    {} : {};", address, hex(word))?;
+                }
+                writeln!(writer, "END;")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use na::vector;
+
+    use super::*;
+
+    fn grid() -> OccupancyGrid {
+        let mut grid = OccupancyGrid::new(4, 4, vector![0.0, 0.0], 1.0);
+        *grid.cell_mut(0, 0) = true;
+        *grid.cell_mut(3, 1) = true;
+        *grid.cell_mut(1, 2) = true;
+        grid
+    }
+
+    fn init_file(layout: &GridLayout, format: InitFormat) -> String {
+        let mut out = Vec::new();
+        layout.write_init(&grid(), format, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_layout_checks() {
+        assert!(matches!(
+            GridLayout::new(2, 2, 12, 4),
+            Err(LayoutError::DataWidth { data_width: 12 })
+        ));
+        assert!(matches!(
+            GridLayout::new(2, 2, 32, 4),
+            Err(LayoutError::UnevenWords { .. })
+        ));
+        assert!(matches!(
+            GridLayout::new(2, 2, 8, 0),
+            Err(LayoutError::TooManyWords { .. })
+        ));
+
+        let layout = GridLayout::minimal(5, 5, 32).unwrap();
+        assert_eq!(layout.addr_width, 5);
+        assert_eq!(layout.words(), 32);
+        assert_eq!(GridLayout::minimal(2, 2, 16).unwrap().addr_width, 0);
+    }
+
+    #[test]
+    fn test_pack_pads_to_depth() {
+        let layout = GridLayout::new(2, 2, 8, 2).unwrap();
+        assert_eq!(layout.pack(&grid()).unwrap(), vec![0b1000_0001, 0b10, 0, 0]);
+    }
+
+    #[test]
+    fn test_grid_too_big() {
+        // Wide enough, but one row too tall
+        let layout = GridLayout::new(3, 2, 8, 2).unwrap();
+        let grid = OccupancyGrid::new(6, 5, vector![0.0, 0.0], 1.0);
+
+        assert!(matches!(
+            layout.pack(&grid),
+            Err(LayoutError::GridTooBig {
+                x_cells: 6,
+                y_cells: 5,
+                width: 8,
+                height: 4
+            })
+        ));
+
+        let mut out = Vec::new();
+        assert!(matches!(
+            layout.write_init(&grid, InitFormat::ReadmemH, &mut out),
+            Err(InitError::Layout { .. })
+        ));
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn test_bram36_count() {
+        // 32x32 in 32 bit words is tiny
+        assert_eq!(GridLayout::minimal(5, 5, 32).unwrap().bram36_count(), 1);
+        // 1024x1024 cells is 1Mb, so at least 29 blocks
+        assert_eq!(GridLayout::minimal(10, 10, 64).unwrap().bram36_count(), 32);
+        assert_eq!(GridLayout::minimal(10, 10, 1).unwrap().bram36_count(), 32);
+    }
+
+    #[test]
+    fn test_formats() {
+        let layout = GridLayout::new(2, 2, 8, 1).unwrap();
+        let description = "4x4 occupancy grid, 2 words of 8 bits";
+
+        assert_eq!(
+            init_file(&layout, InitFormat::ReadmemH),
+            format!("// {}\n81\n02\n", description)
+        );
+        assert_eq!(
+            init_file(&layout, InitFormat::ReadmemB),
+            format!("// {}\n10000001\n00000010\n", description)
+        );
+        assert_eq!(
+            init_file(&layout, InitFormat::Coe),
+            format!(
+                "; {}\nmemory_initialization_radix=16;\nmemory_initialization_vector=\n81,\n02;\n",
+                description
+            )
+        );
+        assert_eq!(
+            init_file(&layout, InitFormat::Mif),
+            format!(
+                "-- {}\nWIDTH=8;\nDEPTH=2;\nADDRESS_RADIX=UNS;\nDATA_RADIX=HEX;\nCONTENT BEGIN\n    \
+                 0 : 81;\n    1 : 02;\nEND;\n",
+                description
+            )
+        );
+    }
+}
@@ -1 +1,9 @@
+pub mod fixed;
+pub mod loader;
+pub mod meminit;
+pub mod perf;
+pub mod protocol;
+pub mod readback;
+#[cfg(feature = "verilator")]
+pub mod verilated;
 mod verilog;
@@ -0,0 +1,507 @@
+//! Cycle-approximate model of `rrt_top.sv`, for trying out design changes without writing them in
+//! Verilog first.
+//!
+//! The model runs the same algorithm as the hardware, in the same fixed point, with the same
+//! random words, and charges each stage of the state machine what it costs in cycles. Steering is
+//! exact here instead of CORDIC, so the tree can drift from the hardware's over a long run, but the
+//! cycle counts stay representative.
+//!
+//! One pass through the loop (an iteration) goes:
+//!
+//! | Stage     | Cycles                                                                  |
+//! |-----------|-------------------------------------------------------------------------|
+//! | Sample    | `sample`, plus `goal_bias_check` for a random point when there's a bias |
+//! | Nearest   | `ceil(nodes / scan_width) + scan_overhead`                              |
+//! | Steer     | `steer_iterations + steer_overhead`                                     |
+//! | Collide   | `collide_overhead` plus a cost per cell walked, if steering stayed in bounds |
+//! | Insert    | `insert` per new node, if the segment was free                          |
+//!
+//! with `start` once at the beginning and `finish` once at the end. The default `CycleCosts` come
+//! from reading the RTL, and can be refit to what Verilator measures (see `calibrate_cell`).
+//!
+//! With more than one DEW unit, each iteration takes that many samples. They're all looked for in
+//! the same scan of the tree, steered and checked side by side, and the free ones get inserted one
+//! after another. That's a design that doesn't exist yet, so it's the model's best guess at one.
+
+use std::time::Duration;
+
+use crate::fpga::fixed::{self, FixedPoint};
+use crate::fpga::protocol::RunParams;
+use crate::shared::grid::OccupancyGrid;
+use crate::shared::prng::Xoshiro256StarStar;
+
+/// How many iterations a run gets per point it's allowed to add before it's given up on. Iterations
+/// that hit something don't add a point, so a start that's walled in would otherwise run forever.
+/// `VerilatedRRT` gives the hardware the same budget.
+pub const ITERATIONS_PER_POINT: usize = 20;
+
+/// The parts of the design being explored. `rrt_top` is one DEW unit with a scan width of 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PipelineConfig {
+    /// How many samples get steered and collision checked at once.
+    pub dew_units: usize,
+
+    /// How many nodes the nearest neighbour scan compares per cycle.
+    pub scan_width: usize,
+
+    /// Bits per grid memory word. Only matters if `CycleCosts::cell_same_word` is different from
+    /// `CycleCosts::cell`.
+    pub grid_data_width: usize,
+
+    pub steer_iterations: u32,
+}
+
+impl PipelineConfig {
+    /// The configuration `rrt_top` has, for a given grid memory and steering precision.
+    pub fn rrt_top(grid_data_width: usize, steer_iterations: u32) -> PipelineConfig {
+        PipelineConfig {
+            dew_units: 1,
+            scan_width: 1,
+            grid_data_width,
+            steer_iterations,
+        }
+    }
+}
+
+/// What each stage costs, in cycles.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CycleCosts {
+    /// Taking the start, and writing the root.
+    pub start: f64,
+    pub sample: f64,
+    /// Extra for a sample that rolled for the goal and didn't get it.
+    pub goal_bias_check: f64,
+    /// Filling and draining the scan pipeline.
+    pub scan_overhead: f64,
+    /// On top of one cycle per steering iteration.
+    pub steer_overhead: f64,
+    pub collide_overhead: f64,
+    /// Reading a cell that's in a different memory word from the last one read.
+    pub cell: f64,
+    /// Reading a cell in the same memory word as the last one. `occupancy_grid.sv` goes to memory
+    /// for every cell, so this is the same as `cell` for now.
+    pub cell_same_word: f64,
+    pub insert: f64,
+    /// Waiting for the last write to the tree before raising done.
+    pub finish: f64,
+}
+
+impl Default for CycleCosts {
+    /// Counted off `rrt_top.sv` and the modules under it. Going through a corner costs another cell
+    /// in the hardware, which isn't counted here.
+    fn default() -> Self {
+        CycleCosts {
+            start: 1.0,
+            sample: 3.0,
+            goal_bias_check: 1.0,
+            scan_overhead: 3.0,
+            steer_overhead: 3.0,
+            collide_overhead: 2.0,
+            cell: 4.0,
+            cell_same_word: 4.0,
+            insert: 1.0,
+            finish: 1.0,
+        }
+    }
+}
+
+impl CycleCosts {
+    /// Refits the cost of a cell to collision checks timed in Verilator, given as (cells walked,
+    /// cycles) pairs like the hardware bench collects. The slope of a least squares line through
+    /// them is the cost of a cell. Its intercept depends on what's around the DEW being timed, so
+    /// `collide_overhead` is left alone.
+    ///
+    /// Needs at least two different numbers of cells.
+    pub fn calibrate_cell(&mut self, samples: &[(usize, usize)]) {
+        let n = samples.len() as f64;
+        let mean_cells = samples.iter().map(|&(c, _)| c as f64).sum::<f64>() / n;
+        let mean_cycles = samples.iter().map(|&(_, c)| c as f64).sum::<f64>() / n;
+
+        let (mut covariance, mut variance) = (0.0, 0.0);
+        for &(cells, cycles) in samples {
+            covariance += (cells as f64 - mean_cells) * (cycles as f64 - mean_cycles);
+            variance += (cells as f64 - mean_cells).powi(2);
+        }
+        assert!(
+            variance > 0.0,
+            "every sample walked the same number of cells"
+        );
+
+        let reuse = self.cell_same_word - self.cell;
+        self.cell = covariance / variance;
+        self.cell_same_word = self.cell + reuse;
+    }
+}
+
+/// Cycles spent in each stage over a whole run.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct StageCycles {
+    pub sample: f64,
+    pub scan: f64,
+    pub steer: f64,
+    pub collide: f64,
+    pub insert: f64,
+    /// Starting and finishing.
+    pub control: f64,
+}
+
+impl StageCycles {
+    pub fn total(&self) -> f64 {
+        self.sample + self.scan + self.steer + self.collide + self.insert + self.control
+    }
+}
+
+/// What the model thinks a run would take.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Estimate {
+    /// Times around the loop, each of which takes `dew_units` samples.
+    pub iterations: usize,
+    pub node_count: usize,
+    pub found: bool,
+    pub cells_walked: usize,
+    pub stages: StageCycles,
+}
+
+impl Estimate {
+    /// Planning latency, from the cycle the start is taken to the one done goes high.
+    pub fn cycles(&self) -> f64 {
+        self.stages.total()
+    }
+
+    pub fn cycles_per_iteration(&self) -> f64 {
+        (self.cycles() - self.stages.control) / self.iterations.max(1) as f64
+    }
+
+    pub fn latency(&self, clock_hz: f64) -> Duration {
+        Duration::from_secs_f64(self.cycles() / clock_hz)
+    }
+}
+
+/// Where to plan, in the hardware's coordinates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Problem {
+    pub start: FixedPoint,
+    pub goal: FixedPoint,
+    pub min_bound: FixedPoint,
+    pub max_bound: FixedPoint,
+    pub seed: u64,
+}
+
+pub struct PerfModel {
+    pub config: PipelineConfig,
+    pub costs: CycleCosts,
+}
+
+impl PerfModel {
+    pub fn new(config: PipelineConfig) -> PerfModel {
+        PerfModel {
+            config,
+            costs: CycleCosts::default(),
+        }
+    }
+
+    /// Runs the planner on `grid`, which is the hardware grid, so both of its sides have to be
+    /// powers of two, like for `fixed::is_segment_occupied`. Gives up without finding the goal
+    /// after `ITERATIONS_PER_POINT` iterations per point.
+    pub fn estimate(
+        &self,
+        grid: &OccupancyGrid,
+        problem: &Problem,
+        params: &RunParams,
+    ) -> Estimate {
+        let costs = &self.costs;
+        let config = &self.config;
+        assert!(config.dew_units > 0 && config.scan_width > 0);
+
+        let mut stages = StageCycles {
+            control: costs.start + costs.finish,
+            ..Default::default()
+        };
+        let mut estimate = Estimate {
+            iterations: 0,
+            node_count: 1,
+            found: false,
+            cells_walked: 0,
+            stages,
+        };
+
+        let num_points = params.num_points as usize;
+        let mut nodes = vec![problem.start];
+        let mut rng = Xoshiro256StarStar::new(problem.seed);
+
+        let max_iterations = ITERATIONS_PER_POINT * num_points;
+
+        while nodes.len() < num_points && estimate.iterations < max_iterations {
+            estimate.iterations += 1;
+
+            let mut sample_cycles: f64 = 0.0;
+            let samples: Vec<_> = (0..config.dew_units)
+                .map(|_| {
+                    let (sample, cycles) = self.sample(&mut rng, problem, params);
+                    sample_cycles = sample_cycles.max(cycles);
+                    sample
+                })
+                .collect();
+            stages.sample += sample_cycles;
+
+            // One scan finds the nearest node for every sample
+            stages.scan += nodes.len().div_ceil(config.scan_width) as f64 + costs.scan_overhead;
+            stages.steer += config.steer_iterations as f64 + costs.steer_overhead;
+
+            let mut collide_cycles: f64 = 0.0;
+            let mut free = Vec::new();
+            for sample in samples {
+                // Ties go to the earlier node
+                let nearest = *nodes
+                    .iter()
+                    .min_by_key(|node| node.sq_dist(&sample))
+                    .unwrap();
+
+                let Some(new_point) = fixed::steer(&nearest, &sample, params.move_dist) else {
+                    continue;
+                };
+                let in_bounds = (problem.min_bound.x..problem.max_bound.x).contains(&new_point.x)
+                    && (problem.min_bound.y..problem.max_bound.y).contains(&new_point.y);
+                if !in_bounds {
+                    continue;
+                }
+
+                let (occupied, cycles) = self.collide(&nearest, &new_point, grid, &mut estimate);
+                collide_cycles = collide_cycles.max(cycles);
+                if !occupied {
+                    free.push(new_point);
+                }
+            }
+            stages.collide += collide_cycles;
+
+            for new_point in free {
+                stages.insert += costs.insert;
+                nodes.push(new_point);
+
+                if new_point.sq_dist(&problem.goal) < params.sq_dist_tol as u128 {
+                    estimate.found = true;
+                    break;
+                }
+                if nodes.len() >= num_points {
+                    break;
+                }
+            }
+
+            if estimate.found {
+                break;
+            }
+        }
+
+        estimate.node_count = nodes.len();
+        estimate.stages = stages;
+        estimate
+    }
+
+    /// Takes a sample the way `sampler.sv` does, and how many cycles it took.
+    fn sample(
+        &self,
+        rng: &mut Xoshiro256StarStar,
+        problem: &Problem,
+        params: &RunParams,
+    ) -> (FixedPoint, f64) {
+        let mut cycles = self.costs.sample;
+
+        if params.goal_bias != 0 {
+            // Each word is only used for one thing
+            if ((rng.next_u64() >> 48) as u16) < params.goal_bias {
+                return (problem.goal, cycles);
+            }
+            cycles += self.costs.goal_bias_check;
+        }
+
+        let word = rng.next_u64();
+        (
+            fixed::sample(word, &problem.min_bound, &problem.max_bound),
+            cycles,
+        )
+    }
+
+    /// Checks a segment, and how many cycles it took.
+    fn collide(
+        &self,
+        a: &FixedPoint,
+        b: &FixedPoint,
+        grid: &OccupancyGrid,
+        estimate: &mut Estimate,
+    ) -> (bool, f64) {
+        let (x_cells, _) = grid.size();
+        let mut cycles = self.costs.collide_overhead;
+        let mut last_word = None;
+
+        let occupied = fixed::walk_cells(a, b, grid, |x, y| {
+            let word = (y * x_cells + x) / self.config.grid_data_width;
+            cycles += if last_word == Some(word) {
+                self.costs.cell_same_word
+            } else {
+                self.costs.cell
+            };
+            last_word = Some(word);
+            estimate.cells_walked += 1;
+        });
+
+        (occupied, cycles)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use na::vector;
+
+    use super::*;
+
+    const GRID_LOG2: u32 = 3;
+    const CELL: u32 = 1 << (fixed::POINT_BITS - GRID_LOG2);
+
+    fn cell_center(x: u32, y: u32) -> FixedPoint {
+        FixedPoint::new(x * CELL + CELL / 2, y * CELL + CELL / 2)
+    }
+
+    fn empty_grid() -> OccupancyGrid {
+        OccupancyGrid::new(1 << GRID_LOG2, 1 << GRID_LOG2, vector![0.0, 0.0], 1.0)
+    }
+
+    fn problem(seed: u64) -> Problem {
+        Problem {
+            start: cell_center(1, 1),
+            goal: cell_center(6, 5),
+            min_bound: FixedPoint::new(0, 0),
+            max_bound: FixedPoint::new(u32::MAX, u32::MAX),
+            seed,
+        }
+    }
+
+    fn params(num_points: u16) -> RunParams {
+        RunParams {
+            move_dist: CELL / 2,
+            sq_dist_tol: (CELL as u64 / 2).pow(2),
+            goal_bias: 1 << 13,
+            num_points,
+        }
+    }
+
+    #[test]
+    fn test_start_only() {
+        let model = PerfModel::new(PipelineConfig::rrt_top(8, 24));
+        let estimate = model.estimate(&empty_grid(), &problem(1), &params(1));
+
+        let costs = CycleCosts::default();
+        assert_eq!(estimate.iterations, 0);
+        assert_eq!(estimate.node_count, 1);
+        assert_eq!(estimate.cycles(), costs.start + costs.finish);
+    }
+
+    #[test]
+    fn test_one_iteration() {
+        let model = PerfModel::new(PipelineConfig::rrt_top(8, 24));
+        let mut p = params(2);
+        p.goal_bias = 0;
+        let estimate = model.estimate(&empty_grid(), &problem(1), &p);
+
+        // Nothing to hit and nowhere to fall out of bounds, so the first sample gets inserted
+        let c = CycleCosts::default();
+        assert_eq!(estimate.iterations, 1);
+        assert_eq!(estimate.node_count, 2);
+        assert!(estimate.cells_walked >= 1);
+        assert_eq!(
+            estimate.cycles(),
+            c.start
+                + c.sample
+                + 1.0
+                + c.scan_overhead
+                + 24.0
+                + c.steer_overhead
+                + c.collide_overhead
+                + c.cell * estimate.cells_walked as f64
+                + c.insert
+                + c.finish
+        );
+    }
+
+    #[test]
+    fn test_finds_goal_around_wall() {
+        let mut grid = empty_grid();
+        for y in 0..6 {
+            *grid.cell_mut(4, y) = true;
+        }
+        let mut problem = problem(0x5eed);
+        problem.goal = cell_center(6, 1);
+
+        let model = PerfModel::new(PipelineConfig::rrt_top(8, 24));
+        let estimate = model.estimate(&grid, &problem, &params(128));
+
+        assert!(estimate.found);
+        assert!(estimate.iterations >= estimate.node_count - 1);
+        assert!(estimate.cycles_per_iteration() > 24.0);
+        assert!(estimate.latency(100e6) > Duration::ZERO);
+    }
+
+    #[test]
+    fn test_gives_up_walled_in() {
+        let mut grid = empty_grid();
+        // Every cell around the start's
+        for y in 0..3 {
+            for x in 0..3 {
+                *grid.cell_mut(x, y) = (x, y) != (1, 1);
+            }
+        }
+
+        let model = PerfModel::new(PipelineConfig::rrt_top(8, 24));
+        let mut p = params(16);
+        p.move_dist = 2 * CELL;
+        let estimate = model.estimate(&grid, &problem(3), &p);
+
+        // Only samples in the start's own cell get anywhere, and not enough of them to fill the tree
+        assert!(!estimate.found);
+        assert!(estimate.node_count < 16);
+        assert_eq!(estimate.iterations, ITERATIONS_PER_POINT * 16);
+    }
+
+    #[test]
+    fn test_design_choices() {
+        let grid = empty_grid();
+        let problem = problem(7);
+        let mut p = params(64);
+        p.sq_dist_tol = 0;
+
+        let base = PerfModel::new(PipelineConfig::rrt_top(8, 24)).estimate(&grid, &problem, &p);
+
+        // Same samples, same tree, fewer cycles scanning
+        let mut config = PipelineConfig::rrt_top(8, 24);
+        config.scan_width = 4;
+        let wide = PerfModel::new(config).estimate(&grid, &problem, &p);
+        assert_eq!(wide.node_count, base.node_count);
+        assert_eq!(wide.stages.collide, base.stages.collide);
+        assert!(wide.stages.scan < base.stages.scan);
+
+        // More units get the tree built in fewer iterations
+        let mut config = PipelineConfig::rrt_top(8, 24);
+        config.dew_units = 4;
+        let parallel = PerfModel::new(config).estimate(&grid, &problem, &p);
+        assert_eq!(parallel.node_count, base.node_count);
+        assert!(parallel.iterations < base.iterations);
+
+        // Reusing words only helps if there's more than one cell per word
+        let mut model = PerfModel::new(PipelineConfig::rrt_top(1, 24));
+        model.costs.cell_same_word = 1.0;
+        assert_eq!(model.estimate(&grid, &problem, &p), base);
+        model.config.grid_data_width = 64;
+        assert!(model.estimate(&grid, &problem, &p).stages.collide < base.stages.collide);
+    }
+
+    #[test]
+    fn test_calibrate_cell() {
+        let mut costs = CycleCosts {
+            cell_same_word: 1.0,
+            ..Default::default()
+        };
+        costs.calibrate_cell(&[(1, 12), (3, 22), (5, 32), (2, 17)]);
+
+        assert!((costs.cell - 5.0).abs() < 1e-9);
+        assert!((costs.cell_same_word - 2.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,734 @@
+//! Command/response protocol for talking to the hardware planner over a serial link, and a client
+//! for it that works over anything `Read + Write`.
+//!
+//! Everything is sent in frames:
+//!
+//! | Field      | Bytes    | Contents                                                  |
+//! |------------|----------|-----------------------------------------------------------|
+//! | `sync`     | 1        | Always [`SYNC`]                                           |
+//! | `kind`     | 1        | A [`Command`], or which command a response is for         |
+//! | `length`   | 2        | Length of `payload`                                       |
+//! | `payload`  | `length` |                                                           |
+//! | `checksum` | 1        | Makes every byte after `sync` add up to 0, mod 256        |
+//!
+//! Numbers are little endian, and points are sent packed the same way as `point_t` (see
+//! [`FixedPoint::to_bits`]).
+//!
+//! The host sends one command at a time, and waits for its response before sending another. If the
+//! command worked, the response has kind `command | RESPONSE`. If it didn't, the response has kind
+//! [`ERROR`], and its payload is the kind of the frame that failed followed by an [`ErrorCode`].
+//!
+//! | Command      | Payload                                | Response payload              |
+//! |--------------|----------------------------------------|-------------------------------|
+//! | `LoadGrid`   | The grid, from [`loader::encode_grid`] |                               |
+//! | `SetStart`   | Point                                  |                               |
+//! | `SetGoal`    | Point                                  |                               |
+//! | `SetBounds`  | Min point, max point                   |                               |
+//! | `SetSeed`    | `u64`, any value including 0           |                               |
+//! | `SetParams`  | [`RunParams`], fields in order         |                               |
+//! | `Run`        |                                        | Sent once it starts           |
+//! | `ReadStatus` |                                        | Flags, node count, goal node  |
+//! | `ReadTree`   |                                        | Point and parent of each node |
+//! | `ReadPath`   |                                        | Node indices, start to goal   |
+//!
+//! The status flags are a `u8` of [`STATUS_RUNNING`], [`STATUS_DONE`] and [`STATUS_FOUND`]. Node
+//! counts, indices and parents are all `u16`. Nothing can be changed while the planner is running.
+//!
+//! [`loader::encode_grid`]: crate::fpga::loader::encode_grid
+
+use std::io::{self, Read, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use snafu::{ResultExt, Snafu, ensure};
+
+use crate::fpga::fixed::FixedPoint;
+
+/// First byte of every frame.
+pub const SYNC: u8 = 0xa5;
+
+/// Set in the kind of a successful response.
+pub const RESPONSE: u8 = 0x80;
+
+/// Kind of an error response.
+pub const ERROR: u8 = 0xff;
+
+pub const STATUS_RUNNING: u8 = 1 << 0;
+pub const STATUS_DONE: u8 = 1 << 1;
+pub const STATUS_FOUND: u8 = 1 << 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Command {
+    LoadGrid = 0x01,
+    SetStart = 0x02,
+    SetGoal = 0x03,
+    SetBounds = 0x04,
+    SetSeed = 0x05,
+    SetParams = 0x06,
+    Run = 0x07,
+    ReadStatus = 0x08,
+    ReadTree = 0x09,
+    ReadPath = 0x0a,
+}
+
+impl TryFrom<u8> for Command {
+    type Error = u8;
+
+    fn try_from(kind: u8) -> Result<Command, u8> {
+        Ok(match kind {
+            0x01 => Command::LoadGrid,
+            0x02 => Command::SetStart,
+            0x03 => Command::SetGoal,
+            0x04 => Command::SetBounds,
+            0x05 => Command::SetSeed,
+            0x06 => Command::SetParams,
+            0x07 => Command::Run,
+            0x08 => Command::ReadStatus,
+            0x09 => Command::ReadTree,
+            0x0a => Command::ReadPath,
+            _ => return Err(kind),
+        })
+    }
+}
+
+/// Why the device rejected a command.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ErrorCode {
+    /// The frame's checksum was wrong, so it was ignored.
+    BadChecksum = 0x01,
+    UnknownCommand = 0x02,
+    /// The payload was the wrong length for the command.
+    BadLength = 0x03,
+    /// The planner is running, so nothing can be changed.
+    Busy = 0x04,
+    /// There's no tree to read, since the planner hasn't finished a run.
+    NotDone = 0x05,
+    /// The last run didn't find the goal, so there's no path.
+    NoPath = 0x06,
+    /// The payload has the right length, but something in it is out of range.
+    InvalidArgument = 0x07,
+}
+
+impl TryFrom<u8> for ErrorCode {
+    type Error = u8;
+
+    fn try_from(code: u8) -> Result<ErrorCode, u8> {
+        Ok(match code {
+            0x01 => ErrorCode::BadChecksum,
+            0x02 => ErrorCode::UnknownCommand,
+            0x03 => ErrorCode::BadLength,
+            0x04 => ErrorCode::Busy,
+            0x05 => ErrorCode::NotDone,
+            0x06 => ErrorCode::NoPath,
+            0x07 => ErrorCode::InvalidArgument,
+            _ => return Err(code),
+        })
+    }
+}
+
+#[derive(Debug, Snafu)]
+pub enum ProtocolError {
+    #[snafu(display("transport failed"))]
+    Io { source: io::Error },
+
+    #[snafu(display("expected sync byte, got {byte:#04x}"))]
+    BadSync { byte: u8 },
+
+    #[snafu(display("bad checksum on frame of kind {kind:#04x}"))]
+    BadChecksum { kind: u8 },
+
+    #[snafu(display("payload of {length} bytes is too long for a frame"))]
+    PayloadTooLong { length: usize },
+
+    #[snafu(display("got response of kind {kind:#04x} to {command:?}"))]
+    UnexpectedResponse { command: Command, kind: u8 },
+
+    #[snafu(display("malformed response to {command:?}"))]
+    Malformed { command: Command },
+
+    #[snafu(display("device rejected {command:?} with {code:?}"))]
+    Device { command: Command, code: ErrorCode },
+
+    #[snafu(display("planner still running after {timeout:?}"))]
+    Timeout { timeout: Duration },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    pub kind: u8,
+    pub payload: Vec<u8>,
+}
+
+fn checksum<'a>(bytes: impl IntoIterator<Item = &'a u8>) -> u8 {
+    bytes
+        .into_iter()
+        .fold(0u8, |sum, &byte| sum.wrapping_add(byte))
+        .wrapping_neg()
+}
+
+pub fn write_frame(writer: &mut impl Write, kind: u8, payload: &[u8]) -> Result<(), ProtocolError> {
+    let length: u16 = payload.len().try_into().map_err(|_| {
+        PayloadTooLongSnafu {
+            length: payload.len(),
+        }
+        .build()
+    })?;
+
+    let mut bytes = Vec::with_capacity(payload.len() + 5);
+    bytes.push(SYNC);
+    bytes.push(kind);
+    bytes.extend_from_slice(&length.to_le_bytes());
+    bytes.extend_from_slice(payload);
+    bytes.push(checksum(&bytes[1..]));
+
+    writer.write_all(&bytes).context(IoSnafu)
+}
+
+/// Reads one frame. This doesn't try to find the next sync byte if the frame doesn't start with
+/// one, since the link is in a bad enough state by then that it's better to start over.
+pub fn read_frame(reader: &mut impl Read) -> Result<Frame, ProtocolError> {
+    let mut header = [0; 4];
+    reader.read_exact(&mut header).context(IoSnafu)?;
+    let [sync, kind, length_low, length_high] = header;
+    ensure!(sync == SYNC, BadSyncSnafu { byte: sync });
+
+    let mut payload = vec![0; u16::from_le_bytes([length_low, length_high]) as usize];
+    reader.read_exact(&mut payload).context(IoSnafu)?;
+
+    let mut received = [0];
+    reader.read_exact(&mut received).context(IoSnafu)?;
+    let expected = checksum(header[1..].iter().chain(&payload));
+    ensure!(received[0] == expected, BadChecksumSnafu { kind });
+
+    Ok(Frame { kind, payload })
+}
+
+/// Everything `rrt_top` needs besides the grid, start, goal, bounds and seed. Distances are in
+/// fixed-point units.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RunParams {
+    pub move_dist: u32,
+    pub sq_dist_tol: u64,
+    pub goal_bias: u16,
+    pub num_points: u16,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Status {
+    pub running: bool,
+    pub done: bool,
+    pub found: bool,
+    pub node_count: usize,
+    pub goal_node: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TreeNode {
+    pub point: FixedPoint,
+    pub parent: usize,
+}
+
+/// Host end of the protocol.
+pub struct Client<T> {
+    transport: T,
+}
+
+impl<T: Read + Write> Client<T> {
+    pub fn new(transport: T) -> Client<T> {
+        Client { transport }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.transport
+    }
+
+    /// Sends a command and waits for its response, returning the response's payload.
+    fn transact(&mut self, command: Command, payload: &[u8]) -> Result<Vec<u8>, ProtocolError> {
+        write_frame(&mut self.transport, command as u8, payload)?;
+        self.transport.flush().context(IoSnafu)?;
+
+        let response = read_frame(&mut self.transport)?;
+        if response.kind == ERROR {
+            ensure!(response.payload.len() == 2, MalformedSnafu { command });
+            let code = ErrorCode::try_from(response.payload[1])
+                .map_err(|_| MalformedSnafu { command }.build())?;
+            return DeviceSnafu { command, code }.fail();
+        }
+
+        ensure!(
+            response.kind == command as u8 | RESPONSE,
+            UnexpectedResponseSnafu {
+                command,
+                kind: response.kind
+            }
+        );
+        Ok(response.payload)
+    }
+
+    /// Like `transact`, for commands that don't have anything in their response.
+    fn transact_empty(&mut self, command: Command, payload: &[u8]) -> Result<(), ProtocolError> {
+        let response = self.transact(command, payload)?;
+        ensure!(response.is_empty(), MalformedSnafu { command });
+        Ok(())
+    }
+
+    /// Loads a grid encoded with `loader::encode_grid`, which has to be the size the hardware was
+    /// built for.
+    pub fn load_grid(&mut self, encoded: &[u8]) -> Result<(), ProtocolError> {
+        self.transact_empty(Command::LoadGrid, encoded)
+    }
+
+    pub fn set_start(&mut self, start: FixedPoint) -> Result<(), ProtocolError> {
+        self.transact_empty(Command::SetStart, &start.to_bits().to_le_bytes())
+    }
+
+    pub fn set_goal(&mut self, goal: FixedPoint) -> Result<(), ProtocolError> {
+        self.transact_empty(Command::SetGoal, &goal.to_bits().to_le_bytes())
+    }
+
+    pub fn set_bounds(&mut self, min: FixedPoint, max: FixedPoint) -> Result<(), ProtocolError> {
+        let mut payload = Vec::with_capacity(16);
+        payload.extend_from_slice(&min.to_bits().to_le_bytes());
+        payload.extend_from_slice(&max.to_bits().to_le_bytes());
+        self.transact_empty(Command::SetBounds, &payload)
+    }
+
+    pub fn set_seed(&mut self, seed: u64) -> Result<(), ProtocolError> {
+        self.transact_empty(Command::SetSeed, &seed.to_le_bytes())
+    }
+
+    pub fn set_params(&mut self, params: &RunParams) -> Result<(), ProtocolError> {
+        let mut payload = Vec::with_capacity(16);
+        payload.extend_from_slice(&params.move_dist.to_le_bytes());
+        payload.extend_from_slice(&params.sq_dist_tol.to_le_bytes());
+        payload.extend_from_slice(&params.goal_bias.to_le_bytes());
+        payload.extend_from_slice(&params.num_points.to_le_bytes());
+        self.transact_empty(Command::SetParams, &payload)
+    }
+
+    /// Starts the planner. This returns straight away, so use `read_status` to find out when it's
+    /// done, or `wait` to block until then.
+    pub fn run(&mut self) -> Result<(), ProtocolError> {
+        self.transact_empty(Command::Run, &[])
+    }
+
+    pub fn read_status(&mut self) -> Result<Status, ProtocolError> {
+        let command = Command::ReadStatus;
+        let response = self.transact(command, &[])?;
+        ensure!(response.len() == 5, MalformedSnafu { command });
+
+        let flags = response[0];
+        Ok(Status {
+            running: flags & STATUS_RUNNING != 0,
+            done: flags & STATUS_DONE != 0,
+            found: flags & STATUS_FOUND != 0,
+            node_count: u16::from_le_bytes([response[1], response[2]]) as usize,
+            goal_node: u16::from_le_bytes([response[3], response[4]]) as usize,
+        })
+    }
+
+    /// Polls the status every `poll_interval` until the planner isn't running anymore, giving up
+    /// once it's been running for `timeout`.
+    pub fn wait(
+        &mut self,
+        timeout: Duration,
+        poll_interval: Duration,
+    ) -> Result<Status, ProtocolError> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let status = self.read_status()?;
+            if !status.running {
+                return Ok(status);
+            }
+
+            ensure!(Instant::now() < deadline, TimeoutSnafu { timeout });
+            thread::sleep(poll_interval);
+        }
+    }
+
+    pub fn read_tree(&mut self) -> Result<Vec<TreeNode>, ProtocolError> {
+        let command = Command::ReadTree;
+        let response = self.transact(command, &[])?;
+        ensure!(
+            response.len().is_multiple_of(10),
+            MalformedSnafu { command }
+        );
+
+        Ok(response
+            .chunks_exact(10)
+            .map(|node| TreeNode {
+                point: FixedPoint::from_bits(u64::from_le_bytes(node[..8].try_into().unwrap())),
+                parent: u16::from_le_bytes([node[8], node[9]]) as usize,
+            })
+            .collect())
+    }
+
+    /// Reads the path from the start to the goal, as indices into the tree.
+    pub fn read_path(&mut self) -> Result<Vec<usize>, ProtocolError> {
+        let command = Command::ReadPath;
+        let response = self.transact(command, &[])?;
+        ensure!(response.len().is_multiple_of(2), MalformedSnafu { command });
+
+        Ok(response
+            .chunks_exact(2)
+            .map(|node| u16::from_le_bytes([node[0], node[1]]) as usize)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::*;
+    use crate::fpga::fixed;
+
+    const GRID_BYTES: usize = 32;
+
+    /// Stands in for the hardware on the other end of the link. Runs are finished as soon as they
+    /// start, and just head straight for the goal.
+    struct Loopback {
+        received: Vec<u8>,
+        to_send: VecDeque<u8>,
+        corrupt_responses: bool,
+        // Reports runs as still going, forever
+        stuck_running: bool,
+
+        start: FixedPoint,
+        goal: FixedPoint,
+        params: Option<RunParams>,
+        tree: Option<Vec<TreeNode>>,
+        found: bool,
+    }
+
+    impl Loopback {
+        fn new() -> Loopback {
+            Loopback {
+                received: Vec::new(),
+                to_send: VecDeque::new(),
+                corrupt_responses: false,
+                stuck_running: false,
+                start: FixedPoint::new(0, 0),
+                goal: FixedPoint::new(0, 0),
+                params: None,
+                tree: None,
+                found: false,
+            }
+        }
+
+        fn respond(&mut self, kind: u8, payload: &[u8]) {
+            let mut bytes = Vec::new();
+            write_frame(&mut bytes, kind, payload).unwrap();
+            if self.corrupt_responses {
+                bytes[4] ^= 1;
+            }
+            self.to_send.extend(bytes);
+        }
+
+        fn handle(&mut self, kind: u8, payload: &[u8]) -> Result<Vec<u8>, ErrorCode> {
+            let command = Command::try_from(kind).map_err(|_| ErrorCode::UnknownCommand)?;
+
+            let expected_length = match command {
+                Command::LoadGrid => GRID_BYTES,
+                Command::SetStart | Command::SetGoal | Command::SetSeed => 8,
+                Command::SetBounds | Command::SetParams => 16,
+                _ => 0,
+            };
+            if payload.len() != expected_length {
+                return Err(ErrorCode::BadLength);
+            }
+
+            let point =
+                || FixedPoint::from_bits(u64::from_le_bytes(payload[..8].try_into().unwrap()));
+
+            match command {
+                Command::LoadGrid | Command::SetBounds | Command::SetSeed => {}
+                Command::SetStart => self.start = point(),
+                Command::SetGoal => self.goal = point(),
+                Command::SetParams => {
+                    self.params = Some(RunParams {
+                        move_dist: u32::from_le_bytes(payload[0..4].try_into().unwrap()),
+                        sq_dist_tol: u64::from_le_bytes(payload[4..12].try_into().unwrap()),
+                        goal_bias: u16::from_le_bytes(payload[12..14].try_into().unwrap()),
+                        num_points: u16::from_le_bytes(payload[14..16].try_into().unwrap()),
+                    })
+                }
+                Command::Run => self.run(),
+                Command::ReadStatus => {
+                    let (mut flags, mut count) = (0, 0);
+                    if let Some(tree) = &self.tree {
+                        flags |= STATUS_DONE;
+                        count = tree.len();
+                    }
+                    if self.found {
+                        flags |= STATUS_FOUND;
+                    }
+                    if self.stuck_running {
+                        flags = STATUS_RUNNING;
+                    }
+
+                    let mut response = vec![flags];
+                    response.extend_from_slice(&(count as u16).to_le_bytes());
+                    response.extend_from_slice(&(count.saturating_sub(1) as u16).to_le_bytes());
+                    return Ok(response);
+                }
+                Command::ReadTree => {
+                    let tree = self.tree.as_ref().ok_or(ErrorCode::NotDone)?;
+                    let mut response = Vec::new();
+                    for node in tree {
+                        response.extend_from_slice(&node.point.to_bits().to_le_bytes());
+                        response.extend_from_slice(&(node.parent as u16).to_le_bytes());
+                    }
+                    return Ok(response);
+                }
+                Command::ReadPath => {
+                    let tree = self.tree.as_ref().ok_or(ErrorCode::NotDone)?;
+                    if !self.found {
+                        return Err(ErrorCode::NoPath);
+                    }
+                    return Ok((0..tree.len() as u16).flat_map(u16::to_le_bytes).collect());
+                }
+            }
+
+            Ok(Vec::new())
+        }
+
+        fn run(&mut self) {
+            let params = self.params.expect("ran without params");
+
+            let mut tree = vec![TreeNode {
+                point: self.start,
+                parent: 0,
+            }];
+            self.found = false;
+
+            while tree.len() < params.num_points as usize {
+                let last = tree.last().unwrap().point;
+                if last.sq_dist(&self.goal) < params.sq_dist_tol as u128 {
+                    self.found = true;
+                    break;
+                }
+
+                let Some(point) = fixed::steer(&last, &self.goal, params.move_dist) else {
+                    break;
+                };
+                tree.push(TreeNode {
+                    point,
+                    parent: tree.len() - 1,
+                });
+            }
+
+            self.tree = Some(tree);
+        }
+    }
+
+    impl Read for Loopback {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.to_send.read(buf)
+        }
+    }
+
+    impl Write for Loopback {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.received.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        // The client flushes after every frame, so that's when we answer it
+        fn flush(&mut self) -> io::Result<()> {
+            let received = std::mem::take(&mut self.received);
+            match read_frame(&mut received.as_slice()) {
+                Ok(frame) => match self.handle(frame.kind, &frame.payload) {
+                    Ok(response) => self.respond(frame.kind | RESPONSE, &response),
+                    Err(code) => self.respond(ERROR, &[frame.kind, code as u8]),
+                },
+                Err(ProtocolError::BadChecksum { kind }) => {
+                    self.respond(ERROR, &[kind, ErrorCode::BadChecksum as u8])
+                }
+                Err(err) => panic!("loopback got a bad frame: {}", err),
+            }
+            Ok(())
+        }
+    }
+
+    fn assert_device_error<T: std::fmt::Debug>(
+        result: Result<T, ProtocolError>,
+        expected: ErrorCode,
+    ) {
+        match result {
+            Err(ProtocolError::Device { code, .. }) => assert_eq!(code, expected),
+            other => panic!("expected {:?}, got {:?}", expected, other),
+        }
+    }
+
+    #[test]
+    fn test_frame_round_trip() {
+        let mut bytes = Vec::new();
+        write_frame(&mut bytes, 0x42, &[1, 2, 3]).unwrap();
+
+        assert_eq!(bytes[..5], [SYNC, 0x42, 3, 0, 1]);
+        assert_eq!(
+            bytes[1..].iter().fold(0u8, |sum, &b| sum.wrapping_add(b)),
+            0
+        );
+
+        let frame = read_frame(&mut bytes.as_slice()).unwrap();
+        assert_eq!(
+            frame,
+            Frame {
+                kind: 0x42,
+                payload: vec![1, 2, 3]
+            }
+        );
+
+        let mut empty = Vec::new();
+        write_frame(&mut empty, 0x07, &[]).unwrap();
+        assert_eq!(read_frame(&mut empty.as_slice()).unwrap().payload, vec![]);
+    }
+
+    #[test]
+    fn test_frame_errors() {
+        let mut bytes = Vec::new();
+        write_frame(&mut bytes, 0x42, &[1, 2, 3]).unwrap();
+
+        let mut corrupted = bytes.clone();
+        corrupted[5] ^= 0x10;
+        assert!(matches!(
+            read_frame(&mut corrupted.as_slice()),
+            Err(ProtocolError::BadChecksum { kind: 0x42 })
+        ));
+
+        assert!(matches!(
+            read_frame(&mut &bytes[1..]),
+            Err(ProtocolError::BadSync { byte: 0x42 })
+        ));
+        assert!(matches!(
+            read_frame(&mut &bytes[..6]),
+            Err(ProtocolError::Io { .. })
+        ));
+        assert!(matches!(
+            write_frame(&mut Vec::new(), 0x01, &vec![0; 1 << 16]),
+            Err(ProtocolError::PayloadTooLong { .. })
+        ));
+    }
+
+    #[test]
+    fn test_full_run() {
+        let mut client = Client::new(Loopback::new());
+
+        let start = FixedPoint::new(1000, 1000);
+        let goal = FixedPoint::new(5000, 4000);
+        let params = RunParams {
+            move_dist: 500,
+            sq_dist_tol: 500 * 500,
+            goal_bias: 0,
+            num_points: 100,
+        };
+
+        client.load_grid(&[0; GRID_BYTES]).unwrap();
+        client.set_start(start).unwrap();
+        client.set_goal(goal).unwrap();
+        client
+            .set_bounds(FixedPoint::new(0, 0), FixedPoint::new(u32::MAX, u32::MAX))
+            .unwrap();
+        client.set_seed(0x5eed).unwrap();
+        client.set_params(&params).unwrap();
+        client.run().unwrap();
+
+        let status = client
+            .wait(Duration::from_secs(1), Duration::from_millis(1))
+            .unwrap();
+        assert!(status.done && status.found);
+
+        let tree = client.read_tree().unwrap();
+        assert_eq!(tree.len(), status.node_count);
+        assert_eq!(tree[0].point, start);
+        assert!(tree[status.goal_node].point.sq_dist(&goal) < params.sq_dist_tol as u128);
+
+        let path = client.read_path().unwrap();
+        assert_eq!(path[0], 0);
+        assert_eq!(*path.last().unwrap(), status.goal_node);
+        for pair in path.windows(2) {
+            assert_eq!(tree[pair[1]].parent, pair[0]);
+        }
+    }
+
+    #[test]
+    fn test_error_replies() {
+        let mut client = Client::new(Loopback::new());
+
+        assert_device_error(client.read_tree(), ErrorCode::NotDone);
+        assert_device_error(client.read_path(), ErrorCode::NotDone);
+        // A seed of 0 is fine, the PRNG never starts from an all-zero state
+        client.set_seed(0).unwrap();
+        assert_device_error(client.load_grid(&[0; 3]), ErrorCode::BadLength);
+
+        // The client can't send these, so do it by hand
+        let mut device = client.into_inner();
+        write_frame(&mut device, 0x7e, &[]).unwrap();
+        device.flush().unwrap();
+        assert_eq!(
+            read_frame(&mut device).unwrap(),
+            Frame {
+                kind: ERROR,
+                payload: vec![0x7e, ErrorCode::UnknownCommand as u8]
+            }
+        );
+
+        let mut corrupted = Vec::new();
+        write_frame(&mut corrupted, Command::SetSeed as u8, &[1; 8]).unwrap();
+        corrupted[6] ^= 0x80;
+        device.write_all(&corrupted).unwrap();
+        device.flush().unwrap();
+        assert_eq!(
+            read_frame(&mut device).unwrap(),
+            Frame {
+                kind: ERROR,
+                payload: vec![Command::SetSeed as u8, ErrorCode::BadChecksum as u8]
+            }
+        );
+
+        // A run that never gets close enough to the goal
+        let mut client = Client::new(device);
+        client.set_goal(FixedPoint::new(1 << 20, 0)).unwrap();
+        client
+            .set_params(&RunParams {
+                move_dist: 10,
+                sq_dist_tol: 1,
+                goal_bias: 0,
+                num_points: 5,
+            })
+            .unwrap();
+        client.run().unwrap();
+        assert!(!client.read_status().unwrap().found);
+        assert_device_error(client.read_path(), ErrorCode::NoPath);
+    }
+
+    #[test]
+    fn test_corrupted_response() {
+        let mut device = Loopback::new();
+        device.corrupt_responses = true;
+        let mut client = Client::new(device);
+
+        assert!(matches!(
+            client.set_seed(1),
+            Err(ProtocolError::BadChecksum { .. })
+        ));
+    }
+
+    #[test]
+    fn test_wait_times_out() {
+        let mut device = Loopback::new();
+        device.stuck_running = true;
+        let mut client = Client::new(device);
+
+        let started = Instant::now();
+        assert!(matches!(
+            client.wait(Duration::from_millis(20), Duration::from_millis(5)),
+            Err(ProtocolError::Timeout { .. })
+        ));
+        assert!(started.elapsed() >= Duration::from_millis(20));
+    }
+}
@@ -0,0 +1,258 @@
+//! Host side of getting a tree out of the hardware, from what `tree_readback.sv` streams out.
+//!
+//! Nodes are numbered the way `rrt_top.sv` stores them: the start is node 0, which is its own
+//! parent, and every other node's parent comes before it. A path readback starts at the goal node
+//! and follows parents back to node 0, and a full tree readback is every node, in order.
+
+use snafu::{Snafu, ensure};
+
+use crate::RRTResult;
+use crate::fpga::fixed::{FixedPoint, GridMapping};
+
+/// One node, as it comes out of `tree_readback.sv`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReadbackNode {
+    pub index: usize,
+    pub point: FixedPoint,
+    pub parent: usize,
+}
+
+#[derive(Debug, Snafu)]
+pub enum ReadbackError {
+    #[snafu(display("nothing was read back"))]
+    Empty,
+
+    #[snafu(display("got node {index} where node {expected} should have been"))]
+    OutOfOrder { index: usize, expected: usize },
+
+    #[snafu(display("got node {index} after the start"))]
+    PastStart { index: usize },
+
+    #[snafu(display("node {index} has parent {parent}, which doesn't come before it"))]
+    BadParent { index: usize, parent: usize },
+
+    #[snafu(display("goal node {goal_node} isn't in a tree of {node_count} nodes"))]
+    BadGoal { goal_node: usize, node_count: usize },
+}
+
+fn check_parent(node: &ReadbackNode) -> Result<(), ReadbackError> {
+    let ok = if node.index == 0 {
+        node.parent == 0
+    } else {
+        node.parent < node.index
+    };
+    ensure!(
+        ok,
+        BadParentSnafu {
+            index: node.index,
+            parent: node.parent
+        }
+    );
+    Ok(())
+}
+
+/// Turns a path readback, goal first, into an `RRTResult` that only has the path in it. Points are
+/// renumbered from the start, so `path` is just every point in order.
+pub fn decode_path(
+    nodes: &[ReadbackNode],
+    mapping: &GridMapping,
+) -> Result<RRTResult, ReadbackError> {
+    ensure!(!nodes.is_empty(), EmptySnafu);
+
+    for (node, next) in nodes.iter().zip(nodes.iter().skip(1)) {
+        check_parent(node)?;
+        ensure!(node.index != 0, PastStartSnafu { index: next.index });
+        ensure!(
+            next.index == node.parent,
+            OutOfOrderSnafu {
+                index: next.index,
+                expected: node.parent
+            }
+        );
+    }
+
+    let root = nodes.last().unwrap();
+    check_parent(root)?;
+    ensure!(
+        root.index == 0,
+        OutOfOrderSnafu {
+            index: root.index,
+            expected: 0usize
+        }
+    );
+
+    let points = nodes
+        .iter()
+        .rev()
+        .map(|node| mapping.to_world(&node.point))
+        .collect();
+    let tree = (0..nodes.len())
+        .map(|i| {
+            if i + 1 < nodes.len() {
+                vec![i + 1]
+            } else {
+                Vec::new()
+            }
+        })
+        .collect();
+
+    Ok(RRTResult {
+        points,
+        tree,
+        path: Some((0..nodes.len()).collect()),
+    })
+}
+
+/// Turns a full tree readback into an `RRTResult`. `goal_node` is the node that reached the goal,
+/// if the run found it, and the path gets filled in by following parents back from it.
+pub fn decode_tree(
+    nodes: &[ReadbackNode],
+    goal_node: Option<usize>,
+    mapping: &GridMapping,
+) -> Result<RRTResult, ReadbackError> {
+    ensure!(!nodes.is_empty(), EmptySnafu);
+
+    let mut tree = vec![Vec::new(); nodes.len()];
+    for (expected, node) in nodes.iter().enumerate() {
+        ensure!(
+            node.index == expected,
+            OutOfOrderSnafu {
+                index: node.index,
+                expected
+            }
+        );
+        check_parent(node)?;
+
+        if node.index != 0 {
+            tree[node.parent].push(node.index);
+        }
+    }
+
+    let path = match goal_node {
+        Some(goal_node) => {
+            ensure!(
+                goal_node < nodes.len(),
+                BadGoalSnafu {
+                    goal_node,
+                    node_count: nodes.len()
+                }
+            );
+
+            // Parents always come first, so this gets to 0
+            let mut path = vec![goal_node];
+            while *path.last().unwrap() != 0 {
+                path.push(nodes[*path.last().unwrap()].parent);
+            }
+            path.reverse();
+            Some(path)
+        }
+        None => None,
+    };
+
+    Ok(RRTResult {
+        points: nodes
+            .iter()
+            .map(|node| mapping.to_world(&node.point))
+            .collect(),
+        tree,
+        path,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use na::vector;
+
+    use super::*;
+    use crate::shared::grid::OccupancyGrid;
+
+    fn mapping() -> GridMapping {
+        GridMapping::new(&OccupancyGrid::new(4, 4, vector![0.0, 0.0], 1.0), 2)
+    }
+
+    fn node(index: usize, parent: usize) -> ReadbackNode {
+        ReadbackNode {
+            index,
+            point: FixedPoint::new(index as u32 * 1000, 0),
+            parent,
+        }
+    }
+
+    // 0 <- 1 <- 3, and 0 <- 2
+    fn tree_nodes() -> Vec<ReadbackNode> {
+        vec![node(0, 0), node(1, 0), node(2, 0), node(3, 1)]
+    }
+
+    #[test]
+    fn test_decode_tree() {
+        let result = decode_tree(&tree_nodes(), Some(3), &mapping()).unwrap();
+
+        assert_eq!(result.tree, vec![vec![1, 2], vec![3], vec![], vec![]]);
+        assert_eq!(result.path, Some(vec![0, 1, 3]));
+        assert_eq!(result.points[3], mapping().to_world(&tree_nodes()[3].point));
+
+        let result = decode_tree(&tree_nodes(), None, &mapping()).unwrap();
+        assert_eq!(result.path, None);
+    }
+
+    #[test]
+    fn test_decode_path() {
+        let result = decode_path(&[node(3, 1), node(1, 0), node(0, 0)], &mapping()).unwrap();
+
+        assert_eq!(result.path, Some(vec![0, 1, 2]));
+        assert_eq!(result.tree, vec![vec![1], vec![2], vec![]]);
+        assert_eq!(result.points[0], mapping().to_world(&node(0, 0).point));
+        assert_eq!(result.points[2], mapping().to_world(&node(3, 1).point));
+
+        // The start on its own is a path too, when it was already at the goal
+        let result = decode_path(&[node(0, 0)], &mapping()).unwrap();
+        assert_eq!(result.path, Some(vec![0]));
+    }
+
+    #[test]
+    fn test_decode_rejects_broken_readback() {
+        let m = mapping();
+
+        assert!(matches!(decode_path(&[], &m), Err(ReadbackError::Empty)));
+        assert!(matches!(
+            decode_path(&[node(3, 1), node(2, 0), node(0, 0)], &m),
+            Err(ReadbackError::OutOfOrder {
+                index: 2,
+                expected: 1
+            })
+        ));
+        assert!(matches!(
+            decode_path(&[node(1, 0), node(0, 0), node(2, 0)], &m),
+            Err(ReadbackError::PastStart { index: 2 })
+        ));
+        assert!(matches!(
+            decode_path(&[node(3, 1), node(1, 0)], &m),
+            Err(ReadbackError::OutOfOrder {
+                index: 1,
+                expected: 0
+            })
+        ));
+
+        let mut nodes = tree_nodes();
+        nodes.swap(1, 2);
+        assert!(matches!(
+            decode_tree(&nodes, None, &m),
+            Err(ReadbackError::OutOfOrder { .. })
+        ));
+
+        let mut nodes = tree_nodes();
+        nodes[2].parent = 3;
+        assert!(matches!(
+            decode_tree(&nodes, None, &m),
+            Err(ReadbackError::BadParent {
+                index: 2,
+                parent: 3
+            })
+        ));
+
+        assert!(matches!(
+            decode_tree(&tree_nodes(), Some(4), &m),
+            Err(ReadbackError::BadGoal { .. })
+        ));
+    }
+}
@@ -0,0 +1,413 @@
+use std::path::Path;
+
+use marlin::verilator::{VerilatorRuntime, VerilatorRuntimeOptions};
+use marlin::verilog::prelude::*;
+use na::Vector2;
+
+use crate::fpga::fixed::{FixedPoint, GridMapping};
+use crate::fpga::loader;
+use crate::fpga::perf::ITERATIONS_PER_POINT;
+use crate::fpga::readback::{self, ReadbackNode};
+use crate::shared::grid::OccupancyGrid;
+use crate::{RRTAlgorithm, RRTParameters, RRTResult};
+
+// The hardware is built for these, so they have to match the params below
+const GRID_DATA_WIDTH: usize = 32;
+const TREE_ADDR_WIDTH: u32 = 11;
+const STEER_ITERATIONS: usize = 24;
+
+// The grid sizes the hardware is built in, as the log2 of a side. A run uses the smallest one its
+// grid fits in.
+const MIN_GRID_LOG2: u32 = 5;
+const MAX_GRID_LOG2: u32 = 7;
+
+/// Inputs to one run, in the hardware's coordinates.
+struct RunInputs {
+    start: FixedPoint,
+    goal: FixedPoint,
+    min_bound: FixedPoint,
+    max_bound: FixedPoint,
+    move_dist: u32,
+    sq_dist_tol: u64,
+    goal_bias: u16,
+    seed: u64,
+    num_points: usize,
+}
+
+/// `rrt_host_top` built for one grid size. The ports are the same for every size, but the grid
+/// address is wider on the bigger ones, so `run` goes through this.
+trait HostTop {
+    const GRID_LOG2: u32;
+
+    fn tick(&mut self);
+
+    fn reset(&mut self);
+
+    /// Writes the grid memory from the host port.
+    fn load_grid(&mut self, words: &[u64]);
+
+    /// Puts the inputs on the ports and pulses input_valid.
+    fn start_run(&mut self, inputs: &RunInputs);
+
+    fn is_done(&self) -> bool;
+
+    /// The node count, and the goal node if one was found.
+    fn result(&self) -> (usize, Option<usize>);
+
+    /// Reads a node back from the tree memory. Only works while the host has it.
+    fn read_node(&mut self, index: usize) -> ReadbackNode;
+
+    fn set_host_select(&mut self, select: bool);
+}
+
+macro_rules! host_top {
+    ($name:ident, $log2:tt, $addr_width:tt) => {
+        #[verilog(src = "src/fpga/verilog/src/rrt_host_top.sv", name = "rrt_host_top", params = { GRID_WIDTH_LOG2: $log2, GRID_HEIGHT_LOG2: $log2, GRID_DATA_WIDTH: 32, GRID_ADDR_WIDTH: $addr_width, TREE_ADDR_WIDTH: 11, STEER_ITERATIONS: 24 }, includes = ["src/fpga/verilog/src/"])]
+        struct $name;
+
+        impl<'ctx> HostTop for $name<'ctx> {
+            const GRID_LOG2: u32 = $log2;
+
+            fn tick(&mut self) {
+                self.clk = 1;
+                self.eval();
+                self.clk = 0;
+                self.eval();
+            }
+
+            fn reset(&mut self) {
+                self.rst_n = 0;
+                self.input_valid = 0;
+                self.host_select = 0;
+                self.host_grid_write_enable = 0;
+                self.clk = 0;
+                self.eval();
+                self.tick();
+                self.rst_n = 1;
+                self.tick();
+            }
+
+            fn load_grid(&mut self, words: &[u64]) {
+                self.host_select = 1;
+                for (address, &word) in words.iter().enumerate() {
+                    self.host_grid_address = address as _;
+                    self.host_grid_write_data = word as u32;
+                    self.host_grid_write_enable = 1;
+                    self.tick();
+                }
+                self.host_grid_write_enable = 0;
+                self.host_select = 0;
+            }
+
+            fn start_run(&mut self, inputs: &RunInputs) {
+                self.start = inputs.start.to_bits();
+                self.goal = inputs.goal.to_bits();
+                self.min_bound = inputs.min_bound.to_bits();
+                self.max_bound = inputs.max_bound.to_bits();
+                self.move_dist = inputs.move_dist;
+                self.sq_dist_tol = inputs.sq_dist_tol;
+                self.goal_bias = inputs.goal_bias;
+                self.seed = inputs.seed;
+                self.num_points = inputs.num_points as u16;
+
+                self.input_valid = 1;
+                self.tick();
+                self.input_valid = 0;
+            }
+
+            fn is_done(&self) -> bool {
+                self.done != 0
+            }
+
+            fn result(&self) -> (usize, Option<usize>) {
+                (
+                    self.node_count as usize,
+                    (self.found != 0).then_some(self.goal_node as usize),
+                )
+            }
+
+            fn read_node(&mut self, index: usize) -> ReadbackNode {
+                self.host_tree_address = index as u16;
+                self.tick();
+                ReadbackNode {
+                    index,
+                    point: FixedPoint::from_bits(self.host_tree_point),
+                    parent: self.host_tree_parent as usize,
+                }
+            }
+
+            fn set_host_select(&mut self, select: bool) {
+                self.host_select = select as u8;
+            }
+        }
+    };
+}
+
+// GRID_ADDR_WIDTH is whatever fits the grid in 32 bit words
+host_top!(RrtTop32, 5, 5);
+host_top!(RrtTop64, 6, 7);
+host_top!(RrtTop128, 7, 9);
+
+/// Runs the hardware RRT (`rrt_top.sv`) in Verilator, so it can be used anywhere a `VanillaRRT`
+/// can.
+///
+/// The hardware is built with a 32x32, 64x64 or 128x128 grid, and room for 2048 points. A run uses
+/// the smallest grid its `OccupancyGrid` fits in, and panics if it doesn't fit in any of them.
+/// `num_points` gets capped. The `OccupancyGrid` can be any shape: any part of the hardware grid
+/// that isn't covered by it is treated as occupied, and the bounds are cut down to it so nothing
+/// gets planned there.
+///
+/// Runs that go on too long, like ones where the start is walled in, are stopped after a number of
+/// cycles that goes with `num_points`, and give back the tree they had built without a path.
+pub struct VerilatedRRT {
+    /// Seed for the hardware PRNG. A `VanillaRRT` with the same seed goes through the same samples
+    /// as long as `goal_bias` is 0.
+    pub seed: u64,
+
+    /// How often the goal gets sampled instead of a random point, out of 2^16.
+    pub goal_bias: u16,
+}
+
+impl Default for VerilatedRRT {
+    fn default() -> Self {
+        VerilatedRRT {
+            seed: rand::random(),
+            goal_bias: 0,
+        }
+    }
+}
+
+impl VerilatedRRT {
+    // Paths are absolute so this works no matter where the caller runs from
+    fn make_runtime() -> VerilatorRuntime {
+        VerilatorRuntime::new2(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/build"),
+            &[concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/src/fpga/verilog/src/rrt_host_top.sv"
+            )],
+            &[Path::new(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/src/fpga/verilog/src/"
+            ))],
+            [],
+            VerilatorRuntimeOptions::default(),
+        )
+        .expect("failed to set up Verilator")
+    }
+
+    fn run_on<D: HostTop>(
+        &self,
+        dut: &mut D,
+        start: &Vector2<f32>,
+        goal: &Vector2<f32>,
+        grid: &OccupancyGrid,
+        params: &RRTParameters,
+    ) -> RRTResult {
+        let mapping = GridMapping::new(grid, D::GRID_LOG2);
+
+        dut.reset();
+        dut.load_grid(&loader::pack_grid(
+            grid,
+            D::GRID_LOG2,
+            D::GRID_LOG2,
+            GRID_DATA_WIDTH,
+        ));
+
+        let (min_bound, max_bound) = mapping.bounds_to_fixed(&params.min_bound, &params.max_bound);
+        let num_points = params.num_points.min(1 << TREE_ADDR_WIDTH);
+        dut.start_run(&RunInputs {
+            start: mapping.to_fixed(start),
+            goal: mapping.to_fixed(goal),
+            min_bound,
+            max_bound,
+            move_dist: mapping.distance_to_fixed(params.move_dist),
+            sq_dist_tol: mapping.sq_distance_to_fixed(params.sq_dist_tol),
+            goal_bias: self.goal_bias,
+            seed: self.seed,
+            num_points,
+        });
+
+        // Plenty for one iteration: a scan over the whole tree, steering, and a segment walked
+        // corner to corner across the grid at a few cycles per cell
+        let iteration_cycles = num_points + STEER_ITERATIONS + 4 * (2 << D::GRID_LOG2) + 64;
+        let max_cycles = ITERATIONS_PER_POINT * num_points.max(1) * iteration_cycles;
+
+        let mut cycles = 0;
+        while !dut.is_done() && cycles < max_cycles {
+            dut.tick();
+            cycles += 1;
+        }
+        let finished = dut.is_done();
+        if !finished {
+            // A node that was just counted is still on its way into the tree memory
+            dut.tick();
+        }
+
+        let (node_count, goal_node) = dut.result();
+        dut.set_host_select(true);
+        let nodes: Vec<_> = (0..node_count).map(|i| dut.read_node(i)).collect();
+
+        // A run that ran out of cycles still has its tree so far, but found isn't valid yet
+        let goal_node = goal_node.filter(|_| finished);
+        readback::decode_tree(&nodes, goal_node, &mapping).expect("hardware built a broken tree")
+    }
+}
+
+impl RRTAlgorithm for VerilatedRRT {
+    fn run(
+        &self,
+        start: &Vector2<f32>,
+        goal: &Vector2<f32>,
+        grid: &OccupancyGrid,
+        params: &RRTParameters,
+    ) -> RRTResult {
+        let grid_log2 = GridMapping::smallest_grid_log2(grid).max(MIN_GRID_LOG2);
+        let (x_cells, y_cells) = grid.size();
+        assert!(
+            grid_log2 <= MAX_GRID_LOG2,
+            "a {}x{} grid doesn't fit in the biggest hardware grid, which is {}x{}",
+            x_cells,
+            y_cells,
+            1 << MAX_GRID_LOG2,
+            1 << MAX_GRID_LOG2
+        );
+
+        let runtime = Self::make_runtime();
+        macro_rules! run_with {
+            ($top:ty) => {
+                self.run_on(
+                    &mut runtime
+                        .create_model_simple::<$top>()
+                        .expect("failed to build rrt_top"),
+                    start,
+                    goal,
+                    grid,
+                    params,
+                )
+            };
+        }
+        match grid_log2 {
+            5 => run_with!(RrtTop32),
+            6 => run_with!(RrtTop64),
+            _ => run_with!(RrtTop128),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use na::vector;
+
+    use super::*;
+    use crate::cpu::raytrace;
+
+    #[test]
+    fn test_path_avoids_obstacles() {
+        let mut grid = OccupancyGrid::new(20, 20, vector![-0.1, -0.1], 0.01);
+        for y in 5..15 {
+            *grid.cell_mut(12, y) = true;
+        }
+
+        let params = RRTParameters {
+            num_points: 1000,
+            move_dist: 0.01,
+            min_bound: vector![-0.1, -0.1],
+            max_bound: vector![0.1, 0.1],
+            sq_dist_tol: 0.0001,
+        };
+
+        let rrt = VerilatedRRT {
+            seed: 0x1234_5678,
+            goal_bias: 1 << 13,
+        };
+        let start = vector![0.0, 0.0];
+        let goal = vector![0.05, 0.0];
+        let result = rrt.run(&start, &goal, &grid, &params);
+
+        assert!((result.points[0] - start).norm() < 1e-6);
+
+        let path = result.path.expect("no path found");
+        assert_eq!(path[0], 0);
+        assert!((result.points[*path.last().unwrap()] - goal).norm_squared() < params.sq_dist_tol);
+
+        for pair in path.windows(2) {
+            assert!(result.tree[pair[0]].contains(&pair[1]));
+            assert!(!raytrace::is_segment_occupied(
+                &result.points[pair[0]],
+                &result.points[pair[1]],
+                &grid
+            ));
+        }
+    }
+
+    #[test]
+    fn test_non_square_grid() {
+        // Too wide for the 32x32 hardware grid, and only covers the bottom of the 64x64 one
+        let mut grid = OccupancyGrid::new(40, 20, vector![-0.1, -0.1], 0.01);
+        for y in 0..15 {
+            *grid.cell_mut(25, y) = true;
+        }
+
+        // Bounds past the grid on every side but the origin's, which get cut down to it
+        let params = RRTParameters {
+            num_points: 2000,
+            move_dist: 0.01,
+            min_bound: vector![-0.1, -0.1],
+            max_bound: vector![0.5, 0.5],
+            sq_dist_tol: 0.0001,
+        };
+
+        let rrt = VerilatedRRT {
+            seed: 0x1234_5678,
+            goal_bias: 1 << 13,
+        };
+        let start = vector![0.0, 0.0];
+        let goal = vector![0.25, -0.05];
+        let result = rrt.run(&start, &goal, &grid, &params);
+
+        for p in &result.points {
+            assert!(p.x < 0.3 && p.y < 0.1, "{:?} is off the grid", p);
+        }
+
+        let path = result.path.expect("no path found");
+        assert!((result.points[*path.last().unwrap()] - goal).norm_squared() < params.sq_dist_tol);
+        for pair in path.windows(2) {
+            assert!(!raytrace::is_segment_occupied(
+                &result.points[pair[0]],
+                &result.points[pair[1]],
+                &grid
+            ));
+        }
+    }
+
+    #[test]
+    fn test_walled_in_start_gives_up() {
+        // The start's cell with every cell around it occupied, so nothing can ever be added
+        let mut grid = OccupancyGrid::new(20, 20, vector![-0.1, -0.1], 0.01);
+        for y in 9..12 {
+            for x in 9..12 {
+                *grid.cell_mut(x, y) = (x, y) != (10, 10);
+            }
+        }
+
+        let params = RRTParameters {
+            num_points: 50,
+            move_dist: 0.01,
+            min_bound: vector![-0.1, -0.1],
+            max_bound: vector![0.1, 0.1],
+            sq_dist_tol: 0.0001,
+        };
+
+        let rrt = VerilatedRRT {
+            seed: 0x1234_5678,
+            goal_bias: 1 << 13,
+        };
+        let start = vector![0.005, 0.005];
+        let result = rrt.run(&start, &vector![0.05, 0.0], &grid, &params);
+
+        assert_eq!(result.points.len(), 1);
+        assert!((result.points[0] - start).norm() < 1e-6);
+        assert_eq!(result.path, None);
+    }
+}
@@ -1,14 +1,35 @@
+`ifndef BRAM_SV
+`define BRAM_SV
+
 `include "membus.sv"
 
+// Contents start out undefined, unless INIT_FILE names a file to load with $readmemh, or
+// $readmemb if INIT_BINARY is set. rrt/src/fpga/meminit.rs writes those for an OccupancyGrid.
 module bram #(
     parameter ADDR_WIDTH,
-    parameter DATA_WIDTH
+    parameter DATA_WIDTH,
+    // Optional, so the many memories that don't need one don't have to say so
+    parameter INIT_FILE = "",
+    parameter INIT_BINARY = 0
 ) (
     input logic clk,
     memory_bus.memory bus
 );
     logic [DATA_WIDTH-1:0] mem_array [0:(1'b1<<ADDR_WIDTH)-1];
 
+    initial begin
+        if (INIT_FILE != "") begin
+            if (INIT_BINARY) begin
+                $readmemb(INIT_FILE, mem_array);
+            end else begin
+                $readmemh(INIT_FILE, mem_array);
+            end
+        end
+    end
+
+    // Nothing else can get in the way, so every access goes through straight away
+    assign bus.grant = '1;
+
     always_ff @(posedge clk) begin
         if (bus.write_enable) begin
             mem_array[bus.address] <= bus.write_data;
@@ -17,3 +38,33 @@ module bram #(
         bus.read_data <= mem_array[bus.address];
     end
 endmodule
+
+// Two ports on one memory, like a true dual port block RAM. Each port works the same as bram's, and
+// doesn't wait on the other. If both write the same address on the same cycle, port_b's write wins.
+module bram_dual_port #(
+    parameter ADDR_WIDTH,
+    parameter DATA_WIDTH
+) (
+    input logic clk,
+    memory_bus.memory port_a,
+    memory_bus.memory port_b
+);
+    logic [DATA_WIDTH-1:0] mem_array [0:(1'b1<<ADDR_WIDTH)-1];
+
+    assign port_a.grant = '1;
+    assign port_b.grant = '1;
+
+    always_ff @(posedge clk) begin
+        if (port_a.write_enable) begin
+            mem_array[port_a.address] <= port_a.write_data;
+        end
+        if (port_b.write_enable) begin
+            mem_array[port_b.address] <= port_b.write_data;
+        end
+
+        port_a.read_data <= mem_array[port_a.address];
+        port_b.read_data <= mem_array[port_b.address];
+    end
+endmodule
+
+`endif
@@ -0,0 +1,207 @@
+`ifndef COLLISION_CLUSTER_SV
+`define COLLISION_CLUSTER_SV
+
+`include "bram.sv"
+`include "directed_energy_weapon.sv"
+`include "membus.sv"
+`include "memory_arbiter.sv"
+`include "occupancy_grid.sv"
+`include "point.sv"
+
+// Checks up to NUM_UNITS segments at once, each on its own directed_energy_weapon. A memory port can
+// only take one access a cycle, and each unit wants one most cycles, so units go in pairs on the two
+// ports of a bram_dual_port, and each pair has its own copy of the grid.
+//
+// Segments go in with a tag whenever ready_for_input is high, and get handed to a free unit. Some
+// segments take longer than others, so results can come back in any order, with the tag their
+// segment went in with. result_valid is only high for one cycle per result, and results can't be
+// held off, so they have to be taken as they come.
+//
+// The grid is written through load, which writes the same word to every copy, on the port of the
+// first unit in each pair. A load waits for every segment being checked to finish, and
+// ready_for_input stays low while load.request is up, so every check sees one whole grid.
+module collision_cluster #(
+    parameter NUM_UNITS,
+    parameter GRID_WIDTH_LOG2,
+    parameter GRID_HEIGHT_LOG2,
+    parameter POINT_BITS,
+    parameter TAG_WIDTH
+) (
+    input logic clk,
+    input logic rst_n,
+
+    input `POINT_T(POINT_BITS) a,
+    input `POINT_T(POINT_BITS) b,
+    input logic [TAG_WIDTH-1:0] tag,
+    input logic input_valid,
+    output logic ready_for_input,
+
+    output logic result_valid,
+    output logic [TAG_WIDTH-1:0] result_tag,
+    output logic result_occupied,
+
+    memory_bus.memory load
+);
+    `POINT_TYPES(POINT_BITS)
+
+    localparam DATA_WIDTH = load.DATA_WIDTH;
+    localparam ADDR_WIDTH = load.ADDR_WIDTH;
+    localparam UNIT_BITS = NUM_UNITS > 1 ? $clog2(NUM_UNITS) : 1;
+    localparam NUM_PAIRS = (NUM_UNITS + 1) / 2;
+
+    // A unit is busy from when it's handed a segment until its result goes out. start is its
+    // input_valid, and running goes up once it has taken the segment, since done is left over from
+    // the last segment until then.
+    logic [NUM_UNITS-1:0] busy;
+    logic [NUM_UNITS-1:0] start;
+    logic [NUM_UNITS-1:0] running;
+    logic [NUM_UNITS-1:0] unit_done;
+    logic [NUM_UNITS-1:0] unit_occupied;
+
+    // Units need their segment to stay put while they work on it
+    point_t unit_a [NUM_UNITS];
+    point_t unit_b [NUM_UNITS];
+    logic [TAG_WIDTH-1:0] unit_tag [NUM_UNITS];
+
+    occupancy_grid_bus #(.GRID_WIDTH_LOG2(GRID_WIDTH_LOG2), .GRID_HEIGHT_LOG2(GRID_HEIGHT_LOG2)) grid_buses [NUM_UNITS] ();
+
+    // Loads only go to the memories once nothing is being checked
+    logic load_allowed;
+    assign load_allowed = busy == '0;
+
+    logic [NUM_PAIRS-1:0] load_grant;
+    logic [DATA_WIDTH-1:0] load_read_data [NUM_PAIRS];
+
+    genvar p;
+    generate
+        for (p = 0; p < NUM_PAIRS; p++) begin : g_pairs
+            memory_bus #(.ADDR_WIDTH(ADDR_WIDTH), .DATA_WIDTH(DATA_WIDTH)) port_a ();
+            memory_bus #(.ADDR_WIDTH(ADDR_WIDTH), .DATA_WIDTH(DATA_WIDTH)) port_b ();
+            memory_bus #(.ADDR_WIDTH(ADDR_WIDTH), .DATA_WIDTH(DATA_WIDTH)) port_a_clients [2] ();
+
+            bram_dual_port #(.ADDR_WIDTH(ADDR_WIDTH), .DATA_WIDTH(DATA_WIDTH)) bram_inst (
+                .clk(clk),
+                .port_a(port_a.memory),
+                .port_b(port_b.memory)
+            );
+
+            // load is client 0, so once it's allowed it always wins
+            assign port_a_clients[0].request = load.request && load_allowed;
+            assign port_a_clients[0].address = load.address;
+            assign port_a_clients[0].write_data = load.write_data;
+            assign port_a_clients[0].write_enable = load.write_enable;
+            assign load_grant[p] = port_a_clients[0].grant;
+            assign load_read_data[p] = port_a_clients[0].read_data;
+
+            memory_arbiter #(.NUM_CLIENTS(2), .ROUND_ROBIN(0)) arbiter (
+                .clk(clk),
+                .rst_n(rst_n),
+                .clients(port_a_clients),
+                .mem(port_a.client)
+            );
+
+            occupancy_grid #(.GRID_WIDTH_LOG2(GRID_WIDTH_LOG2), .GRID_HEIGHT_LOG2(GRID_HEIGHT_LOG2)) first_grid (
+                .clk(clk),
+                .rst_n(rst_n),
+                .bus(grid_buses[2 * p]),
+                .mem(port_a_clients[1])
+            );
+
+            if (2 * p + 1 < NUM_UNITS) begin : g_second
+                occupancy_grid #(.GRID_WIDTH_LOG2(GRID_WIDTH_LOG2), .GRID_HEIGHT_LOG2(GRID_HEIGHT_LOG2)) second_grid (
+                    .clk(clk),
+                    .rst_n(rst_n),
+                    .bus(grid_buses[2 * p + 1]),
+                    .mem(port_b.client)
+                );
+            end else begin : g_no_second
+                assign port_b.request = '0;
+                assign port_b.address = '0;
+                assign port_b.write_data = '0;
+                assign port_b.write_enable = '0;
+            end
+        end
+    endgenerate
+
+    genvar i;
+    generate
+        for (i = 0; i < NUM_UNITS; i++) begin : g_units
+            directed_energy_weapon #(
+                .GRID_WIDTH_LOG2(GRID_WIDTH_LOG2),
+                .GRID_HEIGHT_LOG2(GRID_HEIGHT_LOG2),
+                .POINT_BITS(POINT_BITS)
+            ) dew (
+                .clk(clk),
+                .rst_n(rst_n),
+                .a(unit_a[i]),
+                .b(unit_b[i]),
+                .mark('0),
+                .occupied(unit_occupied[i]),
+                .input_valid(start[i]),
+                .done(unit_done[i]),
+                .grid_bus(grid_buses[i])
+            );
+        end
+    endgenerate
+
+    // Every copy gets the same loads at the same time, so any of them can answer for the rest
+    assign load.grant = load_grant[0];
+    assign load.read_data = load_read_data[0];
+
+    // New segments go to the lowest numbered free unit, and results go out lowest numbered unit
+    // first. A unit takes a few cycles for even the shortest segment, so nobody waits long.
+    logic any_free;
+    logic [UNIT_BITS-1:0] free_unit;
+    logic any_finished;
+    logic [UNIT_BITS-1:0] finished_unit;
+
+    always_comb begin
+        any_free = |(~busy);
+        any_finished = |(running & unit_done);
+
+        // Go backwards so the lowest numbered one is what's left
+        free_unit = '0;
+        finished_unit = '0;
+        for (int j = NUM_UNITS - 1; j >= 0; j--) begin
+            if (!busy[j]) begin
+                free_unit = UNIT_BITS'(j);
+            end
+            if (running[j] && unit_done[j]) begin
+                finished_unit = UNIT_BITS'(j);
+            end
+        end
+
+        ready_for_input = any_free && !load.request;
+    end
+
+    always_ff @(posedge clk) begin
+        if (!rst_n) begin
+            busy <= '0;
+            start <= '0;
+            running <= '0;
+            result_valid <= '0;
+        end else begin
+            // Units take their segment on the cycle start is up
+            running <= running | start;
+            start <= '0;
+
+            if (input_valid && ready_for_input) begin
+                busy[free_unit] <= '1;
+                start[free_unit] <= '1;
+                unit_a[free_unit] <= a;
+                unit_b[free_unit] <= b;
+                unit_tag[free_unit] <= tag;
+            end
+
+            result_valid <= any_finished;
+            if (any_finished) begin
+                result_tag <= unit_tag[finished_unit];
+                result_occupied <= unit_occupied[finished_unit];
+                busy[finished_unit] <= '0;
+                running[finished_unit] <= '0;
+            end
+        end
+    end
+endmodule
+
+`endif
@@ -4,15 +4,25 @@
 `include "occupancy_grid.sv"
 `include "point.sv"
 
+// Walks the cells the segment from a to b goes through, the same way fixed::is_segment_occupied
+// does (see rrt/src/fpga/fixed.rs).
+//
+// Normally, it reads each cell, and stops as soon as it finds one that's occupied. In mark mode, it
+// writes them instead, the way a lidar beam updates the map: every cell is cleared except b's, which
+// is marked occupied. Corners always go through their x neighbour, like fixed::apply_ray, since
+// there's nothing read to decide with. occupied is always 0 after marking.
 module directed_energy_weapon #(
     parameter GRID_WIDTH_LOG2,
-    parameter GRID_HEIGHT_LOG2
+    parameter GRID_HEIGHT_LOG2,
+    parameter POINT_BITS
 ) (
     input logic clk,
     input logic rst_n,
 
-    input point_t a,
-    input point_t b,
+    input `POINT_T(POINT_BITS) a,
+    input `POINT_T(POINT_BITS) b,
+    // Like a and b, this has to stay valid throughout the operation
+    input logic mark,
 
     output logic occupied,
 
@@ -21,18 +31,30 @@ module directed_energy_weapon #(
 
     occupancy_grid_bus.client grid_bus
 );
-    // GRID_CELL_WIDTH = 2^(`POINT_BITS) / GRID_WIDTH
-    //                 = 2^(`POINT_BITS - GRID_WIDTH_LOG2)
+    `POINT_TYPES(POINT_BITS)
+
+    localparam POINT_MULT_BITS = 2 * POINT_BITS;
+
+    // GRID_CELL_WIDTH = 2^POINT_BITS / GRID_WIDTH
+    //                 = 2^(POINT_BITS - GRID_WIDTH_LOG2)
     //
-    // log2(GRID_CELL_WIDTH) = `POINT_BITS - GRID_WIDTH_LOG2
-    localparam GRID_CELL_WIDTH_LOG2 = `POINT_BITS - GRID_WIDTH_LOG2;
-    localparam GRID_CELL_HEIGHT_LOG2 = `POINT_BITS - GRID_HEIGHT_LOG2;
+    // log2(GRID_CELL_WIDTH) = POINT_BITS - GRID_WIDTH_LOG2
+    localparam GRID_CELL_WIDTH_LOG2 = POINT_BITS - GRID_WIDTH_LOG2;
+    localparam GRID_CELL_HEIGHT_LOG2 = POINT_BITS - GRID_HEIGHT_LOG2;
+
+    // Intersections and distances need one more bit than a point, since the intersection after the
+    // last cell is at 2^POINT_BITS
+    localparam INTERSECTION_BITS = POINT_BITS + 1;
 
-    occupancy_grid_util#(GRID_WIDTH_LOG2, GRID_HEIGHT_LOG2) grid_util();
+    occupancy_grid_util#(GRID_WIDTH_LOG2, GRID_HEIGHT_LOG2, POINT_BITS) grid_util();
 
-    typedef enum logic [0:0] {
+    typedef enum logic [2:0] {
         IDLE,
-        TRACING
+        REQUEST_CELL,
+        WAIT_CELL,
+        REQUEST_CORNER,
+        WAIT_CORNER,
+        WRITE_CELL
     } state_t;
 
     state_t state;
@@ -42,6 +64,10 @@ module directed_energy_weapon #(
     logic [GRID_WIDTH_LOG2-1:0] current_cell_x;
     logic [GRID_HEIGHT_LOG2-1:0] current_cell_y;
 
+    // The cells we'd step into along each axis
+    logic [GRID_WIDTH_LOG2-1:0] step_cell_x;
+    logic [GRID_HEIGHT_LOG2-1:0] step_cell_y;
+
     // Note that a and b must stay valid throughout the operation
     logic [GRID_WIDTH_LOG2-1:0] start_cell_x;
     logic [GRID_HEIGHT_LOG2-1:0] start_cell_y;
@@ -52,26 +78,40 @@ module directed_energy_weapon #(
     // Need to have one extra bit to prevent overflow
     logic [GRID_WIDTH_LOG2:0] next_int_cell_x;
     logic [GRID_HEIGHT_LOG2:0] next_int_cell_y;
-    point_t next_intersection;
-    point_diff_t next_int_delta;
-    
+
+    logic [INTERSECTION_BITS-1:0] next_intersection_x;
+    logic [INTERSECTION_BITS-1:0] next_intersection_y;
+
+    // Distances from a to the next intersections, and the length of delta, along each axis
+    logic [INTERSECTION_BITS-1:0] next_int_dist_x;
+    logic [INTERSECTION_BITS-1:0] next_int_dist_y;
+    logic [INTERSECTION_BITS-1:0] abs_delta_x;
+    logic [INTERSECTION_BITS-1:0] abs_delta_y;
+
     logic next_x_beyond_end;
     logic next_y_beyond_end;
 
-    logic signed [`POINT_MULT_BITS:0] intersection_t_diff;
+    logic signed [POINT_MULT_BITS:0] intersection_t_diff;
 
     always_comb begin
         // Note that a and b must stay valid throughout the operation
         delta = point_sub(b, a);
 
+        step_cell_x = delta.x > 0 ? current_cell_x + 1 : current_cell_x - 1;
+        step_cell_y = delta.y > 0 ? current_cell_y + 1 : current_cell_y - 1;
+
         next_int_cell_x = delta.x > 0 ? current_cell_x + 1 : {1'b0, current_cell_x};
         next_int_cell_y = delta.y > 0 ? current_cell_y + 1 : {1'b0, current_cell_y};
 
-        next_intersection.x = `POINT_BITS'(next_int_cell_x) << GRID_CELL_WIDTH_LOG2;
-        next_intersection.y = `POINT_BITS'(next_int_cell_y) << GRID_CELL_HEIGHT_LOG2;
+        next_intersection_x = INTERSECTION_BITS'(next_int_cell_x) << GRID_CELL_WIDTH_LOG2;
+        next_intersection_y = INTERSECTION_BITS'(next_int_cell_y) << GRID_CELL_HEIGHT_LOG2;
 
-        next_x_beyond_end = delta.x > 0 ? (next_intersection.x >= b.x) : (next_intersection.x <= b.x);
-        next_y_beyond_end = delta.y > 0 ? (next_intersection.y >= b.y) : (next_intersection.y <= b.y);
+        // A point on a cell boundary belongs to the cell above it, so going up, b is only past the
+        // next intersection if it's strictly beyond it, and going down, b is in the current cell if
+        // it's anywhere at or above the current cell's lower boundary. An axis with no delta is
+        // always beyond the end, since b is in the current cell along it from the start.
+        next_x_beyond_end = delta.x > 0 ? (next_intersection_x > {1'b0, b.x}) : (next_intersection_x <= {1'b0, b.x});
+        next_y_beyond_end = delta.y > 0 ? (next_intersection_y > {1'b0, b.y}) : (next_intersection_y <= {1'b0, b.y});
 
         // We now need to compute whether we will arrive at the x or y intersection first.
         // Our line can be parameterized as f(t) = a + t * delta, where t: [0, 1].
@@ -84,15 +124,22 @@ module directed_energy_weapon #(
         // t.x = (next_int.x - a.x) / delta.x, t.y = (next_int.y - a.y) / delta.y
         // t.x = k.x / delta.x                 t.y = k.y / delta.y 
         // t.x >? t.y
-        // k.x / delta.x >? k.y / delta.y
-        // k.x * delta.y >? k.y * delta.x
-        // 
+        // |k.x| / |delta.x| >? |k.y| / |delta.y|
+        // |k.x| * |delta.y| >? |k.y| * |delta.x|
+        //
         // So no division is required. We can get away with just DSP slices.
+        // Both t are positive, so we compare magnitudes. Multiplying out the signed values instead
+        // would flip the comparison whenever delta.x and delta.y have different signs.
 
-        next_int_delta = point_sub(next_intersection, a);
+        next_int_dist_x = delta.x > 0 ? next_intersection_x - {1'b0, a.x} : {1'b0, a.x} - next_intersection_x;
+        next_int_dist_y = delta.y > 0 ? next_intersection_y - {1'b0, a.y} : {1'b0, a.y} - next_intersection_y;
 
-        intersection_t_diff = `POINT_MULT_BITS'(next_int_delta.x) * `POINT_MULT_BITS'(delta.y) 
-                            - `POINT_MULT_BITS'(next_int_delta.y) * `POINT_MULT_BITS'(delta.x);
+        abs_delta_x = INTERSECTION_BITS'(delta.x < 0 ? -delta.x : delta.x);
+        abs_delta_y = INTERSECTION_BITS'(delta.y < 0 ? -delta.y : delta.y);
+
+        // Neither product can reach 2^POINT_MULT_BITS, so the sign bit is never lost
+        intersection_t_diff = signed'({1'b0, POINT_MULT_BITS'(next_int_dist_x) * POINT_MULT_BITS'(abs_delta_y)})
+                            - signed'({1'b0, POINT_MULT_BITS'(next_int_dist_y) * POINT_MULT_BITS'(abs_delta_x)});
     end
 
     always_ff @(posedge clk) begin
@@ -110,18 +157,57 @@ module directed_energy_weapon #(
                         done <= '0;
                         current_cell_x <= start_cell_x;
                         current_cell_y <= start_cell_y;
-                        state <= TRACING;
+                        state <= REQUEST_CELL;
                     end else begin
                         state <= IDLE;
                     end
                 end
-                TRACING: begin
-                    if (!grid_bus.output_valid) begin
-                        // Wait for the occupancy grid to give us our value
+                REQUEST_CELL: begin
+                    // Only one request is ever in flight, so output_valid is always for the
+                    // current cell
+                    if (grid_bus.ready_for_input) begin
                         grid_bus.cell_x <= current_cell_x;
                         grid_bus.cell_y <= current_cell_y;
                         grid_bus.input_valid <= '1;
-                    end else begin
+
+                        // When marking, b's cell is the only one that ends up occupied
+                        grid_bus.write_enable <= mark;
+                        grid_bus.write_occupied <= next_x_beyond_end && next_y_beyond_end;
+
+                        state <= mark ? WRITE_CELL : WAIT_CELL;
+                    end
+                end
+                WRITE_CELL: begin
+                    // Writes don't get a response, so we can move on as soon as it's taken
+                    if (grid_bus.ready_for_input) begin
+                        grid_bus.input_valid <= '0;
+                        grid_bus.write_enable <= '0;
+
+                        if (next_x_beyond_end && next_y_beyond_end) begin
+                            occupied <= '0;
+                            done <= '1;
+                            state <= IDLE;
+                        end else begin
+                            if (next_x_beyond_end) begin
+                                current_cell_y <= step_cell_y;
+                            end else if (next_y_beyond_end) begin
+                                current_cell_x <= step_cell_x;
+                            end else if (intersection_t_diff > 0) begin
+                                current_cell_y <= step_cell_y;
+                            end else begin
+                                current_cell_x <= step_cell_x;
+                            end
+                            state <= REQUEST_CELL;
+                        end
+                    end
+                end
+                WAIT_CELL: begin
+                    // The grid can hold us off, so keep asking until it takes the request
+                    if (grid_bus.ready_for_input) begin
+                        grid_bus.input_valid <= '0;
+                    end
+
+                    if (grid_bus.output_valid) begin
                         if (grid_bus.read_occupied) begin
                             // If the grid is occupied at the current cell, we're done
                             occupied <= '1;
@@ -133,21 +219,58 @@ module directed_energy_weapon #(
                             done <= '1;
                             state <= IDLE;
                         end else begin
-                            // We need to go to the next cell
-                            // TODO: delta.y = 0 still works?
-                            if (intersection_t_diff > 0)
-                                current_cell_y <= current_cell_y + (delta.y > 0 ? 1 : -1);
-                            else if (intersection_t_diff < 0)
-                                current_cell_x <= current_cell_x + (delta.x > 0 ? 1 : -1);
-                            else begin
-                                // rare case, t to x intersection == t to y intersection
-                                // TODO: this case actually sucks because we need to do another memory access
-                                // to figure out which path we need to take. for now I'm just gonna increment x
-                                current_cell_x <= current_cell_x + (delta.x > 0 ? 1 : -1);
+                            // We need to go to the next cell. Once we're in b's column or row, we
+                            // can only step along the other axis. This also covers axis-aligned
+                            // segments, where the axis that isn't moving is always beyond the end,
+                            // so we never look at intersection_t_diff for them (it can be 0 if a
+                            // is on a boundary, which would look like a corner).
+                            if (next_x_beyond_end) begin
+                                current_cell_y <= step_cell_y;
+                                state <= REQUEST_CELL;
+                            end else if (next_y_beyond_end) begin
+                                current_cell_x <= step_cell_x;
+                                state <= REQUEST_CELL;
+                            end else if (intersection_t_diff > 0) begin
+                                current_cell_y <= step_cell_y;
+                                state <= REQUEST_CELL;
+                            end else if (intersection_t_diff < 0) begin
+                                current_cell_x <= step_cell_x;
+                                state <= REQUEST_CELL;
+                            end else begin
+                                // t to x intersection == t to y intersection, so we go exactly
+                                // through a corner. That's fine as long as one of the two cells
+                                // next to it is free, like in the CPU raytracer, so we need to look
+                                // at one of them first to know which way to go.
+                                state <= REQUEST_CORNER;
                             end
                         end
                     end
                 end
+                REQUEST_CORNER: begin
+                    if (grid_bus.ready_for_input) begin
+                        grid_bus.cell_x <= step_cell_x;
+                        grid_bus.cell_y <= current_cell_y;
+                        grid_bus.input_valid <= '1;
+                        state <= WAIT_CORNER;
+                    end
+                end
+                WAIT_CORNER: begin
+                    if (grid_bus.ready_for_input) begin
+                        grid_bus.input_valid <= '0;
+                    end
+
+                    // Go around the x neighbour if it's occupied. If the y neighbour is too, we'll
+                    // find out when we read it next.
+                    if (grid_bus.output_valid) begin
+                        if (grid_bus.read_occupied)
+                            current_cell_y <= step_cell_y;
+                        else
+                            current_cell_x <= step_cell_x;
+
+                        state <= REQUEST_CELL;
+                    end
+                end
+                default: state <= IDLE;
             endcase
         end
     end
@@ -0,0 +1,99 @@
+`ifndef GRID_LOADER_SV
+`define GRID_LOADER_SV
+
+`include "membus.sv"
+
+// Bulk loads the whole occupancy grid from a stream of bytes (usually from uart_rx), writing whole
+// words straight into the grid memory instead of going one cell at a time through occupancy_grid.
+//
+// After start, the bytes are every memory word in address order, each least significant byte
+// first, in the layout occupancy_grid uses (see rrt/src/fpga/loader.rs). done goes high once the
+// last word has been written, and stays high until the next start. Bytes that come in while we
+// aren't loading are ignored.
+//
+// Each word is written while the next one is coming in, so the memory has to grant us access
+// within one word's worth of bytes, which is a long time at UART speeds.
+module grid_loader #(
+    parameter GRID_WIDTH_LOG2,
+    parameter GRID_HEIGHT_LOG2
+) (
+    input logic clk,
+    input logic rst_n,
+
+    input logic start,
+    output logic done,
+
+    input logic [7:0] data,
+    input logic data_valid,
+
+    // DATA_WIDTH must be a multiple of 8
+    memory_bus.client mem
+);
+    localparam DATA_WIDTH = mem.DATA_WIDTH;
+    localparam ADDR_WIDTH = mem.ADDR_WIDTH;
+    localparam BYTES_PER_WORD = DATA_WIDTH / 8;
+    localparam NUM_WORDS = (1 << (GRID_WIDTH_LOG2 + GRID_HEIGHT_LOG2)) / DATA_WIDTH;
+    localparam BYTE_COUNT_BITS = BYTES_PER_WORD > 1 ? $clog2(BYTES_PER_WORD) : 1;
+    // One more than the address, so it can count past the last word
+    localparam WORD_COUNT_BITS = ADDR_WIDTH + 1;
+
+    logic loading;
+    logic writing_last;
+    logic [DATA_WIDTH-1:0] word;
+    logic [DATA_WIDTH-1:0] next_word;
+    logic [BYTE_COUNT_BITS-1:0] byte_count;
+    logic [WORD_COUNT_BITS-1:0] next_address;
+
+    // New bytes go in at the top, so after a whole word the first byte is at the bottom
+    assign next_word = (word >> 8) | (DATA_WIDTH'(data) << (DATA_WIDTH - 8));
+
+    always_ff @(posedge clk) begin
+        if (!rst_n) begin
+            loading <= '0;
+            writing_last <= '0;
+            done <= '0;
+            byte_count <= '0;
+            next_address <= '0;
+            mem.request <= '0;
+            mem.write_enable <= '0;
+        end else begin
+            if (mem.request && mem.grant) begin
+                mem.request <= '0;
+                mem.write_enable <= '0;
+
+                if (writing_last) begin
+                    writing_last <= '0;
+                    done <= '1;
+                end
+            end
+
+            if (start) begin
+                loading <= '1;
+                done <= '0;
+                byte_count <= '0;
+                next_address <= '0;
+            end else if (loading && data_valid) begin
+                word <= next_word;
+
+                if (byte_count == BYTE_COUNT_BITS'(BYTES_PER_WORD - 1)) begin
+                    byte_count <= '0;
+
+                    mem.address <= ADDR_WIDTH'(next_address);
+                    mem.write_data <= next_word;
+                    mem.write_enable <= '1;
+                    mem.request <= '1;
+                    next_address <= next_address + 1;
+
+                    if (next_address == WORD_COUNT_BITS'(NUM_WORDS - 1)) begin
+                        loading <= '0;
+                        writing_last <= '1;
+                    end
+                end else begin
+                    byte_count <= byte_count + 1;
+                end
+            end
+        end
+    end
+endmodule
+
+`endif
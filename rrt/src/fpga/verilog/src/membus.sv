@@ -1,9 +1,11 @@
 `ifndef MEMBUS_SV
 `define MEMBUS_SV
 
-// TODO: At some point, we probably want some bus controller that can talk to multiple clients
-// This might be good so we can have one module be responsible for fast bulk loading from serial, which
-// I don't want to shove in the occupancy_grid module
+// Clients hold request (along with address, write_data and write_enable) until they see grant at a
+// clock edge, which is when the access happens. Read data shows up on read_data the cycle after
+// that. A memory that only ever has one client, like bram, can just tie grant high.
+//
+// Use memory_arbiter to put several clients on one memory.
 interface memory_bus #(
     parameter ADDR_WIDTH,
     parameter DATA_WIDTH
@@ -12,9 +14,11 @@ interface memory_bus #(
     logic [DATA_WIDTH-1:0] read_data;
     logic [DATA_WIDTH-1:0] write_data;
     logic write_enable;
+    logic request;
+    logic grant;
 
-    modport memory (input address, write_data, write_enable, output read_data);
-    modport client (input read_data, output address, write_data, write_enable);
+    modport memory (input address, write_data, write_enable, request, output read_data, grant);
+    modport client (input read_data, grant, output address, write_data, write_enable, request);
 endinterface
 
 `endif
@@ -0,0 +1,90 @@
+`ifndef MEMORY_ARBITER_SV
+`define MEMORY_ARBITER_SV
+
+`include "membus.sv"
+
+// Puts several memory_bus clients on one memory. Every cycle, one of the clients that are requesting
+// gets its access passed through to mem, and sees grant for it. Everyone else has to keep waiting
+// with their request up, so their writes don't get lost, just delayed.
+//
+// Nothing is registered on the way through, so the read data still shows up the cycle after the
+// grant, the same as talking to the memory directly. All clients see read_data, but it's only meant
+// for whoever got the grant the cycle before.
+//
+// Each access is arbitrated on its own, so a read-modify-write from one client can have another
+// client's access land in the middle of it.
+module memory_arbiter #(
+    parameter NUM_CLIENTS,
+    // 1 for round robin, 0 for fixed priority, where the lowest numbered client always wins
+    parameter ROUND_ROBIN
+) (
+    input logic clk,
+    input logic rst_n,
+
+    memory_bus.memory clients [NUM_CLIENTS],
+
+    memory_bus.client mem
+);
+    localparam DATA_WIDTH = mem.DATA_WIDTH;
+    localparam ADDR_WIDTH = mem.ADDR_WIDTH;
+    localparam CLIENT_BITS = NUM_CLIENTS > 1 ? $clog2(NUM_CLIENTS) : 1;
+
+    // Interface arrays can only be indexed by constants, so pull everything out into plain arrays
+    logic [NUM_CLIENTS-1:0] request;
+    logic [NUM_CLIENTS-1:0] write_enable;
+    logic [NUM_CLIENTS-1:0] grant;
+    logic [ADDR_WIDTH-1:0] address [NUM_CLIENTS];
+    logic [DATA_WIDTH-1:0] write_data [NUM_CLIENTS];
+
+    genvar i;
+    generate
+        for (i = 0; i < NUM_CLIENTS; i++) begin : g_clients
+            assign request[i] = clients[i].request;
+            assign write_enable[i] = clients[i].write_enable;
+            assign address[i] = clients[i].address;
+            assign write_data[i] = clients[i].write_data;
+
+            assign clients[i].grant = grant[i];
+            assign clients[i].read_data = mem.read_data;
+        end
+    endgenerate
+
+    // The search for a requesting client starts here. For round robin, it's one past whoever got
+    // the last access, so everyone gets a turn before anyone gets a second one.
+    logic [CLIENT_BITS-1:0] first;
+    logic [CLIENT_BITS-1:0] selected;
+    logic any_request;
+
+    always_comb begin
+        int index;
+
+        any_request = |request;
+
+        // Go backwards so the client closest to first is the one left in selected
+        selected = '0;
+        for (int offset = NUM_CLIENTS - 1; offset >= 0; offset--) begin
+            index = (int'(first) + offset) % NUM_CLIENTS;
+            if (request[index]) begin
+                selected = CLIENT_BITS'(index);
+            end
+        end
+
+        mem.request = any_request;
+        mem.address = address[selected];
+        mem.write_data = write_data[selected];
+        mem.write_enable = any_request && write_enable[selected];
+
+        grant = '0;
+        grant[selected] = any_request && mem.grant;
+    end
+
+    always_ff @(posedge clk) begin
+        if (!rst_n) begin
+            first <= '0;
+        end else if (ROUND_ROBIN && any_request && mem.grant) begin
+            first <= selected == CLIENT_BITS'(NUM_CLIENTS - 1) ? '0 : selected + 1;
+        end
+    end
+endmodule
+
+`endif
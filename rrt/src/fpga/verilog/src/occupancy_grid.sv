@@ -44,6 +44,19 @@ interface occupancy_grid_bus #(
     );
 endinterface
 
+// Takes a new request every cycle. A read's result shows up on read_occupied, with output_valid
+// high for just that cycle, two cycles after the cycle the read was accepted. Writes don't get a
+// response.
+//
+// A request is accepted on a cycle where input_valid and ready_for_input are both high, and
+// clients have to hold it until then. ready_for_input can depend on the request, so it's only
+// meaningful once the request is up.
+//
+// Writes are a read-modify-write of a whole memory word. The modified word waits in a one word
+// buffer until the memory is free, and reads of that word get it from there instead, so every read
+// sees every write accepted before it. Reads can go every cycle, and so can writes to the word
+// that's already buffered, but a write to any other word stalls until the buffer is written out.
+// When the memory is shared, a write out that didn't get it holds off new requests until it does.
 module occupancy_grid #(
     parameter GRID_WIDTH_LOG2,
     parameter GRID_HEIGHT_LOG2
@@ -59,105 +72,114 @@ module occupancy_grid #(
     localparam DATA_WIDTH = mem.DATA_WIDTH;
     localparam ADDR_WIDTH = mem.ADDR_WIDTH;
     localparam DATA_WIDTH_LOG2 = $clog2(DATA_WIDTH);
-    
+
     // Address calculation
     logic [GRID_WIDTH_LOG2 + GRID_HEIGHT_LOG2 - 1:0] linear_address;
     assign linear_address = {bus.cell_y, bus.cell_x};
 
     logic [ADDR_WIDTH-1:0] req_word_address;
     logic [DATA_WIDTH_LOG2-1:0] req_bit_off;
-    
+
     // Synthesis should handle optimization for power-of-2 widths
     assign req_word_address = ADDR_WIDTH'(linear_address / DATA_WIDTH);
     assign req_bit_off = DATA_WIDTH_LOG2'(linear_address % DATA_WIDTH);
 
-    logic [DATA_WIDTH_LOG2-1:0] req_bit_off_reg;
-    logic write_occupied_reg;
-    logic write_enable_reg;
-
-    typedef enum logic [1:0] {
-        START_READ,
-        WAIT_READ,
-        FINISH_READ,
-        WRITE_BACK
-    } state_t;
+    // The request accepted last cycle, whose word is on mem.read_data now
+    logic stage_valid;
+    logic stage_write_enable;
+    logic stage_write_occupied;
+    logic [ADDR_WIDTH-1:0] stage_word_address;
+    logic [DATA_WIDTH_LOG2-1:0] stage_bit_off;
+
+    // The last word written, until it makes it to memory
+    logic pending_valid;
+    logic [ADDR_WIDTH-1:0] pending_address;
+    logic [DATA_WIDTH-1:0] pending_data;
+
+    // The stage's word, including everything written to it so far
+    logic [DATA_WIDTH-1:0] stage_word;
+    logic [DATA_WIDTH-1:0] stage_written_word;
+    always_comb begin
+        stage_word = pending_valid && pending_address == stage_word_address ? pending_data
+                                                                             : mem.read_data;
+
+        stage_written_word = stage_word;
+        stage_written_word[stage_bit_off] = stage_write_occupied;
+    end
 
-    state_t state;
+    // A write needs the buffer at the end of next cycle, so the buffer can't be holding a different
+    // word by then. The stage is checked as well, since a write in it takes over the buffer.
+    logic write_blocked;
+    assign write_blocked = bus.write_enable
+                        && ((pending_valid && pending_address != req_word_address)
+                            || (stage_valid && stage_write_enable
+                                && stage_word_address != req_word_address));
+
+    // Requests get the memory first. When there isn't one, write out the buffer, unless a write in
+    // the stage is about to change it. Once a write back is up it has to stay up until it's
+    // granted, like any other memory request, so requests wait for it.
+    logic write_back_waiting;
+    logic take_request;
+    logic write_back;
+    always_comb begin
+        take_request = bus.input_valid && !write_blocked && !write_back_waiting;
+        write_back = !take_request && pending_valid && !(stage_valid && stage_write_enable);
+
+        mem.request = take_request || write_back;
+        mem.address = take_request ? req_word_address : pending_address;
+        mem.write_data = pending_data;
+        mem.write_enable = write_back;
+
+        bus.ready_for_input = !write_blocked && !write_back_waiting
+                           && (!bus.input_valid || mem.grant);
+    end
 
-    // TODO: I think the latency here can be lower?
     always_ff @(posedge clk) begin
         if (!rst_n) begin
-            state <= START_READ;
-            mem.write_enable <= '0;
-            mem.address <= '0;
-            mem.write_data <= '0;
-            req_bit_off_reg <= '0;
-            write_occupied_reg <= '0;
-            write_enable_reg <= '0;
+            stage_valid <= '0;
+            pending_valid <= '0;
+            write_back_waiting <= '0;
             bus.output_valid <= '0;
-            bus.ready_for_input <= '1;
         end else begin
-            case (state)
-                START_READ: begin
-                    if (bus.input_valid) begin
-                        mem.address <= req_word_address;
-                        mem.write_enable <= '0;
-
-                        write_enable_reg <= bus.write_enable;
-                        req_bit_off_reg <= req_bit_off;
-                        write_occupied_reg <= bus.write_occupied;
-
-                        bus.output_valid <= '0;
-                        bus.ready_for_input <= '0;
-
-                        state <= WAIT_READ;
-                    end else begin
-                        bus.ready_for_input <= '1;
-
-                        state <= START_READ;
-                    end
-                end
-                WAIT_READ: begin
-                    // write_enable have to wait one cycle for the read data to actually become available.
-                    if (write_enable_reg)
-                        state <= WRITE_BACK;
-                    else
-                        state <= FINISH_READ;
-                end
-                WRITE_BACK: begin
-                    mem.write_enable <= '1;
-
-                    if (write_occupied_reg)
-                        mem.write_data <= mem.read_data | (DATA_WIDTH'(1) << req_bit_off_reg);
-                    else
-                        mem.write_data <= mem.read_data & ~(DATA_WIDTH'(1) << req_bit_off_reg);
-
-                    state <= START_READ;
-                end
-                FINISH_READ: begin
-                    bus.read_occupied <= mem.read_data[req_bit_off_reg];
-
-                    bus.output_valid <= '1;
-
-                    state <= START_READ;
-                end
-            endcase
+            stage_valid <= take_request && mem.grant;
+            write_back_waiting <= write_back && !mem.grant;
+            stage_write_enable <= bus.write_enable;
+            stage_write_occupied <= bus.write_occupied;
+            stage_word_address <= req_word_address;
+            stage_bit_off <= req_bit_off;
+
+            bus.output_valid <= stage_valid && !stage_write_enable;
+            bus.read_occupied <= stage_word[stage_bit_off];
+
+            if (write_back && mem.grant) begin
+                pending_valid <= '0;
+            end
+
+            // Newer than what was just written back, if it's the same word
+            if (stage_valid && stage_write_enable) begin
+                pending_valid <= '1;
+                pending_address <= stage_word_address;
+                pending_data <= stage_written_word;
+            end
         end
     end
-
 endmodule
 
 module occupancy_grid_util #(
     parameter GRID_WIDTH_LOG2,
-    parameter GRID_HEIGHT_LOG2
+    parameter GRID_HEIGHT_LOG2,
+    parameter POINT_BITS
 ) ();
+    `POINT_TYPES(POINT_BITS)
+
+    // The grid covers the whole point range, so the cell is just the top bits of each coordinate
     function automatic void point_to_cell(
         input point_t p,
         output logic [GRID_WIDTH_LOG2-1:0] cx,
         output logic [GRID_HEIGHT_LOG2-1:0] cy
     );
-        cx = p.x[31 -: GRID_WIDTH_LOG2];
-        cy = p.y[31 -: GRID_HEIGHT_LOG2];
+        cx = p.x[POINT_BITS-1 -: GRID_WIDTH_LOG2];
+        cy = p.y[POINT_BITS-1 -: GRID_HEIGHT_LOG2];
     endfunction
 endmodule
 
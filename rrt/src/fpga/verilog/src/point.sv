@@ -1,26 +1,40 @@
 `ifndef POINT_SV
 `define POINT_SV
 
-// TODO: maybe we should refactor this so that we can just use one type everywhere.
-// And adjust the resolution accordingly so nothing really overflows.
-`define POINT_BITS 32
-`define POINT_MULT_BITS 64
+// A point is a pair of unsigned integers covering the whole grid. The collision checking can be
+// built with narrower ones, like 16 bit points on smaller FPGAs, but the planner as a whole can't.
+//
+// directed_energy_weapon, occupancy_grid_util and collision_cluster take the width as a POINT_BITS
+// parameter, and put `POINT_TYPES(POINT_BITS) in their body to get point_t, point_diff_t and
+// point_sub for it. Their ports can't see those yet, so they use `POINT_T(POINT_BITS) instead,
+// which is the same layout.
+//
+// Everything else (steer, sampler, rrt_top, tree_readback, rrt_axi_lite and rrt_host_top) only
+// does 32 bit points, through the `POINT_BITS and point_t below, and so does FixedPoint on the host.
+// A 16 bit planner needs the parameter threaded through all of those first.
+`define POINT_T(bits) struct packed { \
+    logic [(bits)-1:0] x; \
+    logic [(bits)-1:0] y; \
+}
+
+`define POINT_DIFF_T(bits) struct packed { \
+    logic signed [(bits):0] x; \
+    logic signed [(bits):0] y; \
+}
 
-typedef struct packed {
-    logic [`POINT_BITS-1:0] x;
-    logic [`POINT_BITS-1:0] y;
-} point_t;
+`define POINT_TYPES(bits) \
+    typedef `POINT_T(bits) point_t; \
+    typedef `POINT_DIFF_T(bits) point_diff_t; \
+    \
+    function automatic point_diff_t point_sub (input point_t a, b); \
+        point_sub.x = signed'({1'b0, a.x}) - signed'({1'b0, b.x}); \
+        point_sub.y = signed'({1'b0, a.y}) - signed'({1'b0, b.y}); \
+    endfunction
 
-typedef struct packed {
-    logic signed [`POINT_BITS:0] x;
-    logic signed [`POINT_BITS:0] y;
-} point_diff_t;
+// For the modules that only do 32 bit points
+`define POINT_BITS 32
+`define POINT_MULT_BITS 64
 
-function automatic point_diff_t point_sub (input point_t a, b);
-	begin
-        point_sub.x = signed'({1'b0, a.x}) - signed'({1'b0, b.x});
-        point_sub.y = signed'({1'b0, a.y}) - signed'({1'b0, b.y});
-	end
-endfunction
+`POINT_TYPES(`POINT_BITS)
 
 `endif
@@ -1,3 +1,10 @@
+`ifndef PRNG_SV
+`define PRNG_SV
+
+// Xorshift never leaves 0, so a seed of 0 gets swapped for this. Matches ZERO_SEED_REPLACEMENT in
+// rrt/src/shared/prng.rs.
+`define PRNG_ZERO_SEED_REPLACEMENT 64'h9e3779b97f4a7c15
+
 module prng64 (
     input clk,
     input rst_n,
@@ -7,7 +14,7 @@ module prng64 (
 );
     always_ff @(posedge clk) begin
         if (!rst_n) begin
-            out <= seed;
+            out <= seed != '0 ? seed : `PRNG_ZERO_SEED_REPLACEMENT;
         end else if (enable) begin
             // Algorithm source: https://en.wikipedia.org/wiki/Xorshift#Example_implementation
             logic [63:0] x0;
@@ -24,3 +31,104 @@ module prng64 (
         end
     end
 endmodule
+
+// xoshiro256** (https://prng.di.unimi.it/xoshiro256starstar.c), which has a much longer period than
+// prng64 and can be split into streams. Like prng64, out is the current word, and the next one shows
+// up the cycle after enable is high. Matches Xoshiro256StarStar in rrt/src/shared/prng.rs.
+//
+// The state is filled in from the seed with splitmix64, so any seed works, including 0. Instances
+// with the same seed but a different STREAM get streams that are 2^128 words apart, which is what
+// you want for several samplers running at once. Getting to stream n takes 256 * n cycles after
+// reset, and out isn't valid (and enable is ignored) until ready goes high.
+module xoshiro256ss #(
+    parameter STREAM
+) (
+    input logic clk,
+    input logic rst_n,
+    input logic enable,
+    input logic [63:0] seed,
+    output logic [63:0] out,
+    output logic ready
+);
+    // Bit i of this says whether to fold in the state after i steps, when jumping
+    localparam logic [255:0] JUMP = {
+        64'h39abdc4529b1661c,
+        64'ha9582618e03fc9aa,
+        64'hd5a61266f0c9392c,
+        64'h180ec6d33cfd0aba
+    };
+
+    localparam STREAM_BITS = STREAM > 0 ? $clog2(STREAM + 1) : 1;
+
+    function automatic logic [63:0] splitmix64(input logic [63:0] counter);
+        logic [63:0] z;
+        z = counter;
+        z = (z ^ (z >> 30)) * 64'hbf58476d1ce4e5b9;
+        z = (z ^ (z >> 27)) * 64'h94d049bb133111eb;
+        return z ^ (z >> 31);
+    endfunction
+
+    function automatic logic [63:0] rotl(input logic [63:0] x, input int k);
+        return (x << k) | (x >> (64 - k));
+    endfunction
+
+    logic [63:0] s [4];
+    logic [63:0] next_s [4];
+
+    always_comb begin
+        logic [63:0] t;
+
+        out = rotl(s[1] * 5, 7) * 9;
+
+        t = s[1] << 17;
+        next_s = s;
+        next_s[2] = s[2] ^ s[0];
+        next_s[3] = s[3] ^ s[1];
+        next_s[1] = s[1] ^ next_s[2];
+        next_s[0] = s[0] ^ next_s[3];
+        next_s[2] = next_s[2] ^ t;
+        next_s[3] = rotl(next_s[3], 45);
+    end
+
+    // Jumping steps through 256 states, and xors together the ones JUMP picks out
+    logic [STREAM_BITS-1:0] jumps_left;
+    logic [7:0] jump_bit;
+    logic [63:0] jump_acc [4];
+    logic [63:0] folded [4];
+
+    always_comb begin
+        for (int i = 0; i < 4; i++) begin
+            folded[i] = JUMP[jump_bit] ? jump_acc[i] ^ s[i] : jump_acc[i];
+        end
+    end
+
+    assign ready = jumps_left == '0;
+
+    always_ff @(posedge clk) begin
+        if (!rst_n) begin
+            for (int i = 0; i < 4; i++) begin
+                s[i] <= splitmix64(seed + 64'h9e3779b97f4a7c15 * 64'(i + 1));
+                jump_acc[i] <= '0;
+            end
+            jumps_left <= STREAM_BITS'(STREAM);
+            jump_bit <= '0;
+        end else if (!ready) begin
+            jump_bit <= jump_bit + 1;
+
+            if (jump_bit == 8'd255) begin
+                s <= folded;
+                for (int i = 0; i < 4; i++) begin
+                    jump_acc[i] <= '0;
+                end
+                jumps_left <= jumps_left - 1;
+            end else begin
+                s <= next_s;
+                jump_acc <= folded;
+            end
+        end else if (enable) begin
+            s <= next_s;
+        end
+    end
+endmodule
+
+`endif
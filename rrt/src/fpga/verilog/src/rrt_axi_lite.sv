@@ -0,0 +1,338 @@
+`ifndef RRT_AXI_LITE_SV
+`define RRT_AXI_LITE_SV
+
+`include "bram.sv"
+`include "membus.sv"
+`include "rrt_top.sv"
+
+// rrt_top behind an AXI4-Lite slave, along with its grid and tree memories, so it can sit on a SoC
+// bus. Everything is 32 bits wide, and the top two address bits pick what's being accessed:
+//
+// 0: Registers, one per word:
+//     0x00 CONTROL     Write 1 to bit 0 to start a run. Reads as 0.
+//     0x04 STATUS      Bit 0 is busy, bit 1 is done, bit 2 is found. Read only.
+//     0x08 NODE_COUNT  Read only, like GOAL_NODE. Both are for the last run, once it's done.
+//     0x0c GOAL_NODE
+//     0x10 START_X     0x14 START_Y
+//     0x18 GOAL_X      0x1c GOAL_Y
+//     0x20 MIN_X       0x24 MIN_Y
+//     0x28 MAX_X       0x2c MAX_Y
+//     0x30 MOVE_DIST
+//     0x34 SQ_DIST_TOL_LO  0x38 SQ_DIST_TOL_HI
+//     0x3c GOAL_BIAS
+//     0x40 SEED_LO     0x44 SEED_HI
+//     0x48 NUM_POINTS
+//    These are rrt_top's inputs, and get used when a run starts. Anything else reads as 0.
+// 1: The grid memory, one grid word per bus word, laid out the way occupancy_grid reads it. Writes
+//    replace the whole word, whatever the strobes say.
+// 2: The tree memory, four bus words per node: the point's y, its x, its parent, and a word that
+//    reads as 0. Read only.
+//
+// The memories are the core's while it's running, so accessing them while busy is set gets SLVERR
+// and does nothing, and so does writing to the tree, or starting a run while one is going.
+// Registers can be written at any time, but changes only get picked up by the next run.
+module rrt_axi_lite #(
+    parameter GRID_WIDTH_LOG2,
+    parameter GRID_HEIGHT_LOG2,
+    // At most 32
+    parameter GRID_DATA_WIDTH,
+    parameter GRID_ADDR_WIDTH,
+    parameter TREE_ADDR_WIDTH,
+    parameter STEER_ITERATIONS,
+    // Has to leave room for the biggest region, which is usually the tree at 2^(TREE_ADDR_WIDTH+4)
+    // bytes, plus the two bits that pick the region
+    parameter AXI_ADDR_WIDTH
+) (
+    input logic clk,
+    input logic rst_n,
+
+    input logic [AXI_ADDR_WIDTH-1:0] s_axi_awaddr,
+    input logic [2:0] s_axi_awprot,
+    input logic s_axi_awvalid,
+    output logic s_axi_awready,
+
+    input logic [31:0] s_axi_wdata,
+    input logic [3:0] s_axi_wstrb,
+    input logic s_axi_wvalid,
+    output logic s_axi_wready,
+
+    output logic [1:0] s_axi_bresp,
+    output logic s_axi_bvalid,
+    input logic s_axi_bready,
+
+    input logic [AXI_ADDR_WIDTH-1:0] s_axi_araddr,
+    input logic [2:0] s_axi_arprot,
+    input logic s_axi_arvalid,
+    output logic s_axi_arready,
+
+    output logic [31:0] s_axi_rdata,
+    output logic [1:0] s_axi_rresp,
+    output logic s_axi_rvalid,
+    input logic s_axi_rready
+);
+    localparam TREE_DATA_WIDTH = TREE_ADDR_WIDTH + 2 * `POINT_BITS;
+    // Word index within a region
+    localparam INDEX_BITS = AXI_ADDR_WIDTH - 4;
+
+    localparam logic [1:0] RESP_OKAY = 2'b00;
+    localparam logic [1:0] RESP_SLVERR = 2'b10;
+
+    localparam logic [1:0] REGION_REGS = 2'd0;
+    localparam logic [1:0] REGION_GRID = 2'd1;
+    localparam logic [1:0] REGION_TREE = 2'd2;
+
+    typedef enum logic [4:0] {
+        REG_CONTROL,
+        REG_STATUS,
+        REG_NODE_COUNT,
+        REG_GOAL_NODE,
+        REG_START_X,
+        REG_START_Y,
+        REG_GOAL_X,
+        REG_GOAL_Y,
+        REG_MIN_X,
+        REG_MIN_Y,
+        REG_MAX_X,
+        REG_MAX_Y,
+        REG_MOVE_DIST,
+        REG_SQ_DIST_TOL_LO,
+        REG_SQ_DIST_TOL_HI,
+        REG_GOAL_BIAS,
+        REG_SEED_LO,
+        REG_SEED_HI,
+        REG_NUM_POINTS,
+        REG_COUNT
+    } reg_t;
+
+    // Only the ones from REG_START_X on are stored
+    logic [31:0] regs [REG_COUNT];
+
+    logic busy;
+    logic start_pulse;
+
+    logic core_done;
+    logic core_found;
+    logic [TREE_ADDR_WIDTH:0] node_count;
+    logic [TREE_ADDR_WIDTH-1:0] goal_node;
+
+    // Decoding
+
+    logic [1:0] write_region;
+    logic [INDEX_BITS-1:0] write_index;
+    logic [1:0] read_region;
+    logic [INDEX_BITS-1:0] read_index;
+    assign write_region = s_axi_awaddr[AXI_ADDR_WIDTH-1 -: 2];
+    assign write_index = s_axi_awaddr[AXI_ADDR_WIDTH-3:2];
+    assign read_region = s_axi_araddr[AXI_ADDR_WIDTH-1 -: 2];
+    assign read_index = s_axi_araddr[AXI_ADDR_WIDTH-3:2];
+
+    // A write goes once both its address and data are up, and the last response has been taken.
+    // Reads wait for writes, so the two never want the memories in the same cycle.
+    logic write_accept;
+    logic read_accept;
+    logic read_pending;
+    assign write_accept = s_axi_awvalid && s_axi_wvalid && !s_axi_bvalid;
+    assign read_accept = s_axi_arvalid && s_axi_arready;
+
+    assign s_axi_awready = write_accept;
+    assign s_axi_wready = write_accept;
+    assign s_axi_arready = !write_accept && !s_axi_rvalid && !read_pending;
+
+    // Memories
+
+    memory_bus #(.ADDR_WIDTH(GRID_ADDR_WIDTH), .DATA_WIDTH(GRID_DATA_WIDTH)) grid_mem ();
+    memory_bus #(.ADDR_WIDTH(GRID_ADDR_WIDTH), .DATA_WIDTH(GRID_DATA_WIDTH)) core_grid_mem ();
+    memory_bus #(.ADDR_WIDTH(TREE_ADDR_WIDTH), .DATA_WIDTH(TREE_DATA_WIDTH)) tree_mem ();
+    memory_bus #(.ADDR_WIDTH(TREE_ADDR_WIDTH), .DATA_WIDTH(TREE_DATA_WIDTH)) core_tree_mem ();
+
+    bram #(.ADDR_WIDTH(GRID_ADDR_WIDTH), .DATA_WIDTH(GRID_DATA_WIDTH)) grid_bram (
+        .clk(clk),
+        .bus(grid_mem.memory)
+    );
+
+    bram #(.ADDR_WIDTH(TREE_ADDR_WIDTH), .DATA_WIDTH(TREE_DATA_WIDTH)) tree_bram (
+        .clk(clk),
+        .bus(tree_mem.memory)
+    );
+
+    always_comb begin
+        if (busy) begin
+            grid_mem.address = core_grid_mem.address;
+            grid_mem.write_data = core_grid_mem.write_data;
+            grid_mem.write_enable = core_grid_mem.write_enable;
+            grid_mem.request = core_grid_mem.request;
+
+            tree_mem.address = core_tree_mem.address;
+            tree_mem.write_data = core_tree_mem.write_data;
+            tree_mem.write_enable = core_tree_mem.write_enable;
+            tree_mem.request = core_tree_mem.request;
+        end else begin
+            grid_mem.address = GRID_ADDR_WIDTH'(write_accept ? write_index : read_index);
+            grid_mem.write_data = GRID_DATA_WIDTH'(s_axi_wdata);
+            grid_mem.write_enable = write_accept && write_region == REGION_GRID;
+            grid_mem.request = '1;
+
+            // Four words per node
+            tree_mem.address = TREE_ADDR_WIDTH'(read_index >> 2);
+            tree_mem.write_data = '0;
+            tree_mem.write_enable = '0;
+            tree_mem.request = '1;
+        end
+
+        core_grid_mem.read_data = grid_mem.read_data;
+        core_tree_mem.read_data = tree_mem.read_data;
+        core_grid_mem.grant = busy && grid_mem.grant;
+        core_tree_mem.grant = busy && tree_mem.grant;
+    end
+
+    // Registers
+
+    function automatic logic [31:0] read_register(input logic [INDEX_BITS-1:0] index);
+        if (index >= INDEX_BITS'(REG_COUNT)) begin
+            return '0;
+        end
+
+        case (reg_t'(index))
+            REG_CONTROL: return '0;
+            REG_STATUS: return {29'b0, core_found, core_done && !busy, busy};
+            REG_NODE_COUNT: return 32'(node_count);
+            REG_GOAL_NODE: return 32'(goal_node);
+            default: return regs[index];
+        endcase
+    endfunction
+
+    // Which word of a node a tree read wants, saved for when the data comes back
+    logic [1:0] read_slot;
+    logic read_from_tree;
+
+    always_ff @(posedge clk) begin
+        if (!rst_n) begin
+            busy <= '0;
+            start_pulse <= '0;
+            read_pending <= '0;
+            s_axi_bvalid <= '0;
+            s_axi_rvalid <= '0;
+            for (int i = 0; i < REG_COUNT; i++) begin
+                regs[i] <= '0;
+            end
+        end else begin
+            start_pulse <= '0;
+
+            // done is left over from the last run until the core takes the start
+            if (busy && !start_pulse && core_done) begin
+                busy <= '0;
+            end
+
+            if (s_axi_bvalid && s_axi_bready) begin
+                s_axi_bvalid <= '0;
+            end
+
+            if (write_accept) begin
+                s_axi_bvalid <= '1;
+                s_axi_bresp <= RESP_OKAY;
+
+                case (write_region)
+                    REGION_REGS: begin
+                        if (write_index == INDEX_BITS'(REG_CONTROL)) begin
+                            if (s_axi_wstrb[0] && s_axi_wdata[0]) begin
+                                if (busy) begin
+                                    s_axi_bresp <= RESP_SLVERR;
+                                end else begin
+                                    busy <= '1;
+                                    start_pulse <= '1;
+                                end
+                            end
+                        end else if (write_index >= INDEX_BITS'(REG_START_X)
+                                     && write_index < INDEX_BITS'(REG_COUNT)) begin
+                            for (int i = 0; i < 4; i++) begin
+                                if (s_axi_wstrb[i]) begin
+                                    regs[write_index][8*i +: 8] <= s_axi_wdata[8*i +: 8];
+                                end
+                            end
+                        end
+                    end
+                    REGION_GRID: begin
+                        // The write itself went straight to the memory
+                        if (busy) begin
+                            s_axi_bresp <= RESP_SLVERR;
+                        end
+                    end
+                    default: s_axi_bresp <= RESP_SLVERR;
+                endcase
+            end
+
+            if (s_axi_rvalid && s_axi_rready) begin
+                s_axi_rvalid <= '0;
+            end
+
+            // The memories take a cycle to read, so those reads answer a cycle later
+            if (read_pending) begin
+                read_pending <= '0;
+                s_axi_rvalid <= '1;
+                s_axi_rresp <= RESP_OKAY;
+
+                if (!read_from_tree) begin
+                    s_axi_rdata <= 32'(grid_mem.read_data);
+                end else begin
+                    case (read_slot)
+                        2'd0: s_axi_rdata <= tree_mem.read_data[0 +: 32];
+                        2'd1: s_axi_rdata <= tree_mem.read_data[32 +: 32];
+                        2'd2: s_axi_rdata <= 32'(tree_mem.read_data[TREE_DATA_WIDTH-1:64]);
+                        default: s_axi_rdata <= '0;
+                    endcase
+                end
+            end
+
+            if (read_accept) begin
+                read_slot <= read_index[1:0];
+                read_from_tree <= read_region == REGION_TREE;
+
+                if (read_region == REGION_REGS) begin
+                    s_axi_rvalid <= '1;
+                    s_axi_rresp <= RESP_OKAY;
+                    s_axi_rdata <= read_register(read_index);
+                end else if (read_region == REGION_GRID || read_region == REGION_TREE) begin
+                    if (busy) begin
+                        s_axi_rvalid <= '1;
+                        s_axi_rresp <= RESP_SLVERR;
+                        s_axi_rdata <= '0;
+                    end else begin
+                        read_pending <= '1;
+                    end
+                end else begin
+                    s_axi_rvalid <= '1;
+                    s_axi_rresp <= RESP_SLVERR;
+                    s_axi_rdata <= '0;
+                end
+            end
+        end
+    end
+
+    rrt_top #(
+        .GRID_WIDTH_LOG2(GRID_WIDTH_LOG2),
+        .GRID_HEIGHT_LOG2(GRID_HEIGHT_LOG2),
+        .TREE_ADDR_WIDTH(TREE_ADDR_WIDTH),
+        .STEER_ITERATIONS(STEER_ITERATIONS)
+    ) core (
+        .clk(clk),
+        .rst_n(rst_n),
+        .start({regs[REG_START_X], regs[REG_START_Y]}),
+        .goal({regs[REG_GOAL_X], regs[REG_GOAL_Y]}),
+        .min_bound({regs[REG_MIN_X], regs[REG_MIN_Y]}),
+        .max_bound({regs[REG_MAX_X], regs[REG_MAX_Y]}),
+        .move_dist(regs[REG_MOVE_DIST]),
+        .sq_dist_tol({regs[REG_SQ_DIST_TOL_HI], regs[REG_SQ_DIST_TOL_LO]}),
+        .goal_bias(regs[REG_GOAL_BIAS][15:0]),
+        .seed({regs[REG_SEED_HI], regs[REG_SEED_LO]}),
+        .num_points(regs[REG_NUM_POINTS][TREE_ADDR_WIDTH:0]),
+        .input_valid(start_pulse),
+        .done(core_done),
+        .found(core_found),
+        .node_count(node_count),
+        .goal_node(goal_node),
+        .grid_mem(core_grid_mem.client),
+        .tree_mem(core_tree_mem.client)
+    );
+endmodule
+
+`endif
@@ -0,0 +1,127 @@
+`ifndef RRT_HOST_TOP_SV
+`define RRT_HOST_TOP_SV
+
+`include "bram.sv"
+`include "point.sv"
+`include "rrt_top.sv"
+
+// rrt_top with its grid and tree memories, and a plain port for the host to get at them between
+// runs. This is what VerilatedRRT runs, and what the rrt_top tests drive.
+//
+// While host_select is high, the host has both memories and the core gets no grants, so it should
+// only be raised while the core is idle. Grid writes take effect on the clock edge, and a tree read
+// shows up on host_tree_point and host_tree_parent the cycle after host_tree_address is set.
+module rrt_host_top #(
+    parameter GRID_WIDTH_LOG2,
+    parameter GRID_HEIGHT_LOG2,
+    parameter GRID_DATA_WIDTH,
+    parameter GRID_ADDR_WIDTH,
+    parameter TREE_ADDR_WIDTH,
+    parameter STEER_ITERATIONS
+) (
+    input logic clk,
+    input logic rst_n,
+
+    input point_t start,
+    input point_t goal,
+    input point_t min_bound,
+    input point_t max_bound,
+    input logic [`POINT_BITS-1:0] move_dist,
+    input logic [`POINT_MULT_BITS-1:0] sq_dist_tol,
+    input logic [15:0] goal_bias,
+    input logic [63:0] seed,
+    input logic [TREE_ADDR_WIDTH:0] num_points,
+
+    input logic input_valid,
+    output logic done,
+
+    output logic found,
+    output logic [TREE_ADDR_WIDTH:0] node_count,
+    output logic [TREE_ADDR_WIDTH-1:0] goal_node,
+
+    // While host_select is high, the host has the memories instead of the core
+    input logic host_select,
+    input logic [GRID_ADDR_WIDTH-1:0] host_grid_address,
+    input logic [GRID_DATA_WIDTH-1:0] host_grid_write_data,
+    input logic host_grid_write_enable,
+    input logic [TREE_ADDR_WIDTH-1:0] host_tree_address,
+    output point_t host_tree_point,
+    output logic [TREE_ADDR_WIDTH-1:0] host_tree_parent
+);
+    localparam TREE_DATA_WIDTH = TREE_ADDR_WIDTH + 2 * `POINT_BITS;
+
+    memory_bus #(.ADDR_WIDTH(GRID_ADDR_WIDTH), .DATA_WIDTH(GRID_DATA_WIDTH)) grid_mem ();
+    memory_bus #(.ADDR_WIDTH(GRID_ADDR_WIDTH), .DATA_WIDTH(GRID_DATA_WIDTH)) core_grid_mem ();
+    memory_bus #(.ADDR_WIDTH(TREE_ADDR_WIDTH), .DATA_WIDTH(TREE_DATA_WIDTH)) tree_mem ();
+    memory_bus #(.ADDR_WIDTH(TREE_ADDR_WIDTH), .DATA_WIDTH(TREE_DATA_WIDTH)) core_tree_mem ();
+
+    bram #(.ADDR_WIDTH(GRID_ADDR_WIDTH), .DATA_WIDTH(GRID_DATA_WIDTH)) grid_bram (
+        .clk(clk),
+        .bus(grid_mem.memory)
+    );
+
+    bram #(.ADDR_WIDTH(TREE_ADDR_WIDTH), .DATA_WIDTH(TREE_DATA_WIDTH)) tree_bram (
+        .clk(clk),
+        .bus(tree_mem.memory)
+    );
+
+    always_comb begin
+        if (host_select) begin
+            grid_mem.address = host_grid_address;
+            grid_mem.write_data = host_grid_write_data;
+            grid_mem.write_enable = host_grid_write_enable;
+            grid_mem.request = '1;
+
+            tree_mem.address = host_tree_address;
+            tree_mem.write_data = '0;
+            tree_mem.write_enable = '0;
+            tree_mem.request = '1;
+        end else begin
+            grid_mem.address = core_grid_mem.address;
+            grid_mem.write_data = core_grid_mem.write_data;
+            grid_mem.write_enable = core_grid_mem.write_enable;
+            grid_mem.request = core_grid_mem.request;
+
+            tree_mem.address = core_tree_mem.address;
+            tree_mem.write_data = core_tree_mem.write_data;
+            tree_mem.write_enable = core_tree_mem.write_enable;
+            tree_mem.request = core_tree_mem.request;
+        end
+
+        core_grid_mem.read_data = grid_mem.read_data;
+        core_tree_mem.read_data = tree_mem.read_data;
+        core_grid_mem.grant = !host_select && grid_mem.grant;
+        core_tree_mem.grant = !host_select && tree_mem.grant;
+
+        host_tree_point = tree_mem.read_data[2*`POINT_BITS-1:0];
+        host_tree_parent = tree_mem.read_data[2*`POINT_BITS +: TREE_ADDR_WIDTH];
+    end
+
+    rrt_top #(
+        .GRID_WIDTH_LOG2(GRID_WIDTH_LOG2),
+        .GRID_HEIGHT_LOG2(GRID_HEIGHT_LOG2),
+        .TREE_ADDR_WIDTH(TREE_ADDR_WIDTH),
+        .STEER_ITERATIONS(STEER_ITERATIONS)
+    ) core (
+        .clk(clk),
+        .rst_n(rst_n),
+        .start(start),
+        .goal(goal),
+        .min_bound(min_bound),
+        .max_bound(max_bound),
+        .move_dist(move_dist),
+        .sq_dist_tol(sq_dist_tol),
+        .goal_bias(goal_bias),
+        .seed(seed),
+        .num_points(num_points),
+        .input_valid(input_valid),
+        .done(done),
+        .found(found),
+        .node_count(node_count),
+        .goal_node(goal_node),
+        .grid_mem(core_grid_mem.client),
+        .tree_mem(core_tree_mem.client)
+    );
+endmodule
+
+`endif
@@ -0,0 +1,315 @@
+`ifndef RRT_TOP_SV
+`define RRT_TOP_SV
+
+`include "directed_energy_weapon.sv"
+`include "membus.sv"
+`include "occupancy_grid.sv"
+`include "point.sv"
+`include "prng.sv"
+`include "sampler.sv"
+`include "steer.sv"
+
+// Runs RRT the same way VanillaRRT does (see rrt/src/cpu/vanilla.rs), except that the nearest
+// neighbour is found with a linear scan over the tree.
+//
+// The tree lives in tree_mem: node i is stored at address i as {parent, point}. The start is node 0,
+// which is its own parent. Once done goes high, node_count nodes are valid, and if found is set,
+// goal_node is the node that got within sq_dist_tol of the goal.
+//
+// The bounds are [min_bound, max_bound), for both sampling and new points: the sampler never gives
+// back max_bound, and new points outside of the bounds are thrown away. Since both ends of every edge
+// are inside, so is the edge, so a map that doesn't fill the grid only needs the bounds cut down to
+// it (see GridMapping::bounds_to_fixed in rrt/src/fpga/fixed.rs).
+//
+// The occupancy grid is read through grid_mem, and must be loaded before starting. grid_mem can be
+// shared with other clients, but tree_mem can't, since the nearest neighbour scan needs a read every
+// cycle.
+module rrt_top #(
+    parameter GRID_WIDTH_LOG2,
+    parameter GRID_HEIGHT_LOG2,
+    // Must match tree_mem.ADDR_WIDTH, and tree_mem.DATA_WIDTH must be TREE_ADDR_WIDTH + 2 * `POINT_BITS
+    parameter TREE_ADDR_WIDTH,
+    parameter STEER_ITERATIONS
+) (
+    input logic clk,
+    input logic rst_n,
+
+    input point_t start,
+    input point_t goal,
+    input point_t min_bound,
+    input point_t max_bound,
+    input logic [`POINT_BITS-1:0] move_dist,
+    input logic [`POINT_MULT_BITS-1:0] sq_dist_tol,
+    input logic [15:0] goal_bias,
+    input logic [63:0] seed,
+    // Maximum number of nodes in the tree, including the start. Can be at most 2^TREE_ADDR_WIDTH.
+    input logic [TREE_ADDR_WIDTH:0] num_points,
+
+    input logic input_valid,
+    output logic done,
+
+    output logic found,
+    output logic [TREE_ADDR_WIDTH:0] node_count,
+    output logic [TREE_ADDR_WIDTH-1:0] goal_node,
+
+    memory_bus.client grid_mem,
+    memory_bus.client tree_mem
+);
+    // Squared distances need one more bit than the product of two coordinates
+    localparam SQ_DIST_BITS = `POINT_MULT_BITS + 1;
+
+    typedef logic [TREE_ADDR_WIDTH-1:0] node_t;
+
+    typedef enum logic [3:0] {
+        IDLE,
+        SAMPLE_START,
+        SAMPLE_WAIT,
+        NEAREST,
+        STEER_START,
+        STEER_WAIT,
+        COLLIDE_START,
+        COLLIDE_WAIT,
+        INSERT,
+        FINISH
+    } state_t;
+
+    state_t state;
+
+    function automatic logic [SQ_DIST_BITS-1:0] sq_dist(input point_t p, input point_t q);
+        point_diff_t d;
+        logic [`POINT_BITS-1:0] abs_x;
+        logic [`POINT_BITS-1:0] abs_y;
+
+        d = point_sub(p, q);
+        abs_x = `POINT_BITS'(d.x < 0 ? -d.x : d.x);
+        abs_y = `POINT_BITS'(d.y < 0 ? -d.y : d.y);
+
+        sq_dist = SQ_DIST_BITS'(abs_x) * SQ_DIST_BITS'(abs_x)
+                + SQ_DIST_BITS'(abs_y) * SQ_DIST_BITS'(abs_y);
+    endfunction
+
+    // Sampling
+
+    logic starting;
+    assign starting = state == IDLE && input_valid;
+
+    logic [63:0] random;
+    logic random_enable;
+    logic random_ready;
+
+    // Reseed at the start of every run, so the result only depends on the inputs. Stream 0 is
+    // ready straight away, but waiting on it keeps this right if the stream ever changes.
+    xoshiro256ss #(
+        .STREAM(0)
+    ) rng (
+        .clk(clk),
+        .rst_n(rst_n && !starting),
+        .enable(random_enable),
+        .seed(seed),
+        .out(random),
+        .ready(random_ready)
+    );
+
+    point_t sample;
+    logic sampler_done;
+
+    sampler sampler_inst (
+        .clk(clk),
+        .rst_n(rst_n),
+        .min_bound(min_bound),
+        .max_bound(max_bound),
+        .goal(goal),
+        .goal_bias(goal_bias),
+        .random(random),
+        .random_enable(random_enable),
+        .input_valid(state == SAMPLE_START && random_ready),
+        .done(sampler_done),
+        .sample(sample)
+    );
+
+    // Always asking, and never waiting for grant, since tree_mem is all ours
+    assign tree_mem.request = '1;
+
+    // Nearest neighbour scan. Reads are pipelined, so there's one node in flight per cycle. The
+    // address is registered and the BRAM read takes a cycle, so data shows up two cycles after we
+    // decide to read it.
+
+    logic [TREE_ADDR_WIDTH:0] scan_next;
+    logic [1:0] scan_valid;
+    node_t scan_node [2];
+
+    logic [SQ_DIST_BITS-1:0] nearest_dist;
+    node_t nearest_node;
+    point_t nearest_point;
+
+    point_t scan_point;
+    logic [SQ_DIST_BITS-1:0] scan_dist;
+    always_comb begin
+        scan_point = tree_mem.read_data[2*`POINT_BITS-1:0];
+        scan_dist = sq_dist(scan_point, sample);
+    end
+
+    // Steering
+
+    point_t new_point;
+    logic steer_done;
+    logic steer_out_of_bounds;
+
+    steer #(.ITERATIONS(STEER_ITERATIONS)) steer_inst (
+        .clk(clk),
+        .rst_n(rst_n),
+        .from(nearest_point),
+        .toward(sample),
+        .move_dist(move_dist),
+        .input_valid(state == STEER_START),
+        .done(steer_done),
+        .result(new_point),
+        .out_of_bounds(steer_out_of_bounds)
+    );
+
+    logic new_point_in_bounds;
+    assign new_point_in_bounds = !steer_out_of_bounds
+                              && min_bound.x <= new_point.x && new_point.x < max_bound.x
+                              && min_bound.y <= new_point.y && new_point.y < max_bound.y;
+
+    // Collision checking
+
+    occupancy_grid_bus #(.GRID_WIDTH_LOG2(GRID_WIDTH_LOG2), .GRID_HEIGHT_LOG2(GRID_HEIGHT_LOG2)) grid_bus ();
+
+    occupancy_grid #(.GRID_WIDTH_LOG2(GRID_WIDTH_LOG2), .GRID_HEIGHT_LOG2(GRID_HEIGHT_LOG2)) grid (
+        .clk(clk),
+        .rst_n(rst_n),
+        .bus(grid_bus.grid),
+        .mem(grid_mem)
+    );
+
+    logic dew_done;
+    logic dew_occupied;
+
+    directed_energy_weapon #(
+        .GRID_WIDTH_LOG2(GRID_WIDTH_LOG2),
+        .GRID_HEIGHT_LOG2(GRID_HEIGHT_LOG2),
+        .POINT_BITS(`POINT_BITS)
+    ) dew (
+        .clk(clk),
+        .rst_n(rst_n),
+        .a(nearest_point),
+        .b(new_point),
+        .mark('0),
+        .occupied(dew_occupied),
+        .input_valid(state == COLLIDE_START),
+        .done(dew_done),
+        .grid_bus(grid_bus.client)
+    );
+
+    // The submodules all have input_valid driven straight from our state, so they start on the
+    // cycle we're in *_START. Their done outputs stay high from the previous run until then, so we
+    // only look at them from *_WAIT onwards.
+
+    always_ff @(posedge clk) begin
+        if (!rst_n) begin
+            state <= IDLE;
+            done <= '0;
+            found <= '0;
+            node_count <= '0;
+            goal_node <= '0;
+            scan_valid <= '0;
+            tree_mem.write_enable <= '0;
+        end else begin
+            tree_mem.write_enable <= '0;
+
+            case (state)
+                IDLE: begin
+                    if (input_valid) begin
+                        // The start is the root, and its own parent
+                        tree_mem.address <= '0;
+                        tree_mem.write_data <= {node_t'(0), start};
+                        tree_mem.write_enable <= '1;
+
+                        node_count <= 1;
+                        found <= '0;
+                        done <= '0;
+
+                        state <= num_points > 1 ? SAMPLE_START : FINISH;
+                    end
+                end
+                SAMPLE_START: begin
+                    if (random_ready) begin
+                        state <= SAMPLE_WAIT;
+                    end
+                end
+                SAMPLE_WAIT: begin
+                    if (sampler_done) begin
+                        scan_next <= '0;
+                        scan_valid <= '0;
+                        nearest_dist <= '1;
+                        state <= NEAREST;
+                    end
+                end
+                NEAREST: begin
+                    if (scan_next < node_count) begin
+                        tree_mem.address <= node_t'(scan_next);
+                        scan_next <= scan_next + 1;
+                    end
+
+                    scan_valid <= {scan_valid[0], scan_next < node_count};
+                    scan_node[0] <= node_t'(scan_next);
+                    scan_node[1] <= scan_node[0];
+
+                    // Ties go to the earlier node
+                    if (scan_valid[1] && scan_dist < nearest_dist) begin
+                        nearest_dist <= scan_dist;
+                        nearest_node <= scan_node[1];
+                        nearest_point <= scan_point;
+                    end
+
+                    if (scan_next == node_count && scan_valid == '0) begin
+                        state <= STEER_START;
+                    end
+                end
+                STEER_START: begin
+                    state <= STEER_WAIT;
+                end
+                STEER_WAIT: begin
+                    if (steer_done) begin
+                        state <= new_point_in_bounds ? COLLIDE_START : SAMPLE_START;
+                    end
+                end
+                COLLIDE_START: begin
+                    state <= COLLIDE_WAIT;
+                end
+                COLLIDE_WAIT: begin
+                    if (dew_done) begin
+                        state <= dew_occupied ? SAMPLE_START : INSERT;
+                    end
+                end
+                INSERT: begin
+                    tree_mem.address <= node_t'(node_count);
+                    tree_mem.write_data <= {nearest_node, new_point};
+                    tree_mem.write_enable <= '1;
+
+                    node_count <= node_count + 1;
+
+                    if (sq_dist(new_point, goal) < SQ_DIST_BITS'(sq_dist_tol)) begin
+                        found <= '1;
+                        goal_node <= node_t'(node_count);
+                        state <= FINISH;
+                    end else if (node_count + 1 >= num_points) begin
+                        state <= FINISH;
+                    end else begin
+                        state <= SAMPLE_START;
+                    end
+                end
+                FINISH: begin
+                    // Waits for the last write to the tree to land, so it's all there once done
+                    // goes high
+                    done <= '1;
+                    state <= IDLE;
+                end
+                default: state <= IDLE;
+            endcase
+        end
+    end
+endmodule
+
+`endif
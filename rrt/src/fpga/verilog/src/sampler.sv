@@ -0,0 +1,90 @@
+`ifndef SAMPLER_SV
+`define SAMPLER_SV
+
+`include "point.sv"
+
+// Turns raw random words (e.g. from xoshiro256ss) into points uniformly distributed within
+// [min_bound, max_bound), so max_bound itself is never sampled. With probability goal_bias / 2^16,
+// the goal is sampled instead, which pulls the tree towards it.
+module sampler (
+    input logic clk,
+    input logic rst_n,
+
+    input point_t min_bound,
+    input point_t max_bound,
+    input point_t goal,
+    input logic [15:0] goal_bias,
+
+    // One word gets consumed per cycle that random_enable is high. The next word is expected on the
+    // following cycle, which is how the generators in prng.sv behave.
+    input logic [63:0] random,
+    output logic random_enable,
+
+    input logic input_valid,
+    output logic done,
+
+    output point_t sample
+);
+    typedef enum logic [1:0] {
+        IDLE,
+        GOAL_BIAS,
+        COORDS
+    } state_t;
+
+    state_t state;
+
+    // Each random word is only used once, since the bias decision and the coordinates would
+    // otherwise be correlated
+    assign random_enable = state == GOAL_BIAS || state == COORDS;
+
+    // Multiply-shift maps a 32 bit random value r onto [0, range) with (r * range) >> 32, which is
+    // uniform up to a bias of range / 2^32
+    localparam SCALED_BITS = 2 * `POINT_BITS;
+
+    logic [`POINT_BITS-1:0] range_x;
+    logic [`POINT_BITS-1:0] range_y;
+    logic [SCALED_BITS-1:0] scaled_x;
+    logic [SCALED_BITS-1:0] scaled_y;
+
+    always_comb begin
+        range_x = max_bound.x - min_bound.x;
+        range_y = max_bound.y - min_bound.y;
+
+        scaled_x = SCALED_BITS'(random[63:32]) * SCALED_BITS'(range_x);
+        scaled_y = SCALED_BITS'(random[31:0]) * SCALED_BITS'(range_y);
+    end
+
+    always_ff @(posedge clk) begin
+        if (!rst_n) begin
+            state <= IDLE;
+            done <= '0;
+        end else begin
+            case (state)
+                IDLE: begin
+                    if (input_valid) begin
+                        done <= '0;
+                        state <= goal_bias != '0 ? GOAL_BIAS : COORDS;
+                    end
+                end
+                GOAL_BIAS: begin
+                    if (random[63:48] < goal_bias) begin
+                        sample <= goal;
+                        done <= '1;
+                        state <= IDLE;
+                    end else begin
+                        state <= COORDS;
+                    end
+                end
+                COORDS: begin
+                    sample.x <= min_bound.x + `POINT_BITS'(scaled_x >> `POINT_BITS);
+                    sample.y <= min_bound.y + `POINT_BITS'(scaled_y >> `POINT_BITS);
+                    done <= '1;
+                    state <= IDLE;
+                end
+                default: state <= IDLE;
+            endcase
+        end
+    end
+endmodule
+
+`endif
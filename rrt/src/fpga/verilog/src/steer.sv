@@ -0,0 +1,175 @@
+`ifndef STEER_SV
+`define STEER_SV
+
+`include "point.sv"
+
+// Computes from + normalize(toward - from) * move_dist, which is how RRT extends the tree towards a
+// sample. There's no square root or divider, so we use two CORDICs running in lockstep:
+//
+// 1. In vectoring mode, (toward - from) gets rotated onto the x axis. We don't care about the
+//    resulting magnitude, just the direction each micro-rotation went.
+// 2. In rotation mode, (move_dist / K, 0) gets the same micro-rotations in the opposite direction,
+//    which lands on move_dist * (cos, sin) of the original angle.
+//
+// Both CORDICs see the same sequence of rotations, so no arctangent table is needed, and the CORDIC
+// gain K cancels out since the second vector is scaled down by it beforehand.
+//
+// With ITERATIONS micro-rotations, each coordinate of the result is within
+// (move_dist >> (ITERATIONS - 2)) + 2 of the exact value.
+module steer #(
+    parameter ITERATIONS
+) (
+    input logic clk,
+    input logic rst_n,
+
+    input point_t from,
+    input point_t toward,
+    input logic [`POINT_BITS-1:0] move_dist,
+
+    input logic input_valid,
+    output logic done,
+
+    output point_t result,
+    // Set if toward == from (there is no direction to move in), or if the result doesn't fit in a
+    // point_t
+    output logic out_of_bounds
+);
+    // Guard bits below the LSB so the shifts in each iteration don't throw away all the precision
+    localparam FRAC_BITS = 4;
+
+    // The vectoring vector is normalized so its largest component uses all `POINT_BITS bits. Over the
+    // iterations it can grow by K * sqrt(2) < 4, so we need two more bits plus a sign.
+    localparam VEC_BITS = `POINT_BITS + 3 + FRAC_BITS;
+    // The rotation vector never has a component bigger than move_dist, plus one bit of slack
+    localparam ROT_BITS = `POINT_BITS + 2 + FRAC_BITS;
+
+    // round(2^32 / K), where K = prod(sqrt(1 + 2^(-2i))) is the CORDIC gain. K converges quickly, so
+    // the limit is good enough for any reasonable number of iterations.
+    localparam logic [31:0] GAIN_INV = 32'd2608131496;
+
+    localparam ITER_BITS = $clog2(ITERATIONS + 1);
+    localparam SHIFT_BITS = $clog2(`POINT_BITS);
+    // Enough to hold from + offset with a sign and without overflowing
+    localparam RESULT_BITS = `POINT_BITS + 3;
+
+    typedef enum logic [1:0] {
+        IDLE,
+        ROTATING,
+        FINISH
+    } state_t;
+
+    state_t state;
+
+    logic [ITER_BITS-1:0] iteration;
+
+    logic signed [VEC_BITS-1:0] vec_x;
+    logic signed [VEC_BITS-1:0] vec_y;
+    logic signed [ROT_BITS-1:0] rot_x;
+    logic signed [ROT_BITS-1:0] rot_y;
+
+    // The CORDIC only works in the first quadrant here, so we remember the signs and apply them at
+    // the end
+    logic negate_x;
+    logic negate_y;
+    logic degenerate;
+
+    point_diff_t delta;
+    logic [`POINT_BITS-1:0] abs_delta_x;
+    logic [`POINT_BITS-1:0] abs_delta_y;
+    logic [SHIFT_BITS-1:0] norm_shift;
+    logic [63:0] scaled_move_dist;
+
+    function automatic logic [SHIFT_BITS-1:0] count_leading_zeros(
+        input logic [`POINT_BITS-1:0] value
+    );
+        count_leading_zeros = '0;
+        for (int i = 0; i < `POINT_BITS; i++) begin
+            if (value[i]) count_leading_zeros = SHIFT_BITS'(`POINT_BITS - 1 - i);
+        end
+    endfunction
+
+    always_comb begin
+        delta = point_sub(toward, from);
+
+        abs_delta_x = `POINT_BITS'(delta.x < 0 ? -delta.x : delta.x);
+        abs_delta_y = `POINT_BITS'(delta.y < 0 ? -delta.y : delta.y);
+
+        // Normalizing first means small deltas don't lose all of their precision in the shifts
+        norm_shift = count_leading_zeros(abs_delta_x | abs_delta_y);
+
+        scaled_move_dist = 64'(move_dist) * 64'(GAIN_INV);
+    end
+
+    // Rounded results of the rotation CORDIC, as offsets from `from`
+    logic signed [ROT_BITS-1:0] offset_x;
+    logic signed [ROT_BITS-1:0] offset_y;
+    logic signed [RESULT_BITS-1:0] result_x;
+    logic signed [RESULT_BITS-1:0] result_y;
+
+    always_comb begin
+        offset_x = (rot_x + ROT_BITS'(1 << (FRAC_BITS - 1))) >>> FRAC_BITS;
+        offset_y = (rot_y + ROT_BITS'(1 << (FRAC_BITS - 1))) >>> FRAC_BITS;
+
+        result_x = signed'({3'b0, from.x}) + (negate_x ? -RESULT_BITS'(offset_x) : RESULT_BITS'(offset_x));
+        result_y = signed'({3'b0, from.y}) + (negate_y ? -RESULT_BITS'(offset_y) : RESULT_BITS'(offset_y));
+    end
+
+    always_ff @(posedge clk) begin
+        if (!rst_n) begin
+            state <= IDLE;
+            done <= '0;
+            iteration <= '0;
+        end else begin
+            case (state)
+                IDLE: begin
+                    if (input_valid) begin
+                        done <= '0;
+
+                        vec_x <= VEC_BITS'(abs_delta_x << norm_shift) << FRAC_BITS;
+                        vec_y <= VEC_BITS'(abs_delta_y << norm_shift) << FRAC_BITS;
+                        rot_x <= ROT_BITS'(scaled_move_dist >> (32 - FRAC_BITS));
+                        rot_y <= '0;
+
+                        negate_x <= delta.x < 0;
+                        negate_y <= delta.y < 0;
+                        degenerate <= delta.x == 0 && delta.y == 0;
+
+                        iteration <= '0;
+                        state <= ROTATING;
+                    end
+                end
+                ROTATING: begin
+                    if (vec_y >= 0) begin
+                        // Vector is above the x axis, rotate it clockwise and the other one
+                        // counterclockwise
+                        vec_x <= vec_x + (vec_y >>> iteration);
+                        vec_y <= vec_y - (vec_x >>> iteration);
+                        rot_x <= rot_x - (rot_y >>> iteration);
+                        rot_y <= rot_y + (rot_x >>> iteration);
+                    end else begin
+                        vec_x <= vec_x - (vec_y >>> iteration);
+                        vec_y <= vec_y + (vec_x >>> iteration);
+                        rot_x <= rot_x + (rot_y >>> iteration);
+                        rot_y <= rot_y - (rot_x >>> iteration);
+                    end
+
+                    iteration <= iteration + 1;
+                    if (iteration == ITER_BITS'(ITERATIONS - 1)) state <= FINISH;
+                end
+                FINISH: begin
+                    result.x <= `POINT_BITS'(result_x);
+                    result.y <= `POINT_BITS'(result_y);
+                    // Anything set above the point bits means we went negative or overflowed
+                    out_of_bounds <= degenerate
+                                  || result_x[RESULT_BITS-1:`POINT_BITS] != '0
+                                  || result_y[RESULT_BITS-1:`POINT_BITS] != '0;
+                    done <= '1;
+                    state <= IDLE;
+                end
+                default: state <= IDLE;
+            endcase
+        end
+    end
+endmodule
+
+`endif
@@ -0,0 +1,108 @@
+`ifndef TREE_READBACK_SV
+`define TREE_READBACK_SV
+
+`include "membus.sv"
+`include "point.sv"
+
+// Gets a tree that rrt_top built back out of tree_mem, one node at a time, over a valid/ready
+// stream. Node i is read from address i as {parent, point}, the same way rrt_top stores it.
+//
+// With full_tree low, this starts at goal_node and follows parents back to the root, so the path
+// comes out goal first. With it high, every node from 0 up to node_count - 1 comes out in order.
+// Either way, last is set on the final node, and input is only taken again once it's been sent.
+// node_count has to be at least 1, which it always is after a run.
+//
+// A node stays on the output until out_ready is seen with out_valid at a clock edge. tree_mem can
+// be shared, since requests are held until they're granted.
+module tree_readback #(
+    // Must match tree_mem.ADDR_WIDTH, and tree_mem.DATA_WIDTH must be TREE_ADDR_WIDTH + 2 * `POINT_BITS
+    parameter TREE_ADDR_WIDTH
+) (
+    input logic clk,
+    input logic rst_n,
+
+    input logic full_tree,
+    input logic [TREE_ADDR_WIDTH-1:0] goal_node,
+    input logic [TREE_ADDR_WIDTH:0] node_count,
+
+    input logic input_valid,
+    output logic ready_for_input,
+
+    output logic [TREE_ADDR_WIDTH-1:0] out_index,
+    output point_t out_point,
+    output logic [TREE_ADDR_WIDTH-1:0] out_parent,
+    output logic out_last,
+    output logic out_valid,
+    input logic out_ready,
+
+    memory_bus.client tree_mem
+);
+    typedef logic [TREE_ADDR_WIDTH-1:0] node_t;
+
+    typedef enum logic [1:0] {
+        IDLE,
+        READ,
+        WAIT,
+        SEND
+    } state_t;
+
+    state_t state;
+
+    logic walking_full_tree;
+    logic [TREE_ADDR_WIDTH:0] count;
+
+    assign ready_for_input = state == IDLE;
+
+    assign tree_mem.request = state == READ;
+    assign tree_mem.write_enable = '0;
+    assign tree_mem.write_data = '0;
+
+    always_ff @(posedge clk) begin
+        if (!rst_n) begin
+            state <= IDLE;
+            out_valid <= '0;
+        end else begin
+            case (state)
+                IDLE: begin
+                    if (input_valid) begin
+                        tree_mem.address <= full_tree ? '0 : goal_node;
+                        walking_full_tree <= full_tree;
+                        count <= node_count;
+                        state <= READ;
+                    end
+                end
+                READ: begin
+                    if (tree_mem.grant) begin
+                        state <= WAIT;
+                    end
+                end
+                WAIT: begin
+                    out_index <= tree_mem.address;
+                    out_point <= tree_mem.read_data[2*`POINT_BITS-1:0];
+                    out_parent <= tree_mem.read_data[2*`POINT_BITS +: TREE_ADDR_WIDTH];
+                    out_last <= walking_full_tree
+                        ? (TREE_ADDR_WIDTH+1)'(tree_mem.address) + 1 >= count
+                        : tree_mem.address == '0;
+                    out_valid <= '1;
+                    state <= SEND;
+                end
+                SEND: begin
+                    if (out_ready) begin
+                        out_valid <= '0;
+
+                        if (out_last) begin
+                            state <= IDLE;
+                        end else begin
+                            // The root is its own parent, but out_last has already stopped us there
+                            tree_mem.address <= walking_full_tree ? out_index + 1 : out_parent;
+                            state <= READ;
+                        end
+                    end
+                end
+                default: state <= IDLE;
+            endcase
+        end
+    end
+endmodule
+
+`endif
@@ -0,0 +1,107 @@
+`ifndef UART_RX_SV
+`define UART_RX_SV
+
+// Receives 8N1 UART: a low start bit, 8 data bits least significant first, and a high stop bit.
+// data_valid pulses for one cycle with each byte. If the stop bit isn't high, the byte gets dropped
+// and framing_error pulses instead.
+module uart_rx #(
+    // Clock frequency / baud rate. Must be at least 2.
+    parameter CLKS_PER_BIT
+) (
+    input logic clk,
+    input logic rst_n,
+
+    input logic rx,
+
+    output logic [7:0] data,
+    output logic data_valid,
+    output logic framing_error
+);
+    localparam COUNT_BITS = $clog2(CLKS_PER_BIT);
+
+    // rx comes from outside of our clock domain
+    logic rx_meta;
+    logic rx_sync;
+
+    typedef enum logic [1:0] {
+        IDLE,
+        START_BIT,
+        DATA_BITS,
+        STOP_BIT
+    } state_t;
+
+    state_t state;
+    logic [COUNT_BITS-1:0] clk_count;
+    logic [2:0] bit_index;
+
+    always_ff @(posedge clk) begin
+        if (!rst_n) begin
+            // The line idles high
+            rx_meta <= '1;
+            rx_sync <= '1;
+
+            state <= IDLE;
+            clk_count <= '0;
+            bit_index <= '0;
+            data_valid <= '0;
+            framing_error <= '0;
+        end else begin
+            rx_meta <= rx;
+            rx_sync <= rx_meta;
+
+            data_valid <= '0;
+            framing_error <= '0;
+
+            case (state)
+                IDLE: begin
+                    if (!rx_sync) begin
+                        // This cycle was already part of the start bit
+                        clk_count <= COUNT_BITS'(1);
+                        state <= START_BIT;
+                    end
+                end
+                START_BIT: begin
+                    // Everything after this gets sampled in the middle of its bit. If the line's
+                    // gone back up by now, it was a glitch and not a start bit.
+                    if (clk_count >= COUNT_BITS'(CLKS_PER_BIT / 2)) begin
+                        clk_count <= '0;
+                        bit_index <= '0;
+                        state <= rx_sync ? IDLE : DATA_BITS;
+                    end else begin
+                        clk_count <= clk_count + 1;
+                    end
+                end
+                DATA_BITS: begin
+                    if (clk_count == COUNT_BITS'(CLKS_PER_BIT - 1)) begin
+                        clk_count <= '0;
+                        data <= {rx_sync, data[7:1]};
+
+                        if (bit_index == 3'd7) begin
+                            state <= STOP_BIT;
+                        end else begin
+                            bit_index <= bit_index + 1;
+                        end
+                    end else begin
+                        clk_count <= clk_count + 1;
+                    end
+                end
+                STOP_BIT: begin
+                    if (clk_count == COUNT_BITS'(CLKS_PER_BIT - 1)) begin
+                        clk_count <= '0;
+                        data_valid <= rx_sync;
+                        framing_error <= !rx_sync;
+
+                        // We're halfway through the stop bit, which is fine, since the next start
+                        // bit is the first low after it
+                        state <= IDLE;
+                    end else begin
+                        clk_count <= clk_count + 1;
+                    end
+                end
+                default: state <= IDLE;
+            endcase
+        end
+    end
+endmodule
+
+`endif
@@ -0,0 +1,781 @@
+//! Shared pieces for the Verilator tests, so a new module only needs its own checks.
+//!
+//! Models get wrapped in a `Dut`, which dumps a waveform for every test when `RRT_VCD` is set. The
+//! traits here get implemented on that, which the `impl_*` macros do given which ports are which:
+//! * `Clocked` gives `tick` and a reset sequence.
+//! * `MemoryRequester` drives the client end of a `memory_bus`.
+//! * `GridRequester` and `GridResponder` are the client and grid ends of an `occupancy_grid_bus`.
+//! * `AxiLiteMaster` drives an AXI4-Lite slave port.
+//!
+//! `BramModel` and `GridModel` stand in for a `bram` and an `occupancy_grid`, either to answer a
+//! model's requests or as the reference to check one against. `Scoreboard` matches up results
+//! that can come back in any order.
+//!
+//! Every model is built with the assertions in `PROTOCOL_CHECKERS` bound in, and `Clocked::tick`
+//! panics if any of them failed on the clock edge.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+
+use marlin::verilator::vcd::Vcd;
+use marlin::verilator::{VerilatorRuntime, VerilatorRuntimeOptions};
+use marlin::verilog::prelude::*;
+use snafu::{ResultExt, Whatever};
+
+use crate::shared::grid::OccupancyGrid;
+
+/// Set this to anything to get a waveform out of every test, in `WAVES_DIR`.
+pub const VCD_ENV: &str = "RRT_VCD";
+
+/// Where waveforms go, relative to the `rrt` crate, like the Verilator build.
+pub const WAVES_DIR: &str = "build/waves";
+
+/// Bus protocol assertions, which get built alongside every model so they can bind into it.
+pub const PROTOCOL_CHECKERS: &str = "src/fpga/verilog/test/protocol_checkers.sv";
+
+/// Builds `src` (a wrapper, usually) with everything in `src/fpga/verilog/src/` includable, and
+/// assertions turned on.
+pub fn runtime(src: &str) -> Result<VerilatorRuntime, Whatever> {
+    VerilatorRuntime::new2(
+        "build",
+        &[src, PROTOCOL_CHECKERS],
+        &[Path::new("src/fpga/verilog/src/")],
+        [protocol_violation],
+        VerilatorRuntimeOptions {
+            verilator_flags: vec!["--assert".into()],
+            ..Default::default()
+        },
+    )
+}
+
+thread_local! {
+    // Checks that have failed since the last clock edge. Each test has its own thread, and so do
+    // its models.
+    static VIOLATIONS: RefCell<Vec<i32>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Called from `PROTOCOL_CHECKERS` when an assertion fails, with the `CHECK_*` number for it.
+#[verilog::dpi]
+pub extern "C" fn protocol_violation(check: i32) {
+    VIOLATIONS.with_borrow_mut(|violations| violations.push(check));
+}
+
+/// Panics if any protocol assertion has failed since the last time this was called.
+pub fn assert_protocol() {
+    let violations = VIOLATIONS.take();
+    if let Some(&check) = violations.first() {
+        let what = match check {
+            1 => "an occupancy_grid_bus request changed before the grid took it",
+            2 => "an occupancy_grid answered a read at the wrong time, or answered a write",
+            3 => "a memory_bus request changed before it was granted",
+            _ => "unknown check",
+        };
+        panic!("protocol violation: {} (check {})", what, check);
+    }
+}
+
+/// A model that can dump a waveform. `impl_clocked` implements this along with `Clocked`.
+pub trait Traceable {
+    type Waveform: Waveform;
+
+    fn open_waveform(&mut self, path: &Path) -> Self::Waveform;
+}
+
+/// Somewhere to record a model's state over time.
+pub trait Waveform {
+    fn dump(&mut self, time: u64);
+}
+
+impl<'ctx> Waveform for Vcd<'ctx> {
+    fn dump(&mut self, time: u64) {
+        Vcd::dump(self, time);
+    }
+}
+
+/// A model under test, which is what everything else here works on. Derefs to the model, so its
+/// ports are still right there.
+pub struct Dut<M: Traceable> {
+    model: M,
+    waveform: Option<M::Waveform>,
+    time: u64,
+}
+
+impl<M: Traceable> Dut<M> {
+    /// Wraps `model`, and starts its waveform if `RRT_VCD` is set. The file is named after the
+    /// running test and the model, so tests with several models get one for each.
+    pub fn new(mut model: M) -> Result<Dut<M>, Whatever> {
+        let waveform = match std::env::var_os(VCD_ENV) {
+            Some(_) => {
+                let path = waveform_path::<M>();
+                std::fs::create_dir_all(WAVES_DIR)
+                    .with_whatever_context(|_| format!("couldn't create {}", WAVES_DIR))?;
+                Some(model.open_waveform(&path))
+            }
+            None => None,
+        };
+
+        Ok(Dut {
+            model,
+            waveform,
+            time: 0,
+        })
+    }
+
+    /// Records what the model looks like right now, half a clock period after the last one.
+    pub fn dump_waveform(&mut self) {
+        if let Some(waveform) = &mut self.waveform {
+            waveform.dump(self.time);
+        }
+        self.time += 5;
+    }
+}
+
+impl<M: Traceable> Deref for Dut<M> {
+    type Target = M;
+
+    fn deref(&self) -> &M {
+        &self.model
+    }
+}
+
+impl<M: Traceable> DerefMut for Dut<M> {
+    fn deref_mut(&mut self) -> &mut M {
+        &mut self.model
+    }
+}
+
+/// `WAVES_DIR/<test>.<model>.vcd`. Tests run on a thread named after them.
+fn waveform_path<M>() -> PathBuf {
+    let thread = std::thread::current();
+    let test = thread.name().unwrap_or("unknown").replace("::", ".");
+    let model = std::any::type_name::<M>();
+    let model = model.split('<').next().unwrap_or(model);
+    let model = model.rsplit("::").next().unwrap_or(model);
+    Path::new(WAVES_DIR).join(format!("{}.{}.vcd", test, model))
+}
+
+/// A model with a `clk` and an active low `rst_n`.
+pub trait Clocked {
+    fn set_clk(&mut self, clk: bool);
+
+    fn set_rst_n(&mut self, rst_n: bool);
+
+    /// Evaluates the model with whatever is on its inputs right now.
+    fn settle(&mut self);
+
+    /// Adds the current state to the waveform, if there is one.
+    fn dump(&mut self);
+
+    /// Runs one clock cycle. The waveform gets the inputs just before the rising edge, then what
+    /// came out of it. Panics if a protocol assertion failed on the edge.
+    fn tick(&mut self) {
+        self.dump();
+        self.set_clk(true);
+        self.settle();
+        self.dump();
+        assert_protocol();
+        self.set_clk(false);
+        self.settle();
+    }
+
+    /// Holds reset for a cycle, then runs one more with it released. Any other inputs should be
+    /// put in their idle state first.
+    fn pulse_reset(&mut self) {
+        self.set_rst_n(false);
+        self.set_clk(false);
+        self.settle();
+
+        self.tick();
+        self.set_rst_n(true);
+        self.tick();
+    }
+}
+
+/// Implements `Clocked` for a `Dut` of a model with `clk` and `rst_n` ports, and `Traceable` for
+/// the model. Models without a reset pass `no_reset`.
+macro_rules! impl_clocked {
+    ($model:ident) => {
+        impl_clocked!(@impl $model, rst_n);
+    };
+    ($model:ident, no_reset) => {
+        impl_clocked!(@impl $model);
+    };
+    (@impl $model:ident $(, $rst_n:ident)?) => {
+        impl<'ctx> $crate::fpga::verilog::test::bench::Traceable for $model<'ctx> {
+            type Waveform = marlin::verilator::vcd::Vcd<'ctx>;
+
+            fn open_waveform(&mut self, path: &std::path::Path) -> Self::Waveform {
+                self.open_vcd(path)
+            }
+        }
+
+        impl<'ctx> $crate::fpga::verilog::test::bench::Clocked
+            for $crate::fpga::verilog::test::bench::Dut<$model<'ctx>>
+        {
+            fn set_clk(&mut self, clk: bool) {
+                self.clk = clk as u8;
+            }
+
+            #[allow(unused_variables)]
+            fn set_rst_n(&mut self, rst_n: bool) {
+                $(self.$rst_n = rst_n as u8;)?
+            }
+
+            fn settle(&mut self) {
+                self.eval();
+            }
+
+            fn dump(&mut self) {
+                self.dump_waveform();
+            }
+        }
+    };
+}
+pub(crate) use impl_clocked;
+
+/// One access on a `memory_bus`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MemoryAccess {
+    Read(u64),
+    Write(u64, u64),
+}
+
+/// The client end of a `memory_bus`, on a model that talks to memory.
+pub trait MemoryRequester: Clocked {
+    /// Puts `access` on the bus, or takes the request down if there isn't one.
+    fn put_memory_access(&mut self, access: Option<MemoryAccess>);
+
+    /// Whether the access on the bus is granted this cycle. Ports without a grant always are.
+    fn memory_granted(&self) -> bool;
+
+    /// What's on `read_data`. Ports that can only write always read 0.
+    fn memory_read_data(&self) -> u64;
+
+    /// Runs one cycle with `access` on the bus, then takes it down. Returns whether it was
+    /// granted. Read data for it is on `memory_read_data` afterwards.
+    fn memory_step(&mut self, access: Option<MemoryAccess>) -> bool {
+        self.put_memory_access(access);
+        self.settle();
+
+        let granted = access.is_some() && self.memory_granted();
+        self.tick();
+
+        self.put_memory_access(None);
+        granted
+    }
+
+    /// Writes one word, returning whether it was granted.
+    fn write_word(&mut self, address: u64, data: u64) -> bool {
+        self.memory_step(Some(MemoryAccess::Write(address, data)))
+    }
+
+    /// Reads one word. Only makes sense on ports that always get a grant.
+    fn read_word(&mut self, address: u64) -> u64 {
+        self.memory_step(Some(MemoryAccess::Read(address)));
+        self.memory_read_data()
+    }
+}
+
+/// Implements `MemoryRequester` for a model, given which of its fields are which `memory_bus`
+/// signals. `request`/`grant` and `read_data` can be left out for ports that don't have them.
+macro_rules! impl_memory_requester {
+    (
+        $model:ident {
+            $(request: $request:ident, grant: $grant:ident,)?
+            address: $address:ident,
+            write_data: $write_data:ident,
+            write_enable: $write_enable:ident
+            $(, read_data: $read_data:ident)?
+            $(,)?
+        }
+    ) => {
+        impl<'ctx> $crate::fpga::verilog::test::bench::MemoryRequester
+            for $crate::fpga::verilog::test::bench::Dut<$model<'ctx>>
+        {
+            fn put_memory_access(
+                &mut self,
+                access: Option<$crate::fpga::verilog::test::bench::MemoryAccess>,
+            ) {
+                use $crate::fpga::verilog::test::bench::MemoryAccess;
+
+                let (address, write_data, write_enable) = match access {
+                    None => (0, 0, false),
+                    Some(MemoryAccess::Read(address)) => (address, 0, false),
+                    Some(MemoryAccess::Write(address, data)) => (address, data, true),
+                };
+                $(self.$request = access.is_some() as u8;)?
+                self.$address = address as _;
+                self.$write_data = write_data as _;
+                self.$write_enable = write_enable as u8;
+            }
+
+            fn memory_granted(&self) -> bool {
+                impl_memory_requester!(@granted self $($grant)?)
+            }
+
+            fn memory_read_data(&self) -> u64 {
+                impl_memory_requester!(@read_data self $($read_data)?)
+            }
+        }
+    };
+    (@granted $self_:ident) => { true };
+    (@granted $self_:ident $grant:ident) => { $self_.$grant != 0 };
+    (@read_data $self_:ident) => { 0 };
+    (@read_data $self_:ident $read_data:ident) => { $self_.$read_data as u64 };
+}
+pub(crate) use impl_memory_requester;
+
+/// How many cycles `AxiLiteMaster` waits for a handshake before giving up.
+pub const AXI_TIMEOUT: usize = 1000;
+
+/// What a slave answers an AXI4-Lite access with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AxiResp {
+    Okay,
+    ExOkay,
+    SlvErr,
+    DecErr,
+}
+
+impl AxiResp {
+    pub fn from_bits(bits: u8) -> AxiResp {
+        match bits & 0b11 {
+            0b00 => AxiResp::Okay,
+            0b01 => AxiResp::ExOkay,
+            0b10 => AxiResp::SlvErr,
+            _ => AxiResp::DecErr,
+        }
+    }
+}
+
+/// The master end of an AXI4-Lite bus, on a model with a slave port. One access at a time, which
+/// is all AXI4-Lite slaves have to handle.
+pub trait AxiLiteMaster: Clocked {
+    /// Puts up the write address and write data channels, or takes them down.
+    fn put_axi_write(&mut self, address: Option<u64>, data: Option<(u32, u8)>);
+
+    /// `awready` and `wready`.
+    fn axi_write_ready(&self) -> (bool, bool);
+
+    fn put_axi_bready(&mut self, ready: bool);
+
+    /// `bresp`, if `bvalid` is up.
+    fn axi_write_response(&self) -> Option<AxiResp>;
+
+    fn put_axi_read(&mut self, address: Option<u64>);
+
+    fn axi_read_ready(&self) -> bool;
+
+    fn put_axi_rready(&mut self, ready: bool);
+
+    /// `rdata` and `rresp`, if `rvalid` is up.
+    fn axi_read_response(&self) -> Option<(u32, AxiResp)>;
+
+    /// Writes a whole word.
+    fn axi_write(&mut self, address: u64, data: u32) -> AxiResp {
+        self.axi_write_strobed(address, data, 0b1111)
+    }
+
+    /// Writes the bytes of `data` picked out by `strobe`. Both channels go up together, and each
+    /// comes down once it's taken, then the response is waited for.
+    fn axi_write_strobed(&mut self, address: u64, data: u32, strobe: u8) -> AxiResp {
+        let mut address = Some(address);
+        let mut data = Some((data, strobe));
+        for _ in 0..AXI_TIMEOUT {
+            if address.is_none() && data.is_none() {
+                break;
+            }
+
+            self.put_axi_write(address, data);
+            self.settle();
+            let (address_ready, data_ready) = self.axi_write_ready();
+            self.tick();
+
+            if address_ready {
+                address = None;
+            }
+            if data_ready {
+                data = None;
+            }
+        }
+        assert!(
+            address.is_none() && data.is_none(),
+            "write wasn't taken in {} cycles",
+            AXI_TIMEOUT
+        );
+        self.put_axi_write(None, None);
+
+        self.put_axi_bready(true);
+        for _ in 0..AXI_TIMEOUT {
+            self.settle();
+            let response = self.axi_write_response();
+            self.tick();
+
+            if let Some(response) = response {
+                self.put_axi_bready(false);
+                return response;
+            }
+        }
+        panic!("no write response in {} cycles", AXI_TIMEOUT);
+    }
+
+    fn axi_read(&mut self, address: u64) -> (u32, AxiResp) {
+        self.put_axi_read(Some(address));
+        let mut taken = false;
+        for _ in 0..AXI_TIMEOUT {
+            self.settle();
+            taken = self.axi_read_ready();
+            self.tick();
+
+            if taken {
+                break;
+            }
+        }
+        assert!(taken, "read wasn't taken in {} cycles", AXI_TIMEOUT);
+        self.put_axi_read(None);
+
+        self.put_axi_rready(true);
+        for _ in 0..AXI_TIMEOUT {
+            self.settle();
+            let response = self.axi_read_response();
+            self.tick();
+
+            if let Some(response) = response {
+                self.put_axi_rready(false);
+                return response;
+            }
+        }
+        panic!("no read response in {} cycles", AXI_TIMEOUT);
+    }
+}
+
+/// Implements `AxiLiteMaster` for a `Dut` of a model whose slave port uses the usual `s_axi_*`
+/// names.
+macro_rules! impl_axi_lite_master {
+    ($model:ident) => {
+        impl<'ctx> $crate::fpga::verilog::test::bench::AxiLiteMaster
+            for $crate::fpga::verilog::test::bench::Dut<$model<'ctx>>
+        {
+            fn put_axi_write(&mut self, address: Option<u64>, data: Option<(u32, u8)>) {
+                self.s_axi_awaddr = address.unwrap_or_default() as _;
+                self.s_axi_awprot = 0;
+                self.s_axi_awvalid = address.is_some() as u8;
+
+                self.s_axi_wvalid = data.is_some() as u8;
+                let (data, strobe) = data.unwrap_or_default();
+                self.s_axi_wdata = data as _;
+                self.s_axi_wstrb = strobe as _;
+            }
+
+            fn axi_write_ready(&self) -> (bool, bool) {
+                (self.s_axi_awready != 0, self.s_axi_wready != 0)
+            }
+
+            fn put_axi_bready(&mut self, ready: bool) {
+                self.s_axi_bready = ready as u8;
+            }
+
+            fn axi_write_response(&self) -> Option<$crate::fpga::verilog::test::bench::AxiResp> {
+                (self.s_axi_bvalid != 0).then(|| {
+                    $crate::fpga::verilog::test::bench::AxiResp::from_bits(self.s_axi_bresp as u8)
+                })
+            }
+
+            fn put_axi_read(&mut self, address: Option<u64>) {
+                self.s_axi_araddr = address.unwrap_or_default() as _;
+                self.s_axi_arprot = 0;
+                self.s_axi_arvalid = address.is_some() as u8;
+            }
+
+            fn axi_read_ready(&self) -> bool {
+                self.s_axi_arready != 0
+            }
+
+            fn put_axi_rready(&mut self, ready: bool) {
+                self.s_axi_rready = ready as u8;
+            }
+
+            fn axi_read_response(
+                &self,
+            ) -> Option<(u32, $crate::fpga::verilog::test::bench::AxiResp)> {
+                (self.s_axi_rvalid != 0).then(|| {
+                    (
+                        self.s_axi_rdata as u32,
+                        $crate::fpga::verilog::test::bench::AxiResp::from_bits(
+                            self.s_axi_rresp as u8,
+                        ),
+                    )
+                })
+            }
+        }
+    };
+}
+pub(crate) use impl_axi_lite_master;
+
+/// What a `bram` does with each access.
+pub struct BramModel {
+    words: Vec<u64>,
+}
+
+impl BramModel {
+    pub fn new(addr_width: u32) -> BramModel {
+        BramModel {
+            words: vec![0; 1 << addr_width],
+        }
+    }
+
+    pub fn word(&self, address: u64) -> u64 {
+        self.words[address as usize]
+    }
+
+    /// Applies `access`, returning what the bram puts on `read_data` the cycle after. That's the
+    /// old contents for a write too.
+    pub fn access(&mut self, access: MemoryAccess) -> u64 {
+        match access {
+            MemoryAccess::Read(address) => self.word(address),
+            MemoryAccess::Write(address, data) => {
+                std::mem::replace(&mut self.words[address as usize], data)
+            }
+        }
+    }
+}
+
+/// One request on an `occupancy_grid_bus`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GridAccess {
+    pub x: usize,
+    pub y: usize,
+    /// What to write to the cell, or `None` to read it.
+    pub write: Option<bool>,
+}
+
+impl GridAccess {
+    pub fn read(x: usize, y: usize) -> GridAccess {
+        GridAccess { x, y, write: None }
+    }
+
+    pub fn write(x: usize, y: usize, occupied: bool) -> GridAccess {
+        GridAccess {
+            x,
+            y,
+            write: Some(occupied),
+        }
+    }
+}
+
+/// The client end of an `occupancy_grid_bus`, on a model that talks to a grid.
+pub trait GridRequester: Clocked {
+    /// Puts `access` on the bus, or takes `input_valid` down if there isn't one.
+    fn put_grid_access(&mut self, access: Option<GridAccess>);
+
+    fn grid_ready_for_input(&self) -> bool;
+
+    /// What's on `read_occupied`, if `output_valid` is up.
+    fn grid_output(&self) -> Option<bool>;
+
+    /// Runs one cycle with `access` on the bus, then takes it down. Returns whether it was
+    /// accepted, and the read result that came out at the end of the cycle, if any.
+    fn grid_step(&mut self, access: Option<GridAccess>) -> (bool, Option<bool>) {
+        self.put_grid_access(access);
+        self.settle();
+
+        let accepted = access.is_some() && self.grid_ready_for_input();
+        self.tick();
+
+        self.put_grid_access(None);
+        (accepted, self.grid_output())
+    }
+
+    /// Puts up `access`, and holds it until the grid accepts it.
+    fn hold_grid_access(&mut self, access: GridAccess) {
+        while !self.grid_step(Some(access)).0 {}
+    }
+
+    fn write_cell(&mut self, x: usize, y: usize, occupied: bool) {
+        self.hold_grid_access(GridAccess::write(x, y, occupied));
+    }
+
+    fn read_cell(&mut self, x: usize, y: usize) -> bool {
+        self.hold_grid_access(GridAccess::read(x, y));
+        loop {
+            if let Some(occupied) = self.grid_output() {
+                return occupied;
+            }
+            self.tick();
+        }
+    }
+}
+
+/// The grid end of an `occupancy_grid_bus`, on a model that needs a grid to talk to.
+pub trait GridResponder: Clocked {
+    /// The request the model has on the bus, if `input_valid` is up.
+    fn grid_request(&self) -> Option<GridAccess>;
+
+    /// Drives `ready_for_input`, and `output_valid`/`read_occupied` with `read`.
+    fn put_grid_response(&mut self, ready: bool, read: Option<bool>);
+}
+
+/// Implements `GridRequester` or `GridResponder` for a model, given which of its fields are which
+/// `occupancy_grid_bus` signals.
+macro_rules! impl_grid_bus {
+    (
+        $trait_:ident for $model:ident {
+            cell_x: $cell_x:ident,
+            cell_y: $cell_y:ident,
+            input_valid: $input_valid:ident,
+            ready_for_input: $ready_for_input:ident,
+            write_enable: $write_enable:ident,
+            write_occupied: $write_occupied:ident,
+            output_valid: $output_valid:ident,
+            read_occupied: $read_occupied:ident $(,)?
+        }
+    ) => {
+        impl_grid_bus!(@$trait_ $model, $cell_x, $cell_y, $input_valid, $ready_for_input,
+            $write_enable, $write_occupied, $output_valid, $read_occupied);
+    };
+    (
+        @GridRequester $model:ident, $cell_x:ident, $cell_y:ident, $input_valid:ident,
+        $ready_for_input:ident, $write_enable:ident, $write_occupied:ident, $output_valid:ident,
+        $read_occupied:ident
+    ) => {
+        impl<'ctx> $crate::fpga::verilog::test::bench::GridRequester
+            for $crate::fpga::verilog::test::bench::Dut<$model<'ctx>>
+        {
+            fn put_grid_access(
+                &mut self,
+                access: Option<$crate::fpga::verilog::test::bench::GridAccess>,
+            ) {
+                self.$input_valid = access.is_some() as u8;
+                if let Some(access) = access {
+                    self.$cell_x = access.x as _;
+                    self.$cell_y = access.y as _;
+                    self.$write_enable = access.write.is_some() as u8;
+                    self.$write_occupied = access.write.unwrap_or(false) as u8;
+                } else {
+                    self.$write_enable = 0;
+                }
+            }
+
+            fn grid_ready_for_input(&self) -> bool {
+                self.$ready_for_input != 0
+            }
+
+            fn grid_output(&self) -> Option<bool> {
+                (self.$output_valid != 0).then_some(self.$read_occupied != 0)
+            }
+        }
+    };
+    (
+        @GridResponder $model:ident, $cell_x:ident, $cell_y:ident, $input_valid:ident,
+        $ready_for_input:ident, $write_enable:ident, $write_occupied:ident, $output_valid:ident,
+        $read_occupied:ident
+    ) => {
+        impl<'ctx> $crate::fpga::verilog::test::bench::GridResponder
+            for $crate::fpga::verilog::test::bench::Dut<$model<'ctx>>
+        {
+            fn grid_request(&self) -> Option<$crate::fpga::verilog::test::bench::GridAccess> {
+                (self.$input_valid != 0).then_some($crate::fpga::verilog::test::bench::GridAccess {
+                    x: self.$cell_x as usize,
+                    y: self.$cell_y as usize,
+                    write: (self.$write_enable != 0).then_some(self.$write_occupied != 0),
+                })
+            }
+
+            fn put_grid_response(&mut self, ready: bool, read: Option<bool>) {
+                self.$ready_for_input = ready as u8;
+                self.$output_valid = read.is_some() as u8;
+                self.$read_occupied = read.unwrap_or(false) as u8;
+            }
+        }
+    };
+}
+pub(crate) use impl_grid_bus;
+
+/// What an `occupancy_grid` holds, indexed `cells[y][x]`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GridModel {
+    pub cells: Vec<Vec<bool>>,
+}
+
+impl GridModel {
+    pub fn new(width: usize, height: usize) -> GridModel {
+        GridModel {
+            cells: vec![vec![false; width]; height],
+        }
+    }
+
+    pub fn from_grid(grid: &OccupancyGrid) -> GridModel {
+        let (width, height) = grid.size();
+        let mut model = GridModel::new(width, height);
+        for (y, row) in model.cells.iter_mut().enumerate() {
+            for (x, cell) in row.iter_mut().enumerate() {
+                *cell = *grid.cell(x, y);
+            }
+        }
+        model
+    }
+
+    /// Applies `access`, returning what was read, if it was a read.
+    pub fn access(&mut self, access: GridAccess) -> Option<bool> {
+        let cell = &mut self.cells[access.y][access.x];
+        match access.write {
+            Some(occupied) => {
+                *cell = occupied;
+                None
+            }
+            None => Some(*cell),
+        }
+    }
+
+    /// Answers whatever `model` has on the bus, to be called before each of its ticks. Always
+    /// ready, and reads come back in the same cycle, which is quicker than a real grid but keeps
+    /// the model's own timing easy to see.
+    pub fn respond(&mut self, model: &mut impl GridResponder) {
+        let read = model.grid_request().and_then(|access| self.access(access));
+        model.put_grid_response(true, read);
+    }
+}
+
+/// Matches results against what they should be, when they can come back in any order. Each
+/// result is looked up by a key, like the tag it was sent with.
+pub struct Scoreboard<K, V> {
+    expected: HashMap<K, V>,
+}
+
+impl<K, V> Default for Scoreboard<K, V> {
+    fn default() -> Scoreboard<K, V> {
+        Scoreboard {
+            expected: HashMap::new(),
+        }
+    }
+}
+
+impl<K: Eq + Hash + Debug, V: PartialEq + Debug> Scoreboard<K, V> {
+    /// Waits for a result for `key`. Panics if there's already one outstanding.
+    pub fn expect(&mut self, key: K, value: V) {
+        assert!(
+            !self.expected.contains_key(&key),
+            "already waiting on a result for {:?}",
+            key
+        );
+        self.expected.insert(key, value);
+    }
+
+    /// Checks a result that came back. Panics if it's wrong, or if nothing was waiting on it,
+    /// which includes it coming back twice.
+    pub fn check(&mut self, key: K, actual: V) {
+        match self.expected.remove(&key) {
+            Some(expected) => assert_eq!(actual, expected, "wrong result for {:?}", key),
+            None => panic!("unexpected result {:?} for {:?}", actual, key),
+        }
+    }
+
+    /// How many results haven't come back yet.
+    pub fn outstanding(&self) -> usize {
+        self.expected.len()
+    }
+}
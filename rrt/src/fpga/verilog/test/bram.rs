@@ -1,11 +1,21 @@
 #[cfg(test)]
 mod tests {
-    use std::{collections::HashMap, path::Path};
+    use std::collections::HashSet;
+    use std::fs::{self, File};
 
-    use marlin::verilator::{VerilatorRuntime, VerilatorRuntimeOptions};
+    use marlin::verilator::VerilatorRuntime;
     use marlin::verilog::prelude::*;
+    use na::vector;
     use rand::RngExt;
-    use snafu::Whatever;
+    use snafu::{ResultExt, Whatever};
+
+    use crate::fpga::meminit::{GridLayout, InitFormat};
+
+    use crate::fpga::verilog::test::bench::{
+        self, BramModel, Clocked, Dut, MemoryAccess, MemoryRequester, impl_clocked,
+        impl_memory_requester,
+    };
+    use crate::shared::grid::OccupancyGrid;
 
     const ADDR_WIDTH: u32 = 8;
     const DATA_WIDTH: u32 = 8;
@@ -18,43 +28,114 @@ mod tests {
     )]
     pub struct BramWrapper;
 
-    impl<'ctx> BramWrapper<'ctx> {
-        fn tick(&mut self) {
-            self.clk = 1;
-            self.eval();
-            self.clk = 0;
-            self.eval();
-        }
+    impl_clocked!(BramWrapper, no_reset);
+    impl_memory_requester!(BramWrapper {
+        address: bus_address,
+        write_data: bus_write_data,
+        write_enable: bus_write_enable,
+        read_data: bus_read_data,
+    });
+
+    /// Runs the hex and binary wrappers through the same test.
+    trait BramInit: MemoryRequester {
+        /// Where the wrapper was built to load its contents from.
+        const INIT_FILE: &'static str;
+        const FORMAT: InitFormat;
+    }
+
+    macro_rules! bram_init_wrapper {
+        ($wrapper:ident, $file:tt, $binary:tt, $format:expr) => {
+            #[verilog(
+                src = "src/fpga/verilog/test/wrappers/bram_init_wrapper.sv",
+                name = "bram_init_wrapper",
+                params = { ADDR_WIDTH: 8, DATA_WIDTH: 8, INIT_FILE: $file, INIT_BINARY: $binary },
+                includes = ["src/fpga/verilog/src/"]
+            )]
+            pub struct $wrapper;
+
+            impl_clocked!($wrapper, no_reset);
+            impl_memory_requester!($wrapper {
+                address: bus_address,
+                write_data: bus_write_data,
+                write_enable: bus_write_enable,
+                read_data: bus_read_data,
+            });
+
+            impl<'ctx> BramInit for Dut<$wrapper<'ctx>> {
+                const INIT_FILE: &'static str = $file;
+                const FORMAT: InitFormat = $format;
+            }
+        };
     }
 
+    bram_init_wrapper!(
+        BramInitHexWrapper,
+        "build/bram_init.hex",
+        0,
+        InitFormat::ReadmemH
+    );
+    bram_init_wrapper!(
+        BramInitBinWrapper,
+        "build/bram_init.bin",
+        1,
+        InitFormat::ReadmemB
+    );
+
     fn make_runtime() -> Result<VerilatorRuntime, Whatever> {
-        VerilatorRuntime::new2(
-            "build",
-            &["src/fpga/verilog/test/wrappers/bram_wrapper.sv"],
-            &[Path::new("src/fpga/verilog/src/")],
-            [],
-            VerilatorRuntimeOptions::default(),
-        )
+        bench::runtime("src/fpga/verilog/test/wrappers/bram_wrapper.sv")
+    }
+
+    /// A random 16x16 grid, which only fills the first 32 words, and the layout it's in.
+    fn write_init_file(path: &str, format: InitFormat) -> Result<Vec<u64>, Whatever> {
+        let mut rng = rand::rng();
+        let mut grid = OccupancyGrid::new(16, 16, vector![0.0, 0.0], 1.0);
+        for y in 0..16 {
+            for x in 0..16 {
+                *grid.cell_mut(x, y) = rng.random_bool(0.5);
+            }
+        }
+
+        let layout = GridLayout::new(4, 4, DATA_WIDTH as usize, ADDR_WIDTH)
+            .whatever_context("bad layout")?;
+        fs::create_dir_all("build").whatever_context("couldn't make build directory")?;
+        let mut file = File::create(path).whatever_context("couldn't create init file")?;
+        layout
+            .write_init(&grid, format, &mut file)
+            .whatever_context("couldn't write init file")?;
+
+        layout.pack(&grid).whatever_context("grid doesn't fit")
+    }
+
+    /// Writes a random grid to the wrapper's init file, then checks that the model `make_dut`
+    /// builds starts out with it. The file is read when the model starts, so it has to be written
+    /// first.
+    fn check_init_file<D: BramInit>(
+        make_dut: impl FnOnce() -> Result<D, Whatever>,
+    ) -> Result<(), Whatever> {
+        let expected = write_init_file(D::INIT_FILE, D::FORMAT)?;
+        let mut dut = make_dut()?;
+
+        dut.set_clk(false);
+        dut.settle();
+
+        for (address, &word) in expected.iter().enumerate() {
+            assert_eq!(dut.read_word(address as u64), word, "word {}", address);
+        }
+
+        Ok(())
     }
 
     #[test]
     #[snafu::report]
     fn test_read_write() -> Result<(), Whatever> {
         let runtime = make_runtime()?;
-        let mut dut = runtime.create_model_simple::<BramWrapper>()?;
-
-        dut.clk = 0;
-        dut.eval();
-
-        dut.bus_address = 0x01;
-        dut.bus_write_data = 0x42;
-        dut.bus_write_enable = 1;
-        dut.tick();
+        let mut dut = Dut::new(runtime.create_model_simple::<BramWrapper>()?)?;
 
-        dut.bus_write_enable = 0;
-        dut.tick();
+        dut.set_clk(false);
+        dut.settle();
 
-        assert_eq!(dut.bus_read_data, 0x42);
+        dut.write_word(0x01, 0x42);
+        assert_eq!(dut.read_word(0x01), 0x42);
 
         Ok(())
     }
@@ -63,35 +144,32 @@ mod tests {
     #[snafu::report]
     fn test_multiple_locations() -> Result<(), Whatever> {
         let runtime = make_runtime()?;
-        let mut dut = runtime.create_model_simple::<BramWrapper>()?;
+        let mut dut = Dut::new(runtime.create_model_simple::<BramWrapper>()?)?;
 
-        dut.clk = 0;
-        dut.eval();
+        dut.set_clk(false);
+        dut.settle();
 
         let mut rng = rand::rng();
-        let mut data: HashMap<u8, u8> = HashMap::new();
+        let mut model = BramModel::new(ADDR_WIDTH);
+        let mut written = HashSet::new();
 
         for _ in 0..20 {
-            let address: u8 = rng.random_range(0..(1u32 << ADDR_WIDTH)) as u8;
-            let value: u8 = rng.random_range(0..(1u32 << DATA_WIDTH)) as u8;
+            let address = rng.random_range(0..(1 << ADDR_WIDTH));
+            let value = rng.random_range(0..(1 << DATA_WIDTH));
 
-            data.insert(address, value);
-
-            dut.bus_address = address;
-            dut.bus_write_data = value;
-            dut.bus_write_enable = 1;
-            dut.tick();
+            model.access(MemoryAccess::Write(address, value));
+            written.insert(address);
+            dut.write_word(address, value);
         }
 
-        dut.bus_write_enable = 0;
-
-        for (&address, &expected) in &data {
-            dut.bus_address = address;
-            dut.tick();
+        // Whatever wasn't written is left at whatever the bram powered up with
+        for address in written {
+            let expected = model.word(address);
+            let read = dut.read_word(address);
             assert_eq!(
-                dut.bus_read_data, expected,
+                read, expected,
                 "addr {:#x}: expected {:#x}, got {:#x}",
-                address, expected, dut.bus_read_data
+                address, expected, read
             );
         }
 
@@ -102,33 +180,44 @@ mod tests {
     #[snafu::report]
     fn test_simultaneous_read_write() -> Result<(), Whatever> {
         let runtime = make_runtime()?;
-        let mut dut = runtime.create_model_simple::<BramWrapper>()?;
+        let mut dut = Dut::new(runtime.create_model_simple::<BramWrapper>()?)?;
 
-        dut.clk = 0;
-        dut.eval();
+        dut.set_clk(false);
+        dut.settle();
 
-        let address: u8 = 0x10;
-        let val1: u8 = 0xAA;
-        let val2: u8 = 0xBB;
+        let address = 0x10;
+        let val1 = 0xAA;
+        let val2 = 0xBB;
 
-        dut.bus_address = address;
-        dut.bus_write_data = val1;
-        dut.bus_write_enable = 1;
-        dut.tick();
-
-        dut.bus_write_data = val2;
-        dut.tick();
+        dut.write_word(address, val1);
+        dut.write_word(address, val2);
 
         assert_eq!(
-            dut.bus_read_data, val1,
+            dut.memory_read_data(),
+            val1,
             "read-during-write should return old value"
         );
 
-        dut.bus_write_enable = 0;
-        dut.tick();
-
-        assert_eq!(dut.bus_read_data, val2);
+        assert_eq!(dut.read_word(address), val2);
 
         Ok(())
     }
+
+    fn make_init_runtime() -> Result<VerilatorRuntime, Whatever> {
+        bench::runtime("src/fpga/verilog/test/wrappers/bram_init_wrapper.sv")
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_init_file_hex() -> Result<(), Whatever> {
+        let runtime = make_init_runtime()?;
+        check_init_file(|| Dut::new(runtime.create_model_simple::<BramInitHexWrapper>()?))
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_init_file_binary() -> Result<(), Whatever> {
+        let runtime = make_init_runtime()?;
+        check_init_file(|| Dut::new(runtime.create_model_simple::<BramInitBinWrapper>()?))
+    }
 }
@@ -0,0 +1,315 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use marlin::verilator::VerilatorRuntime;
+    use marlin::verilog::prelude::*;
+    use na::vector;
+    use rand::RngExt;
+    use snafu::Whatever;
+
+    use crate::fpga::fixed::{self, FixedPoint};
+    use crate::fpga::loader;
+    use crate::fpga::verilog::test::bench::{
+        self, Clocked, Dut, MemoryAccess, MemoryRequester, Scoreboard, impl_clocked,
+        impl_memory_requester,
+    };
+    use crate::shared::grid::OccupancyGrid;
+
+    const GRID_WIDTH_LOG2: u32 = 4;
+    const GRID_HEIGHT_LOG2: u32 = 4;
+    const DATA_WIDTH: usize = 16;
+
+    #[verilog(
+        src = "src/fpga/verilog/test/wrappers/collision_cluster_wrapper.sv",
+        name = "collision_cluster_wrapper",
+        params = {
+            NUM_UNITS: 1,
+            GRID_WIDTH_LOG2: 4,
+            GRID_HEIGHT_LOG2: 4,
+            POINT_BITS: 32,
+            TAG_WIDTH: 8,
+            ADDR_WIDTH: 4,
+            DATA_WIDTH: 16
+        },
+        includes = ["src/fpga/verilog/src/"]
+    )]
+    pub struct OneUnitCluster;
+
+    #[verilog(
+        src = "src/fpga/verilog/test/wrappers/collision_cluster_wrapper.sv",
+        name = "collision_cluster_wrapper",
+        params = {
+            NUM_UNITS: 2,
+            GRID_WIDTH_LOG2: 4,
+            GRID_HEIGHT_LOG2: 4,
+            POINT_BITS: 32,
+            TAG_WIDTH: 8,
+            ADDR_WIDTH: 4,
+            DATA_WIDTH: 16
+        },
+        includes = ["src/fpga/verilog/src/"]
+    )]
+    pub struct TwoUnitCluster;
+
+    #[verilog(
+        src = "src/fpga/verilog/test/wrappers/collision_cluster_wrapper.sv",
+        name = "collision_cluster_wrapper",
+        params = {
+            NUM_UNITS: 4,
+            GRID_WIDTH_LOG2: 4,
+            GRID_HEIGHT_LOG2: 4,
+            POINT_BITS: 32,
+            TAG_WIDTH: 8,
+            ADDR_WIDTH: 4,
+            DATA_WIDTH: 16
+        },
+        includes = ["src/fpga/verilog/src/"]
+    )]
+    pub struct FourUnitCluster;
+
+    /// Runs every cluster size through the same tests. Words written through `MemoryRequester`
+    /// go to every copy of the grid, once every segment being checked is done.
+    trait Cluster: MemoryRequester {
+        fn reset(&mut self);
+
+        /// Puts `segment` up for one cycle. Returns whether it was accepted, and the result that
+        /// came out, if any.
+        fn cycle(&mut self, segment: Option<(u64, u64, u8)>) -> (bool, Option<(u8, bool)>);
+    }
+
+    macro_rules! impl_cluster {
+        ($wrapper:ident) => {
+            impl_clocked!($wrapper);
+            impl_memory_requester!($wrapper {
+                request: load_request,
+                grant: load_grant,
+                address: load_address,
+                write_data: load_write_data,
+                write_enable: load_write_enable,
+            });
+
+            impl<'ctx> Cluster for Dut<$wrapper<'ctx>> {
+                fn reset(&mut self) {
+                    self.input_valid = 0;
+                    self.put_memory_access(None);
+                    self.pulse_reset();
+                }
+
+                fn cycle(&mut self, segment: Option<(u64, u64, u8)>) -> (bool, Option<(u8, bool)>) {
+                    let (a, b, tag) = segment.unwrap_or_default();
+                    self.a = a;
+                    self.b = b;
+                    self.tag = tag;
+                    self.input_valid = segment.is_some() as u8;
+                    self.eval();
+
+                    let accepted = segment.is_some() && self.ready_for_input != 0;
+                    self.tick();
+
+                    let result = (self.result_valid != 0)
+                        .then(|| (self.result_tag, self.result_occupied != 0));
+                    (accepted, result)
+                }
+            }
+        };
+    }
+
+    impl_cluster!(OneUnitCluster);
+    impl_cluster!(TwoUnitCluster);
+    impl_cluster!(FourUnitCluster);
+
+    fn make_runtime() -> Result<VerilatorRuntime, Whatever> {
+        bench::runtime("src/fpga/verilog/test/wrappers/collision_cluster_wrapper.sv")
+    }
+
+    fn load(dut: &mut impl Cluster, grid: &OccupancyGrid) {
+        let words = loader::pack_grid(grid, GRID_WIDTH_LOG2, GRID_HEIGHT_LOG2, DATA_WIDTH);
+        for (address, word) in words.into_iter().enumerate() {
+            assert!(
+                dut.write_word(address as u64, word),
+                "load of word {} wasn't granted",
+                address
+            );
+        }
+    }
+
+    fn random_grid(p: f64) -> OccupancyGrid {
+        let mut rng = rand::rng();
+        let mut grid = OccupancyGrid::new(
+            1 << GRID_WIDTH_LOG2,
+            1 << GRID_HEIGHT_LOG2,
+            vector![0.0, 0.0],
+            1.0,
+        );
+        for y in 0..1 << GRID_HEIGHT_LOG2 {
+            for x in 0..1 << GRID_WIDTH_LOG2 {
+                *grid.cell_mut(x, y) = rng.random_bool(p);
+            }
+        }
+        grid
+    }
+
+    fn random_segments(count: usize) -> Vec<(FixedPoint, FixedPoint)> {
+        let mut rng = rand::rng();
+        (0..count)
+            .map(|_| {
+                (
+                    FixedPoint::new(rng.random(), rng.random()),
+                    FixedPoint::new(rng.random(), rng.random()),
+                )
+            })
+            .collect()
+    }
+
+    /// Sends every segment through, tagged with its index, and checks that each one comes back
+    /// exactly once with the same result as the reference. Returns how many cycles it took.
+    fn run_segments(
+        dut: &mut impl Cluster,
+        grid: &OccupancyGrid,
+        segments: &[(FixedPoint, FixedPoint)],
+    ) -> usize {
+        assert!(segments.len() <= 1 << 8);
+
+        let mut queue: VecDeque<_> = segments.iter().enumerate().collect();
+        let mut scoreboard = Scoreboard::default();
+        for (tag, (a, b)) in segments.iter().enumerate() {
+            scoreboard.expect(tag as u8, fixed::is_segment_occupied(a, b, grid));
+        }
+        let mut cycles = 0;
+
+        while scoreboard.outstanding() > 0 {
+            let segment = queue
+                .front()
+                .map(|(tag, (a, b))| (a.to_bits(), b.to_bits(), *tag as u8));
+            let (accepted, result) = dut.cycle(segment);
+            if accepted {
+                queue.pop_front();
+            }
+
+            if let Some((tag, occupied)) = result {
+                scoreboard.check(tag, occupied);
+            }
+
+            cycles += 1;
+            assert!(cycles < 1_000_000, "results stopped coming back");
+        }
+
+        cycles
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_results_match_reference() -> Result<(), Whatever> {
+        let runtime = make_runtime()?;
+        let grid = random_grid(0.2);
+        let segments = random_segments(200);
+
+        let mut one = Dut::new(runtime.create_model_simple::<OneUnitCluster>()?)?;
+        one.reset();
+        load(&mut one, &grid);
+        run_segments(&mut one, &grid, &segments);
+
+        let mut four = Dut::new(runtime.create_model_simple::<FourUnitCluster>()?)?;
+        four.reset();
+        load(&mut four, &grid);
+        run_segments(&mut four, &grid, &segments);
+
+        // Changing the grid has to change every copy of it
+        let grid = random_grid(0.2);
+        load(&mut four, &grid);
+        run_segments(&mut four, &grid, &segments);
+
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_throughput_scales_with_units() -> Result<(), Whatever> {
+        let runtime = make_runtime()?;
+        // Nothing in the way, so every segment walks all of its cells
+        let grid = random_grid(0.0);
+        let segments = random_segments(256);
+
+        let mut one = Dut::new(runtime.create_model_simple::<OneUnitCluster>()?)?;
+        one.reset();
+        load(&mut one, &grid);
+        let one_cycles = run_segments(&mut one, &grid, &segments);
+
+        let mut two = Dut::new(runtime.create_model_simple::<TwoUnitCluster>()?)?;
+        two.reset();
+        load(&mut two, &grid);
+        let two_cycles = run_segments(&mut two, &grid, &segments);
+
+        let mut four = Dut::new(runtime.create_model_simple::<FourUnitCluster>()?)?;
+        four.reset();
+        load(&mut four, &grid);
+        let four_cycles = run_segments(&mut four, &grid, &segments);
+
+        // Not quite N times faster, since the units don't all finish at once
+        assert!(
+            two_cycles * 10 < one_cycles * 6,
+            "2 units took {} cycles, 1 unit took {}",
+            two_cycles,
+            one_cycles
+        );
+        assert!(
+            four_cycles * 10 < one_cycles * 3,
+            "4 units took {} cycles, 1 unit took {}",
+            four_cycles,
+            one_cycles
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_load_waits_for_checks() -> Result<(), Whatever> {
+        let runtime = make_runtime()?;
+        let mut dut = Dut::new(runtime.create_model_simple::<TwoUnitCluster>()?)?;
+        dut.reset();
+        load(&mut dut, &random_grid(0.0));
+
+        // Corner to corner, so both units are busy for a while
+        let (a, b) = (FixedPoint::new(0, 0), FixedPoint::new(u32::MAX, u32::MAX));
+        for tag in 0..2 {
+            let (accepted, _) = dut.cycle(Some((a.to_bits(), b.to_bits(), tag)));
+            assert!(accepted, "segment {} wasn't accepted", tag);
+        }
+
+        // Start loading a full grid while they're running. The load has to wait for both, and no
+        // new segment can get in until it's over.
+        let full = random_grid(1.0);
+        let words = loader::pack_grid(&full, GRID_WIDTH_LOG2, GRID_HEIGHT_LOG2, DATA_WIDTH);
+        let mut results = Vec::new();
+        let mut address = 0;
+        let mut cycles = 0;
+        while address < words.len() {
+            dut.put_memory_access(Some(MemoryAccess::Write(address as u64, words[address])));
+            dut.settle();
+            let granted = dut.memory_granted();
+            if granted {
+                assert_eq!(results.len(), 2, "load granted with a check still running");
+            }
+
+            let (accepted, result) = dut.cycle(Some((a.to_bits(), b.to_bits(), 2)));
+            assert!(!accepted, "segment accepted during a load");
+            results.extend(result);
+            if granted {
+                address += 1;
+            }
+
+            cycles += 1;
+            assert!(cycles < 10_000, "load never went through");
+        }
+        dut.put_memory_access(None);
+
+        // Both were checked against the empty grid, then everything after sees the full one
+        results.sort();
+        assert_eq!(results, [(0, false), (1, false)]);
+        run_segments(&mut dut, &full, &random_segments(20));
+
+        Ok(())
+    }
+}
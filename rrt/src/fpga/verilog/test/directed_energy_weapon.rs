@@ -1,132 +1,519 @@
 #[cfg(test)]
 mod tests {
-    use std::path::Path;
-
-    use marlin::verilator::{VerilatorRuntime, VerilatorRuntimeOptions};
+    use marlin::verilator::VerilatorRuntime;
     use marlin::verilog::prelude::*;
+    use na::vector;
+    use rand::RngExt;
+    use rand::rngs::ThreadRng;
     use snafu::Whatever;
 
+    use crate::fpga::fixed::{self, FixedPoint, POINT_BITS};
+    use crate::fpga::loader;
+    use crate::fpga::verilog::test::bench::{
+        self, Clocked, Dut, GridModel, GridResponder, MemoryRequester, impl_clocked, impl_grid_bus,
+        impl_memory_requester,
+    };
+    use crate::shared::grid::OccupancyGrid;
+
     const GRID_WIDTH_LOG2: u32 = 2;
     const GRID_HEIGHT_LOG2: u32 = 2;
-
-    const POINT_WIDTH: u32 = 32;
-
-    const CELL_WIDTH: u64 = (1u64 << POINT_WIDTH) >> GRID_WIDTH_LOG2;
-    const CELL_HEIGHT: u64 = (1u64 << POINT_WIDTH) >> GRID_HEIGHT_LOG2;
+    // Several words, so the grid's addressing gets tested too
+    const DATA_WIDTH: usize = 4;
 
     #[verilog(
         src = "src/fpga/verilog/test/wrappers/directed_energy_weapon_wrapper.sv",
         name = "directed_energy_weapon_wrapper",
-        params = { GRID_WIDTH_LOG2: 2, GRID_HEIGHT_LOG2: 2 },
+        params = { GRID_WIDTH_LOG2: 2, GRID_HEIGHT_LOG2: 2, POINT_BITS: 32 },
         includes = ["src/fpga/verilog/src/"]
     )]
     pub struct DewWrapper;
 
-    impl<'ctx> DewWrapper<'ctx> {
-        fn tick(&mut self) {
-            self.clk = 1;
-            self.eval();
-            self.clk = 0;
-            self.eval();
-        }
-    }
+    #[verilog(
+        src = "src/fpga/verilog/test/wrappers/directed_energy_weapon_wrapper.sv",
+        name = "directed_energy_weapon_wrapper",
+        params = { GRID_WIDTH_LOG2: 2, GRID_HEIGHT_LOG2: 2, POINT_BITS: 16 },
+        includes = ["src/fpga/verilog/src/"]
+    )]
+    pub struct Dew16Wrapper;
+
+    #[verilog(
+        src = "src/fpga/verilog/test/wrappers/directed_energy_weapon_grid_wrapper.sv",
+        name = "directed_energy_weapon_grid_wrapper",
+        params = {
+            GRID_WIDTH_LOG2: 2,
+            GRID_HEIGHT_LOG2: 2,
+            POINT_BITS: 32,
+            ADDR_WIDTH: 2,
+            DATA_WIDTH: 4
+        },
+        includes = ["src/fpga/verilog/src/"]
+    )]
+    pub struct DewGridWrapper;
+
+    #[verilog(
+        src = "src/fpga/verilog/test/wrappers/directed_energy_weapon_grid_wrapper.sv",
+        name = "directed_energy_weapon_grid_wrapper",
+        params = {
+            GRID_WIDTH_LOG2: 2,
+            GRID_HEIGHT_LOG2: 2,
+            POINT_BITS: 16,
+            ADDR_WIDTH: 2,
+            DATA_WIDTH: 4
+        },
+        includes = ["src/fpga/verilog/src/"]
+    )]
+    pub struct Dew16GridWrapper;
+
+    /// A point in the DEW's own width.
+    type Point = (u64, u64);
+
+    /// Runs every point width through the same tests.
+    trait Dew {
+        /// Width of each coordinate of a point.
+        const POINT_BITS: u32;
 
-    struct MockGrid {
-        cells: Vec<Vec<bool>>,
+        fn reset(&mut self);
+
+        /// Traces the segment from `a` to `b`, returning `occupied`.
+        fn trace(&mut self, grid: &mut GridModel, a: Point, b: Point, mark: bool) -> u8;
     }
 
-    impl MockGrid {
-        fn new() -> Self {
-            let w = 1usize << GRID_WIDTH_LOG2;
-            let h = 1usize << GRID_HEIGHT_LOG2;
-            Self {
-                cells: vec![vec![false; w]; h],
+    macro_rules! impl_dew {
+        ($wrapper:ident, $point_bits:expr) => {
+            impl_clocked!($wrapper);
+            impl_grid_bus!(GridResponder for $wrapper {
+                cell_x: grid_cell_x,
+                cell_y: grid_cell_y,
+                input_valid: grid_input_valid,
+                ready_for_input: grid_ready_for_input,
+                write_enable: grid_write_enable,
+                write_occupied: grid_write_occupied,
+                output_valid: grid_output_valid,
+                read_occupied: grid_read_occupied,
+            });
+
+            impl<'ctx> Dew for Dut<$wrapper<'ctx>> {
+                const POINT_BITS: u32 = $point_bits;
+
+                fn reset(&mut self) {
+                    self.a = 0;
+                    self.b = 0;
+                    self.mark = 0;
+                    self.input_valid = 0;
+                    self.put_grid_response(false, None);
+                    self.pulse_reset();
+                }
+
+                fn trace(&mut self, grid: &mut GridModel, a: Point, b: Point, mark: bool) -> u8 {
+                    self.a = ((a.0 << $point_bits) | a.1) as _;
+                    self.b = ((b.0 << $point_bits) | b.1) as _;
+                    self.mark = mark as u8;
+                    self.input_valid = 1;
+                    grid.respond(self);
+                    self.tick();
+                    self.input_valid = 0;
+
+                    while self.done == 0 {
+                        grid.respond(self);
+                        self.tick();
+                    }
+
+                    self.occupied
+                }
             }
-        }
+        };
+    }
 
-        fn tick(&mut self, dut: &mut DewWrapper) {
-            dut.grid_ready_for_input = 1;
+    impl_dew!(DewWrapper, 32);
+    impl_dew!(Dew16Wrapper, 16);
 
-            if dut.grid_input_valid != 0 {
-                let x = dut.grid_cell_x as usize;
-                let y = dut.grid_cell_y as usize;
-                if dut.grid_write_enable != 0 {
-                    dut.grid_output_valid = 0;
-                    self.cells[y][x] = dut.grid_write_occupied != 0;
-                } else {
-                    dut.grid_output_valid = 1;
-                    dut.grid_read_occupied = if self.cells[y][x] { 1 } else { 0 };
+    /// The same, for a DEW on a real `occupancy_grid` and `bram`. The grid passed to `trace` gets
+    /// loaded through the host port first, and read back afterwards if anything was marked, so
+    /// the tests can't tell the difference except in how long it takes.
+    macro_rules! impl_dew_grid {
+        ($wrapper:ident, $point_bits:expr) => {
+            impl_clocked!($wrapper);
+            impl_memory_requester!($wrapper {
+                request: host_request,
+                grant: host_grant,
+                address: host_address,
+                write_data: host_write_data,
+                write_enable: host_write_enable,
+                read_data: host_read_data,
+            });
+
+            impl<'ctx> Dew for Dut<$wrapper<'ctx>> {
+                const POINT_BITS: u32 = $point_bits;
+
+                fn reset(&mut self) {
+                    self.a = 0;
+                    self.b = 0;
+                    self.mark = 0;
+                    self.input_valid = 0;
+                    self.put_memory_access(None);
+                    self.pulse_reset();
+                }
+
+                fn trace(&mut self, grid: &mut GridModel, a: Point, b: Point, mark: bool) -> u8 {
+                    load_grid(self, grid);
+
+                    self.a = ((a.0 << $point_bits) | a.1) as _;
+                    self.b = ((b.0 << $point_bits) | b.1) as _;
+                    self.mark = mark as u8;
+                    self.input_valid = 1;
+                    self.tick();
+                    self.input_valid = 0;
+
+                    while self.done == 0 {
+                        self.tick();
+                    }
+
+                    if mark {
+                        // The last write can still be in the grid's buffer, and the host port
+                        // would get the bram ahead of it
+                        while self.grid_idle == 0 {
+                            self.tick();
+                        }
+                        read_back_grid(self, grid);
+                    }
+
+                    self.occupied
                 }
             }
-        }
+        };
+    }
+
+    impl_dew_grid!(DewGridWrapper, 32);
+    impl_dew_grid!(Dew16GridWrapper, 16);
+
+    fn empty_grid() -> GridModel {
+        GridModel::new(1 << GRID_WIDTH_LOG2, 1 << GRID_HEIGHT_LOG2)
     }
 
     fn make_runtime() -> Result<VerilatorRuntime, Whatever> {
-        VerilatorRuntime::new2(
-            "build",
-            &["src/fpga/verilog/test/wrappers/directed_energy_weapon_wrapper.sv"],
-            &[Path::new("src/fpga/verilog/src/")],
-            [],
-            VerilatorRuntimeOptions::default(),
+        bench::runtime("src/fpga/verilog/test/wrappers/directed_energy_weapon_wrapper.sv")
+    }
+
+    fn make_grid_runtime() -> Result<VerilatorRuntime, Whatever> {
+        bench::runtime("src/fpga/verilog/test/wrappers/directed_energy_weapon_grid_wrapper.sv")
+    }
+
+    /// Writes `grid` into the bram through the host port, packed the same way the host does it.
+    fn load_grid(dut: &mut impl MemoryRequester, grid: &GridModel) {
+        let words = loader::pack_grid(
+            &grid_with(|x, y| grid.cells[y][x]),
+            GRID_WIDTH_LOG2,
+            GRID_HEIGHT_LOG2,
+            DATA_WIDTH,
+        );
+        for (address, word) in words.into_iter().enumerate() {
+            assert!(
+                dut.write_word(address as u64, word),
+                "load of word {} wasn't granted",
+                address
+            );
+        }
+    }
+
+    /// Reads every cell of the bram back into `grid`. The grid has to be done writing to it.
+    fn read_back_grid(dut: &mut impl MemoryRequester, grid: &mut GridModel) {
+        let word_count = (1 << (GRID_WIDTH_LOG2 + GRID_HEIGHT_LOG2)) / DATA_WIDTH;
+        for address in 0..word_count {
+            let word = dut.read_word(address as u64);
+            for bit in 0..DATA_WIDTH {
+                let linear = address * DATA_WIDTH + bit;
+                let (x, y) = (linear % (1 << GRID_WIDTH_LOG2), linear >> GRID_WIDTH_LOG2);
+                grid.cells[y][x] = (word >> bit) & 1 != 0;
+            }
+        }
+    }
+
+    fn cell_size<D: Dew>() -> (u64, u64) {
+        (
+            (1 << D::POINT_BITS) >> GRID_WIDTH_LOG2,
+            (1 << D::POINT_BITS) >> GRID_HEIGHT_LOG2,
         )
     }
 
-    fn reset(dut: &mut DewWrapper) {
-        dut.rst_n = 0;
-        dut.a = 0;
-        dut.b = 0;
-        dut.input_valid = 0;
-        dut.grid_output_valid = 0;
-        dut.grid_ready_for_input = 0;
-        dut.grid_read_occupied = 0;
-        dut.clk = 0;
-        dut.eval();
-        dut.tick();
-        dut.rst_n = 1;
-        dut.tick();
+    fn cell_center<D: Dew>(cx: u64, cy: u64) -> Point {
+        let (width, height) = cell_size::<D>();
+        (cx * width + width / 2, cy * height + height / 2)
+    }
+
+    /// Scales a point up to the reference's width. The cells are still at the same places
+    /// relative to it, so it walks the same cells.
+    fn to_fixed<D: Dew>(p: Point) -> FixedPoint {
+        let shift = POINT_BITS - D::POINT_BITS;
+        FixedPoint::new((p.0 << shift) as u32, (p.1 << shift) as u32)
+    }
+
+    fn is_segment_occupied<D: Dew>(a: Point, b: Point, grid: &OccupancyGrid) -> u8 {
+        fixed::is_segment_occupied(&to_fixed::<D>(a), &to_fixed::<D>(b), grid) as u8
+    }
+
+    /// Coordinates on and just below every cell boundary along one axis, plus the largest one.
+    fn boundary_coords<D: Dew>(cell_size: u64, cells: u64) -> Vec<u64> {
+        let mut coords = vec![0];
+        for i in 1..cells {
+            coords.push(i * cell_size - 1);
+            coords.push(i * cell_size);
+        }
+        coords.push((1 << D::POINT_BITS) - 1);
+        coords
     }
 
-    fn point_bits(x: u64, y: u64) -> u64 {
-        (x << POINT_WIDTH) | y
+    fn boundary_points<D: Dew>() -> Vec<Point> {
+        let (width, height) = cell_size::<D>();
+        let xs = boundary_coords::<D>(width, 1 << GRID_WIDTH_LOG2);
+        let ys = boundary_coords::<D>(height, 1 << GRID_HEIGHT_LOG2);
+        xs.iter()
+            .flat_map(|&x| ys.iter().map(move |&y| (x, y)))
+            .collect()
     }
 
-    fn cell_center(cx: u64, cy: u64) -> (u64, u64) {
+    fn random_point<D: Dew>(rng: &mut ThreadRng) -> Point {
         (
-            cx * CELL_WIDTH + CELL_WIDTH / 2,
-            cy * CELL_HEIGHT + CELL_HEIGHT / 2,
+            rng.random_range(0..1 << D::POINT_BITS),
+            rng.random_range(0..1 << D::POINT_BITS),
         )
     }
 
-    fn get_occupied(dut: &mut DewWrapper, grid: &mut MockGrid, a: u64, b: u64) -> u8 {
-        while dut.done == 1 {
-            grid.tick(dut);
-            dut.tick();
+    fn grid_with(mut occupied: impl FnMut(usize, usize) -> bool) -> OccupancyGrid {
+        let mut grid = OccupancyGrid::new(
+            1 << GRID_WIDTH_LOG2,
+            1 << GRID_HEIGHT_LOG2,
+            vector![0.0, 0.0],
+            1.0,
+        );
+        for y in 0..1 << GRID_HEIGHT_LOG2 {
+            for x in 0..1 << GRID_WIDTH_LOG2 {
+                *grid.cell_mut(x, y) = occupied(x, y);
+            }
         }
+        grid
+    }
+
+    fn check_empty(dut: &mut impl Dew) {
+        dut.reset();
+
+        let mut grid = empty_grid();
+        assert_eq!(dut.trace(&mut grid, (0, 0), (0, 0), false), 0);
+    }
+
+    fn check_single_point<D: Dew>(dut: &mut D) {
+        dut.reset();
+
+        let mut grid = empty_grid();
+        grid.cells[2][2] = true;
+
+        let a = cell_center::<D>(2, 2);
+        assert_eq!(dut.trace(&mut grid, a, a, false), 1);
+    }
+
+    fn check_corner_needs_one_free_neighbour<D: Dew>(dut: &mut D) {
+        dut.reset();
+
+        let a = cell_center::<D>(0, 0);
+        let b = cell_center::<D>(1, 1);
+
+        // Only the x neighbour blocked, so we have to go around it through y
+        let mut grid = empty_grid();
+        grid.cells[0][1] = true;
+        assert_eq!(dut.trace(&mut grid, a, b, false), 0);
+        assert_eq!(dut.trace(&mut grid, b, a, false), 0);
+
+        // Only the y neighbour blocked
+        let mut grid = empty_grid();
+        grid.cells[1][0] = true;
+        assert_eq!(dut.trace(&mut grid, a, b, false), 0);
+        assert_eq!(dut.trace(&mut grid, b, a, false), 0);
+
+        // Both blocked, so there's no gap to squeeze through
+        grid.cells[0][1] = true;
+        assert_eq!(dut.trace(&mut grid, a, b, false), 1);
+        assert_eq!(dut.trace(&mut grid, b, a, false), 1);
+    }
+
+    /// A point `(x, y)` quarter cells from the origin.
+    fn quarter_cells<D: Dew>(x: u64, y: u64) -> Point {
+        let (width, height) = cell_size::<D>();
+        (x * width / 4, y * height / 4)
+    }
+
+    /// When delta.x and delta.y have different signs, the t to each next boundary has to be
+    /// compared by magnitude. Comparing the signed products flips it and steps the wrong way.
+    fn check_mixed_sign_slopes<D: Dew>(dut: &mut D) {
+        dut.reset();
+
+        // Goes (0, 3), (1, 3), (1, 2), (2, 2), (2, 1), (3, 1), so it drops out of row 3 before
+        // reaching column 2
+        let a = quarter_cells::<D>(2, 14);
+        let b = quarter_cells::<D>(14, 6);
 
-        dut.a = a;
-        dut.b = b;
-        dut.input_valid = 1;
+        let mut grid = empty_grid();
+        grid.cells[3][2] = true;
+        assert_eq!(dut.trace(&mut grid, a, b, false), 0);
+        assert_eq!(dut.trace(&mut grid, b, a, false), 0);
 
-        while dut.done == 0 {
-            grid.tick(dut);
-            dut.tick();
+        let mut grid = empty_grid();
+        grid.cells[2][2] = true;
+        assert_eq!(dut.trace(&mut grid, a, b, false), 1);
+        assert_eq!(dut.trace(&mut grid, b, a, false), 1);
+    }
+
+    /// The boundary after the last cell is at 2^POINT_BITS. If it wraps to 0, a segment ending in
+    /// the last cell doesn't see that it's done, and walks on into cell 0.
+    fn check_last_cell<D: Dew>(dut: &mut D) {
+        dut.reset();
+
+        let last = (1 << D::POINT_BITS) - 1;
+
+        let mut grid = empty_grid();
+        grid.cells[3][0] = true;
+        grid.cells[0][3] = true;
+        assert_eq!(
+            dut.trace(
+                &mut grid,
+                cell_center::<D>(1, 3),
+                (last, cell_center::<D>(1, 3).1),
+                false
+            ),
+            0
+        );
+        assert_eq!(
+            dut.trace(
+                &mut grid,
+                cell_center::<D>(3, 1),
+                (cell_center::<D>(3, 1).0, last),
+                false
+            ),
+            0
+        );
+    }
+
+    /// Checks every segment between cell centers that goes exactly along a diagonal, so it passes
+    /// through corners, against the reference.
+    fn check_diagonals<D: Dew>(dut: &mut D, grid: &OccupancyGrid) {
+        let mut mock = GridModel::from_grid(grid);
+        let cells = 1i64 << GRID_WIDTH_LOG2;
+
+        for ax in 0..cells {
+            for ay in 0..cells {
+                for (dir_x, dir_y) in [(1, 1), (1, -1), (-1, 1), (-1, -1)] {
+                    for len in 1..cells {
+                        let (bx, by) = (ax + dir_x * len, ay + dir_y * len);
+                        if !(0..cells).contains(&bx) || !(0..cells).contains(&by) {
+                            continue;
+                        }
+
+                        let a = cell_center::<D>(ax as u64, ay as u64);
+                        let b = cell_center::<D>(bx as u64, by as u64);
+                        assert_eq!(
+                            dut.trace(&mut mock, a, b, false),
+                            is_segment_occupied::<D>(a, b, grid),
+                            "segment from ({}, {}) to ({}, {})",
+                            ax,
+                            ay,
+                            bx,
+                            by
+                        );
+                    }
+                }
+            }
         }
+    }
+
+    fn check_checkerboard_diagonals(dut: &mut impl Dew) {
+        dut.reset();
+
+        // Every diagonal only touches one colour, and is walled in on both sides by the other
+        check_diagonals(dut, &grid_with(|x, y| (x + y) % 2 == 1));
+        check_diagonals(dut, &grid_with(|x, y| (x + y) % 2 == 0));
 
-        dut.occupied
+        // Stripes leave one side of each corner open
+        check_diagonals(dut, &grid_with(|x, _| x % 2 == 1));
+        check_diagonals(dut, &grid_with(|_, y| y % 2 == 1));
+    }
+
+    /// Checks every segment between two points on cell boundaries against the reference. That
+    /// includes zero-length segments, axis-aligned ones, and ones that start or end on a boundary
+    /// from either side.
+    fn check_boundary_endpoints<D: Dew>(dut: &mut D, grid: &OccupancyGrid) {
+        let mut mock = GridModel::from_grid(grid);
+        let points = boundary_points::<D>();
+
+        for &a in &points {
+            for &b in &points {
+                assert_eq!(
+                    dut.trace(&mut mock, a, b, false),
+                    is_segment_occupied::<D>(a, b, grid),
+                    "segment from {:?} to {:?}",
+                    a,
+                    b
+                );
+            }
+        }
+    }
+
+    fn check_all_boundary_endpoints(dut: &mut impl Dew) {
+        dut.reset();
+
+        check_boundary_endpoints(dut, &grid_with(|_, _| false));
+        check_boundary_endpoints(dut, &grid_with(|x, y| x == 2 && y == 1));
+        check_boundary_endpoints(dut, &grid_with(|x, y| (x + y) % 2 == 1));
+        check_boundary_endpoints(dut, &grid_with(|x, y| (x * 3 + y * 5) % 7 < 2));
+    }
+
+    fn check_mark_matches_reference<D: Dew>(dut: &mut D) {
+        dut.reset();
+
+        let mut rng = rand::rng();
+        let boundary = boundary_points::<D>();
+
+        // Keep applying rays to the same map, so every write lands on top of earlier ones
+        let mut grid = grid_with(|_, _| rng.random_bool(0.5));
+        let mut mock = GridModel::from_grid(&grid);
+
+        for i in 0..2000 {
+            // Half of them on boundaries, to go through corners and end on edges
+            let random_end = |rng: &mut ThreadRng| {
+                if rng.random_bool(0.5) {
+                    boundary[rng.random_range(0..boundary.len())]
+                } else {
+                    random_point::<D>(rng)
+                }
+            };
+            let (a, b) = (random_end(&mut rng), random_end(&mut rng));
+
+            fixed::apply_ray(&to_fixed::<D>(a), &to_fixed::<D>(b), &mut grid);
+            assert_eq!(dut.trace(&mut mock, a, b, true), 0);
+
+            assert_eq!(
+                mock.cells,
+                GridModel::from_grid(&grid).cells,
+                "ray {} from {:?} to {:?}",
+                i,
+                a,
+                b
+            );
+
+            // Reads still work in between
+            assert_eq!(
+                dut.trace(&mut mock, b, a, false),
+                is_segment_occupied::<D>(b, a, &grid)
+            );
+        }
     }
 
     #[test]
     #[snafu::report]
     fn test_empty() -> Result<(), Whatever> {
         let runtime = make_runtime()?;
-        let mut dut = runtime.create_model_simple::<DewWrapper>()?;
-
-        let _grid = MockGrid::new();
-        reset(&mut dut);
 
-        let _a = point_bits(0, 0);
-        let _b = point_bits(0, 0);
-        // The python test has the assertion commented out; mirror that.
+        check_empty(&mut Dut::new(runtime.create_model_simple::<DewWrapper>()?)?);
+        check_empty(&mut Dut::new(
+            runtime.create_model_simple::<Dew16Wrapper>()?,
+        )?);
 
         Ok(())
     }
@@ -135,18 +522,211 @@ mod tests {
     #[snafu::report]
     fn test_single_point() -> Result<(), Whatever> {
         let runtime = make_runtime()?;
-        let mut dut = runtime.create_model_simple::<DewWrapper>()?;
 
-        let mut grid = MockGrid::new();
-        grid.cells[2][2] = true;
+        check_single_point(&mut Dut::new(runtime.create_model_simple::<DewWrapper>()?)?);
+        check_single_point(&mut Dut::new(
+            runtime.create_model_simple::<Dew16Wrapper>()?,
+        )?);
+
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_corner_needs_one_free_neighbour() -> Result<(), Whatever> {
+        let runtime = make_runtime()?;
+
+        check_corner_needs_one_free_neighbour(&mut Dut::new(
+            runtime.create_model_simple::<DewWrapper>()?,
+        )?);
+        check_corner_needs_one_free_neighbour(&mut Dut::new(
+            runtime.create_model_simple::<Dew16Wrapper>()?,
+        )?);
+
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_mixed_sign_slopes() -> Result<(), Whatever> {
+        let runtime = make_runtime()?;
+
+        check_mixed_sign_slopes(&mut Dut::new(runtime.create_model_simple::<DewWrapper>()?)?);
+        check_mixed_sign_slopes(&mut Dut::new(
+            runtime.create_model_simple::<Dew16Wrapper>()?,
+        )?);
+
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_last_cell() -> Result<(), Whatever> {
+        let runtime = make_runtime()?;
+
+        check_last_cell(&mut Dut::new(runtime.create_model_simple::<DewWrapper>()?)?);
+        check_last_cell(&mut Dut::new(
+            runtime.create_model_simple::<Dew16Wrapper>()?,
+        )?);
+
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_diagonals_through_checkerboard() -> Result<(), Whatever> {
+        let runtime = make_runtime()?;
+
+        check_checkerboard_diagonals(&mut Dut::new(runtime.create_model_simple::<DewWrapper>()?)?);
+        check_checkerboard_diagonals(&mut Dut::new(
+            runtime.create_model_simple::<Dew16Wrapper>()?,
+        )?);
+
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_boundary_endpoints() -> Result<(), Whatever> {
+        let runtime = make_runtime()?;
+
+        check_all_boundary_endpoints(&mut Dut::new(runtime.create_model_simple::<DewWrapper>()?)?);
+        check_all_boundary_endpoints(&mut Dut::new(
+            runtime.create_model_simple::<Dew16Wrapper>()?,
+        )?);
+
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_mark_matches_reference() -> Result<(), Whatever> {
+        let runtime = make_runtime()?;
 
-        reset(&mut dut);
+        check_mark_matches_reference(&mut Dut::new(runtime.create_model_simple::<DewWrapper>()?)?);
+        check_mark_matches_reference(&mut Dut::new(
+            runtime.create_model_simple::<Dew16Wrapper>()?,
+        )?);
 
-        let (cx, cy) = cell_center(2, 2);
-        let a = point_bits(cx, cy);
-        let b = a;
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_real_grid_empty() -> Result<(), Whatever> {
+        let runtime = make_grid_runtime()?;
+
+        check_empty(&mut Dut::new(
+            runtime.create_model_simple::<DewGridWrapper>()?,
+        )?);
+        check_empty(&mut Dut::new(
+            runtime.create_model_simple::<Dew16GridWrapper>()?,
+        )?);
+
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_real_grid_single_point() -> Result<(), Whatever> {
+        let runtime = make_grid_runtime()?;
+
+        check_single_point(&mut Dut::new(
+            runtime.create_model_simple::<DewGridWrapper>()?,
+        )?);
+        check_single_point(&mut Dut::new(
+            runtime.create_model_simple::<Dew16GridWrapper>()?,
+        )?);
+
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_real_grid_corner_needs_one_free_neighbour() -> Result<(), Whatever> {
+        let runtime = make_grid_runtime()?;
+
+        check_corner_needs_one_free_neighbour(&mut Dut::new(
+            runtime.create_model_simple::<DewGridWrapper>()?,
+        )?);
+        check_corner_needs_one_free_neighbour(&mut Dut::new(
+            runtime.create_model_simple::<Dew16GridWrapper>()?,
+        )?);
+
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_real_grid_mixed_sign_slopes() -> Result<(), Whatever> {
+        let runtime = make_grid_runtime()?;
+
+        check_mixed_sign_slopes(&mut Dut::new(
+            runtime.create_model_simple::<DewGridWrapper>()?,
+        )?);
+        check_mixed_sign_slopes(&mut Dut::new(
+            runtime.create_model_simple::<Dew16GridWrapper>()?,
+        )?);
+
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_real_grid_last_cell() -> Result<(), Whatever> {
+        let runtime = make_grid_runtime()?;
+
+        check_last_cell(&mut Dut::new(
+            runtime.create_model_simple::<DewGridWrapper>()?,
+        )?);
+        check_last_cell(&mut Dut::new(
+            runtime.create_model_simple::<Dew16GridWrapper>()?,
+        )?);
+
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_real_grid_diagonals_through_checkerboard() -> Result<(), Whatever> {
+        let runtime = make_grid_runtime()?;
+
+        check_checkerboard_diagonals(&mut Dut::new(
+            runtime.create_model_simple::<DewGridWrapper>()?,
+        )?);
+        check_checkerboard_diagonals(&mut Dut::new(
+            runtime.create_model_simple::<Dew16GridWrapper>()?,
+        )?);
+
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_real_grid_boundary_endpoints() -> Result<(), Whatever> {
+        let runtime = make_grid_runtime()?;
+
+        check_all_boundary_endpoints(&mut Dut::new(
+            runtime.create_model_simple::<DewGridWrapper>()?,
+        )?);
+        check_all_boundary_endpoints(&mut Dut::new(
+            runtime.create_model_simple::<Dew16GridWrapper>()?,
+        )?);
+
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_real_grid_mark_matches_reference() -> Result<(), Whatever> {
+        let runtime = make_grid_runtime()?;
 
-        assert_eq!(get_occupied(&mut dut, &mut grid, a, b), 1);
+        check_mark_matches_reference(&mut Dut::new(
+            runtime.create_model_simple::<DewGridWrapper>()?,
+        )?);
+        check_mark_matches_reference(&mut Dut::new(
+            runtime.create_model_simple::<Dew16GridWrapper>()?,
+        )?);
 
         Ok(())
     }
@@ -0,0 +1,204 @@
+#[cfg(test)]
+mod tests {
+    use marlin::verilator::VerilatorRuntime;
+    use marlin::verilog::prelude::*;
+    use na::vector;
+    use rand::RngExt;
+    use snafu::{Whatever, whatever};
+
+    use crate::fpga::loader;
+    use crate::fpga::verilog::test::bench::{
+        self, Clocked, Dut, GridRequester, impl_clocked, impl_grid_bus,
+    };
+    use crate::shared::grid::OccupancyGrid;
+
+    const CLKS_PER_BIT: usize = 4;
+    const GRID_WIDTH_LOG2: u32 = 4;
+    const GRID_HEIGHT_LOG2: u32 = 4;
+    const DATA_WIDTH: usize = 16;
+
+    #[verilog(
+        src = "src/fpga/verilog/test/wrappers/grid_loader_wrapper.sv",
+        name = "grid_loader_wrapper",
+        params = {
+            CLKS_PER_BIT: 4,
+            GRID_WIDTH_LOG2: 4,
+            GRID_HEIGHT_LOG2: 4,
+            DATA_WIDTH: 16,
+            ADDR_WIDTH: 4
+        },
+        includes = ["src/fpga/verilog/src/"]
+    )]
+    pub struct GridLoaderWrapper;
+
+    impl_clocked!(GridLoaderWrapper);
+    impl_grid_bus!(GridRequester for GridLoaderWrapper {
+        cell_x: cell_x,
+        cell_y: cell_y,
+        input_valid: input_valid,
+        ready_for_input: ready_for_input,
+        write_enable: write_enable,
+        write_occupied: write_occupied,
+        output_valid: output_valid,
+        read_occupied: read_occupied,
+    });
+
+    impl<'ctx> Dut<GridLoaderWrapper<'ctx>> {
+        fn reset(&mut self) {
+            self.rx = 1;
+            self.load_start = 0;
+            self.put_grid_access(None);
+            self.pulse_reset();
+        }
+
+        /// Holds rx for one bit, returning whether framing_error went up.
+        fn send_bit(&mut self, bit: u8) -> bool {
+            let mut framing_error = false;
+            self.rx = bit;
+            for _ in 0..CLKS_PER_BIT {
+                self.tick();
+                framing_error |= self.framing_error != 0;
+            }
+            framing_error
+        }
+
+        fn send_byte(&mut self, byte: u8, stop_bit: u8) -> bool {
+            let mut framing_error = self.send_bit(0);
+            for i in 0..8 {
+                framing_error |= self.send_bit((byte >> i) & 1);
+            }
+            framing_error |= self.send_bit(stop_bit);
+
+            // Give the stop bit time to get through the synchronizer
+            self.rx = 1;
+            for _ in 0..4 {
+                self.tick();
+                framing_error |= self.framing_error != 0;
+            }
+            framing_error
+        }
+
+        fn load(&mut self, grid: &OccupancyGrid) -> Result<(), Whatever> {
+            self.load_start = 1;
+            self.tick();
+            self.load_start = 0;
+
+            for byte in loader::encode_grid(grid, GRID_WIDTH_LOG2, GRID_HEIGHT_LOG2, DATA_WIDTH) {
+                if self.send_byte(byte, 1) {
+                    whatever!("framing error on a good byte");
+                }
+            }
+
+            for _ in 0..10 {
+                if self.load_done != 0 {
+                    return Ok(());
+                }
+                self.tick();
+            }
+            whatever!("load didn't finish after the last byte")
+        }
+
+        /// Reads back every cell of the hardware grid. Cells outside of `grid` should be occupied.
+        fn check_grid(&mut self, grid: &OccupancyGrid) {
+            let (x_cells, y_cells) = grid.size();
+            for y in 0..1 << GRID_HEIGHT_LOG2 {
+                for x in 0..1 << GRID_WIDTH_LOG2 {
+                    let expected = x >= x_cells || y >= y_cells || *grid.cell(x, y);
+                    assert_eq!(self.read_cell(x, y), expected, "mismatch at ({}, {})", x, y);
+                }
+            }
+        }
+    }
+
+    fn make_runtime() -> Result<VerilatorRuntime, Whatever> {
+        bench::runtime("src/fpga/verilog/test/wrappers/grid_loader_wrapper.sv")
+    }
+
+    fn random_grid(x_cells: usize, y_cells: usize) -> OccupancyGrid {
+        let mut rng = rand::rng();
+        let mut grid = OccupancyGrid::new(x_cells, y_cells, vector![0.0, 0.0], 1.0);
+        for y in 0..y_cells {
+            for x in 0..x_cells {
+                *grid.cell_mut(x, y) = rng.random_bool(0.3);
+            }
+        }
+        grid
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_load_and_read_back() -> Result<(), Whatever> {
+        let runtime = make_runtime()?;
+        let mut dut = Dut::new(runtime.create_model_simple::<GridLoaderWrapper>()?)?;
+
+        dut.reset();
+
+        let grid = random_grid(1 << GRID_WIDTH_LOG2, 1 << GRID_HEIGHT_LOG2);
+        dut.load(&grid)?;
+        dut.check_grid(&grid);
+
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_reload_smaller_grid() -> Result<(), Whatever> {
+        let runtime = make_runtime()?;
+        let mut dut = Dut::new(runtime.create_model_simple::<GridLoaderWrapper>()?)?;
+
+        dut.reset();
+
+        let empty = OccupancyGrid::new(
+            1 << GRID_WIDTH_LOG2,
+            1 << GRID_HEIGHT_LOG2,
+            vector![0.0, 0.0],
+            1.0,
+        );
+        dut.load(&empty)?;
+        dut.check_grid(&empty);
+
+        // Has to overwrite everything from the last load, including the padding
+        let grid = random_grid(10, 13);
+        dut.load(&grid)?;
+        dut.check_grid(&grid);
+
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_framing_error_drops_byte() -> Result<(), Whatever> {
+        let runtime = make_runtime()?;
+        let mut dut = Dut::new(runtime.create_model_simple::<GridLoaderWrapper>()?)?;
+
+        dut.reset();
+
+        let grid = random_grid(1 << GRID_WIDTH_LOG2, 1 << GRID_HEIGHT_LOG2);
+        let bytes = loader::encode_grid(&grid, GRID_WIDTH_LOG2, GRID_HEIGHT_LOG2, DATA_WIDTH);
+
+        dut.load_start = 1;
+        dut.tick();
+        dut.load_start = 0;
+
+        // A bad byte in the middle of the stream shouldn't count towards the grid
+        let (first_half, second_half) = bytes.split_at(bytes.len() / 2);
+        for &byte in first_half {
+            assert!(!dut.send_byte(byte, 1));
+        }
+        assert!(
+            dut.send_byte(0xff, 0),
+            "no framing error for a low stop bit"
+        );
+        for &byte in second_half {
+            assert!(!dut.send_byte(byte, 1));
+        }
+
+        for _ in 0..10 {
+            dut.tick();
+        }
+        assert_eq!(dut.load_done, 1);
+        dut.check_grid(&grid);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,272 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use marlin::verilator::VerilatorRuntime;
+    use marlin::verilog::prelude::*;
+    use rand::RngExt;
+    use snafu::Whatever;
+
+    use crate::fpga::verilog::test::bench::{self, Clocked, Dut, impl_clocked};
+
+    const NUM_CLIENTS: usize = 3;
+    const ADDR_WIDTH: u32 = 4;
+    const DATA_WIDTH: u32 = 8;
+
+    #[verilog(
+        src = "src/fpga/verilog/test/wrappers/memory_arbiter_wrapper.sv",
+        name = "memory_arbiter_wrapper",
+        params = { NUM_CLIENTS: 3, ROUND_ROBIN: 1, ADDR_WIDTH: 4, DATA_WIDTH: 8 },
+        includes = ["src/fpga/verilog/src/"]
+    )]
+    pub struct RoundRobinArbiter;
+
+    #[verilog(
+        src = "src/fpga/verilog/test/wrappers/memory_arbiter_wrapper.sv",
+        name = "memory_arbiter_wrapper",
+        params = { NUM_CLIENTS: 3, ROUND_ROBIN: 0, ADDR_WIDTH: 4, DATA_WIDTH: 8 },
+        includes = ["src/fpga/verilog/src/"]
+    )]
+    pub struct FixedPriorityArbiter;
+
+    #[derive(Clone, Copy, Debug)]
+    enum Access {
+        Read(u8),
+        Write(u8, u8),
+    }
+
+    /// Runs both wrappers through the same tests.
+    trait Arbiter {
+        fn reset(&mut self);
+
+        /// Puts the front of each client's queue on the bus for one cycle. Returns which clients
+        /// got a grant, and the read data that came back.
+        fn cycle(&mut self, accesses: &[Option<Access>]) -> (u8, u8);
+    }
+
+    macro_rules! impl_arbiter {
+        ($wrapper:ident) => {
+            impl_clocked!($wrapper);
+
+            impl<'ctx> Arbiter for Dut<$wrapper<'ctx>> {
+                fn reset(&mut self) {
+                    self.request = 0;
+                    self.write_enable = 0;
+                    self.pulse_reset();
+                }
+
+                fn cycle(&mut self, accesses: &[Option<Access>]) -> (u8, u8) {
+                    let mut request = 0;
+                    let mut write_enable = 0;
+                    let mut address = 0;
+                    let mut write_data = 0;
+
+                    for (i, access) in accesses.iter().enumerate() {
+                        let (addr, data, write) = match access {
+                            None => continue,
+                            Some(Access::Read(addr)) => (*addr, 0, false),
+                            Some(Access::Write(addr, data)) => (*addr, *data, true),
+                        };
+
+                        request |= 1 << i;
+                        write_enable |= (write as u8) << i;
+                        address |= (addr as u16) << (i as u32 * ADDR_WIDTH);
+                        write_data |= (data as u32) << (i as u32 * DATA_WIDTH);
+                    }
+
+                    self.request = request;
+                    self.write_enable = write_enable;
+                    self.address = address;
+                    self.write_data = write_data;
+                    self.eval();
+
+                    let grant = self.grant;
+                    self.tick();
+
+                    (grant, self.read_data)
+                }
+            }
+        };
+    }
+
+    impl_arbiter!(RoundRobinArbiter);
+    impl_arbiter!(FixedPriorityArbiter);
+
+    fn make_runtime() -> Result<VerilatorRuntime, Whatever> {
+        bench::runtime("src/fpga/verilog/test/wrappers/memory_arbiter_wrapper.sv")
+    }
+
+    /// Runs every client's queue to empty, checking every read against a model of the memory. The
+    /// model only changes on a grant, so any write that gets dropped or applied twice shows up as a
+    /// bad read later on.
+    fn run_to_completion(
+        dut: &mut impl Arbiter,
+        queues: &mut [VecDeque<Access>],
+        memory: &mut [u8],
+    ) {
+        while queues.iter().any(|queue| !queue.is_empty()) {
+            let accesses: Vec<_> = queues.iter().map(|queue| queue.front().copied()).collect();
+            let (grant, read_data) = dut.cycle(&accesses);
+
+            let is_idle = accesses.iter().all(Option::is_none);
+            assert!(grant.count_ones() <= 1, "more than one grant: {:#b}", grant);
+            assert!(is_idle || grant != 0, "nobody granted with {:?}", accesses);
+
+            for (i, queue) in queues.iter_mut().enumerate() {
+                if grant & (1 << i) == 0 {
+                    continue;
+                }
+
+                match queue
+                    .pop_front()
+                    .expect("granted a client that wasn't asking")
+                {
+                    Access::Read(addr) => assert_eq!(
+                        read_data, memory[addr as usize],
+                        "client {} read {:#x} from {:#x}",
+                        i, read_data, addr
+                    ),
+                    Access::Write(addr, data) => memory[addr as usize] = data,
+                }
+            }
+        }
+    }
+
+    fn check_no_lost_writes(dut: &mut impl Arbiter) {
+        dut.reset();
+
+        let mut rng = rand::rng();
+        let mut memory = [0; 1 << ADDR_WIDTH];
+
+        // Start off with known contents
+        let mut queues: Vec<_> = (0..NUM_CLIENTS).map(|_| VecDeque::new()).collect();
+        for addr in 0..(1 << ADDR_WIDTH) {
+            queues[0].push_back(Access::Write(addr, 0));
+        }
+        run_to_completion(dut, &mut queues, &mut memory);
+
+        // Everyone fights over the same few addresses
+        for queue in &mut queues {
+            for _ in 0..200 {
+                let addr = rng.random_range(0..4);
+                if rng.random_bool(0.5) {
+                    queue.push_back(Access::Write(addr, rng.random()));
+                } else {
+                    queue.push_back(Access::Read(addr));
+                }
+            }
+        }
+        run_to_completion(dut, &mut queues, &mut memory);
+
+        // And the last write to each address has to be what's there at the end
+        for addr in 0..(1 << ADDR_WIDTH) {
+            queues[NUM_CLIENTS - 1].push_back(Access::Read(addr));
+        }
+        run_to_completion(dut, &mut queues, &mut memory);
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_round_robin_fairness() -> Result<(), Whatever> {
+        let runtime = make_runtime()?;
+        let mut dut = Dut::new(runtime.create_model_simple::<RoundRobinArbiter>()?)?;
+
+        dut.reset();
+
+        let accesses = [Some(Access::Read(0)); NUM_CLIENTS];
+        let mut counts = [0; NUM_CLIENTS];
+        let mut last_grant = [0; NUM_CLIENTS];
+
+        for cycle in 0..300 {
+            let (grant, _) = dut.cycle(&accesses);
+            assert_eq!(grant.count_ones(), 1);
+
+            let i = grant.trailing_zeros() as usize;
+            // Everyone else gets a turn in between
+            if counts[i] > 0 {
+                assert_eq!(
+                    cycle - last_grant[i],
+                    NUM_CLIENTS,
+                    "client {} waited too long",
+                    i
+                );
+            }
+            counts[i] += 1;
+            last_grant[i] = cycle;
+        }
+
+        assert_eq!(counts, [100; NUM_CLIENTS]);
+
+        // Clients that aren't asking get skipped over
+        let accesses = [Some(Access::Read(0)), None, Some(Access::Read(1))];
+        for _ in 0..10 {
+            let (grant, _) = dut.cycle(&accesses);
+            assert!(grant == 0b001 || grant == 0b100, "bad grant {:#b}", grant);
+            let (next_grant, _) = dut.cycle(&accesses);
+            assert_eq!(
+                grant ^ next_grant,
+                0b101,
+                "clients 0 and 2 should alternate"
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_fixed_priority() -> Result<(), Whatever> {
+        let runtime = make_runtime()?;
+        let mut dut = Dut::new(runtime.create_model_simple::<FixedPriorityArbiter>()?)?;
+
+        dut.reset();
+
+        let read = Some(Access::Read(0));
+        for _ in 0..20 {
+            assert_eq!(dut.cycle(&[read, read, read]).0, 0b001);
+        }
+        for _ in 0..20 {
+            assert_eq!(dut.cycle(&[None, read, read]).0, 0b010);
+        }
+        assert_eq!(dut.cycle(&[None, None, read]).0, 0b100);
+        assert_eq!(dut.cycle(&[None, None, None]).0, 0b000);
+
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_read_latency() -> Result<(), Whatever> {
+        let runtime = make_runtime()?;
+        let mut dut = Dut::new(runtime.create_model_simple::<RoundRobinArbiter>()?)?;
+
+        dut.reset();
+
+        // Data written by one client is there for another the cycle after its own grant, just like
+        // talking to the bram directly
+        assert_eq!(
+            dut.cycle(&[Some(Access::Write(5, 0x42)), None, None]).0,
+            0b001
+        );
+        let (grant, read_data) = dut.cycle(&[None, Some(Access::Read(5)), None]);
+        assert_eq!(grant, 0b010);
+        assert_eq!(read_data, 0x42);
+
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_no_lost_writes() -> Result<(), Whatever> {
+        let runtime = make_runtime()?;
+
+        check_no_lost_writes(&mut Dut::new(
+            runtime.create_model_simple::<RoundRobinArbiter>()?,
+        )?);
+        check_no_lost_writes(&mut Dut::new(
+            runtime.create_model_simple::<FixedPriorityArbiter>()?,
+        )?);
+
+        Ok(())
+    }
+}
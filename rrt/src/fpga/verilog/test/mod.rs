@@ -1,4 +1,14 @@
+#[cfg(test)]
+mod bench;
 mod bram;
+mod collision_cluster;
 mod directed_energy_weapon;
+mod grid_loader;
+mod memory_arbiter;
 mod occupancy_grid;
 mod prng;
+mod rrt_axi_lite;
+mod rrt_top;
+mod sampler;
+mod steer;
+mod tree_readback;
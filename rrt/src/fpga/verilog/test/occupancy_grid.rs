@@ -1,12 +1,16 @@
 #[cfg(test)]
 mod tests {
-    use std::{collections::HashMap, path::Path};
+    use std::collections::HashMap;
 
-    use marlin::verilator::{VerilatorRuntime, VerilatorRuntimeOptions};
+    use marlin::verilator::VerilatorRuntime;
     use marlin::verilog::prelude::*;
     use rand::RngExt;
     use snafu::Whatever;
 
+    use crate::fpga::verilog::test::bench::{
+        self, Clocked, Dut, GridAccess, GridModel, GridRequester, impl_clocked, impl_grid_bus,
+    };
+
     const GRID_WIDTH_LOG2: u32 = 4;
     const GRID_HEIGHT_LOG2: u32 = 4;
 
@@ -18,86 +22,150 @@ mod tests {
     )]
     pub struct OccupancyGridWrapper;
 
-    impl<'ctx> OccupancyGridWrapper<'ctx> {
-        fn tick(&mut self) {
-            self.clk = 1;
-            self.eval();
-            self.clk = 0;
-            self.eval();
+    impl_clocked!(OccupancyGridWrapper);
+    impl_grid_bus!(GridRequester for OccupancyGridWrapper {
+        cell_x: cell_x,
+        cell_y: cell_y,
+        input_valid: input_valid,
+        ready_for_input: ready_for_input,
+        write_enable: write_enable,
+        write_occupied: write_occupied,
+        output_valid: output_valid,
+        read_occupied: read_occupied,
+    });
+
+    impl<'ctx> Dut<OccupancyGridWrapper<'ctx>> {
+        fn reset(&mut self) {
+            self.put_grid_access(None);
+            self.pulse_reset();
         }
+    }
+
+    #[verilog(
+        src = "src/fpga/verilog/test/wrappers/occupancy_grid_shared_wrapper.sv",
+        name = "occupancy_grid_shared_wrapper",
+        params = { GRID_WIDTH_LOG2: 4, GRID_HEIGHT_LOG2: 4, DATA_WIDTH: 8, ADDR_WIDTH: 8 },
+        includes = ["src/fpga/verilog/src/"]
+    )]
+    pub struct SharedOccupancyGridWrapper;
+
+    impl_clocked!(SharedOccupancyGridWrapper);
+    impl_grid_bus!(GridRequester for SharedOccupancyGridWrapper {
+        cell_x: cell_x,
+        cell_y: cell_y,
+        input_valid: input_valid,
+        ready_for_input: ready_for_input,
+        write_enable: write_enable,
+        write_occupied: write_occupied,
+        output_valid: output_valid,
+        read_occupied: read_occupied,
+    });
 
+    impl<'ctx> Dut<SharedOccupancyGridWrapper<'ctx>> {
         fn reset(&mut self) {
-            self.rst_n = 0;
-            self.input_valid = 0;
-            self.write_enable = 0;
-            self.cell_x = 0;
-            self.cell_y = 0;
-            self.write_occupied = 0;
-            self.clk = 0;
-            self.eval();
-
-            self.tick();
-            self.rst_n = 1;
-            self.tick();
+            self.hog = 0;
+            self.put_grid_access(None);
+            self.pulse_reset();
         }
+    }
+
+    /// Runs the accesses in order, as fast as the grid will take them, checking that every read
+    /// comes back exactly a cycle after `grid_step` got it accepted, with everything written
+    /// before it. Returns how many cycles it took.
+    fn run_accesses(
+        dut: &mut Dut<OccupancyGridWrapper>,
+        accesses: &[GridAccess],
+        model: &mut GridModel,
+    ) -> usize {
+        let mut cycles = 0;
+        let mut expected = None;
+        let mut next = 0;
+
+        while next < accesses.len() || expected.is_some() {
+            let access = accesses.get(next).copied();
+            let (accepted, output) = dut.grid_step(access);
+            cycles += 1;
 
-        fn wait_for_ready(&mut self) {
-            while self.ready_for_input == 0 {
-                self.tick();
+            assert_eq!(output, expected, "wrong output in cycle {}", cycles);
+            expected = None;
+
+            if accepted {
+                expected = model.access(access.unwrap());
+                next += 1;
             }
         }
 
-        fn wait_for_output(&mut self) {
-            while self.output_valid == 0 {
-                self.tick();
+        cycles
+    }
+
+    /// Like `run_accesses`, but the other client on the memory takes it on every cycle `hog` says
+    /// to. Checks that a write back that doesn't get the memory stays up, unchanged, until it
+    /// does. Returns how many cycles the grid had a write back waiting.
+    fn run_contended(
+        dut: &mut Dut<SharedOccupancyGridWrapper>,
+        accesses: &[GridAccess],
+        model: &mut GridModel,
+        mut hog: impl FnMut(usize) -> bool,
+    ) -> usize {
+        let mut cycles = 0;
+        let mut denied = 0;
+        let mut waiting = None;
+        let mut expected = None;
+        let mut next = 0;
+
+        while next < accesses.len() || expected.is_some() || waiting.is_some() {
+            let access = accesses.get(next).copied();
+            dut.hog = hog(cycles) as u8;
+            dut.put_grid_access(access);
+            dut.settle();
+
+            let write_back = (dut.grid_mem_request != 0 && dut.grid_mem_write_enable != 0)
+                .then_some((dut.grid_mem_address, dut.grid_mem_write_data));
+            if let Some(held) = waiting {
+                assert_eq!(
+                    write_back,
+                    Some(held),
+                    "write back dropped or changed in cycle {}",
+                    cycles
+                );
             }
-        }
+            waiting = write_back.filter(|_| dut.grid_mem_grant == 0);
+            denied += waiting.is_some() as usize;
 
-        fn write_cell(&mut self, x: u8, y: u8, val: u8) {
-            self.wait_for_ready();
-            self.cell_x = x;
-            self.cell_y = y;
-            self.write_occupied = val;
-            self.write_enable = 1;
-            self.input_valid = 1;
-            self.tick();
-            self.input_valid = 0;
-            self.write_enable = 0;
-        }
+            let (accepted, output) = dut.grid_step(access);
+            cycles += 1;
+
+            assert_eq!(output, expected, "wrong output in cycle {}", cycles);
+            expected = None;
 
-        fn read_cell(&mut self, x: u8, y: u8) -> u8 {
-            self.wait_for_ready();
-            self.cell_x = x;
-            self.cell_y = y;
-            self.write_enable = 0;
-            self.input_valid = 1;
-            self.tick();
-            self.input_valid = 0;
-            self.wait_for_output();
-            self.read_occupied
+            if accepted {
+                expected = model.access(access.unwrap());
+                next += 1;
+            }
         }
+
+        dut.hog = 0;
+        denied
     }
 
     fn make_runtime() -> Result<VerilatorRuntime, Whatever> {
-        VerilatorRuntime::new2(
-            "build",
-            &["src/fpga/verilog/test/wrappers/occupancy_grid_wrapper.sv"],
-            &[Path::new("src/fpga/verilog/src/")],
-            [],
-            VerilatorRuntimeOptions::default(),
-        )
+        bench::runtime("src/fpga/verilog/test/wrappers/occupancy_grid_wrapper.sv")
+    }
+
+    fn make_shared_runtime() -> Result<VerilatorRuntime, Whatever> {
+        bench::runtime("src/fpga/verilog/test/wrappers/occupancy_grid_shared_wrapper.sv")
     }
 
     #[test]
     #[snafu::report]
     fn test_read_write_single() -> Result<(), Whatever> {
         let runtime = make_runtime()?;
-        let mut dut = runtime.create_model_simple::<OccupancyGridWrapper>()?;
+        let mut dut = Dut::new(runtime.create_model_simple::<OccupancyGridWrapper>()?)?;
 
         dut.reset();
-        dut.write_cell(1, 1, 1);
+        dut.write_cell(1, 1, true);
         let val = dut.read_cell(1, 1);
-        assert_eq!(val, 1, "expected (1,1) occupied, got {}", val);
+        assert!(val, "expected (1,1) occupied, got {}", val);
 
         Ok(())
     }
@@ -106,17 +174,17 @@ mod tests {
     #[snafu::report]
     fn test_random_access() -> Result<(), Whatever> {
         let runtime = make_runtime()?;
-        let mut dut = runtime.create_model_simple::<OccupancyGridWrapper>()?;
+        let mut dut = Dut::new(runtime.create_model_simple::<OccupancyGridWrapper>()?)?;
 
         dut.reset();
 
         let mut rng = rand::rng();
-        let mut expected: HashMap<(u8, u8), u8> = HashMap::new();
+        let mut expected: HashMap<(usize, usize), bool> = HashMap::new();
 
         for _ in 0..50 {
-            let x: u8 = rng.random_range(0..(1u32 << GRID_WIDTH_LOG2)) as u8;
-            let y: u8 = rng.random_range(0..(1u32 << GRID_HEIGHT_LOG2)) as u8;
-            let val: u8 = rng.random_range(0..2u32) as u8;
+            let x = rng.random_range(0..(1 << GRID_WIDTH_LOG2));
+            let y = rng.random_range(0..(1 << GRID_HEIGHT_LOG2));
+            let val = rng.random_bool(0.5);
 
             expected.insert((x, y), val);
             dut.write_cell(x, y, val);
@@ -133,4 +201,165 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    #[snafu::report]
+    fn test_read_every_cycle() -> Result<(), Whatever> {
+        let runtime = make_runtime()?;
+        let mut dut = Dut::new(runtime.create_model_simple::<OccupancyGridWrapper>()?)?;
+
+        dut.reset();
+
+        let mut rng = rand::rng();
+        let mut model = GridModel::new(16, 16);
+        let writes: Vec<_> = (0..16)
+            .flat_map(|y| (0..16).map(move |x| (x, y)))
+            .map(|(x, y)| GridAccess::write(x, y, rng.random_bool(0.5)))
+            .collect();
+        run_accesses(&mut dut, &writes, &mut model);
+
+        // One read a cycle, plus a cycle at the end for the last result
+        let reads: Vec<_> = (0..16)
+            .flat_map(|y| (0..16).map(move |x| GridAccess::read(x, y)))
+            .collect();
+        let cycles = run_accesses(&mut dut, &reads, &mut model);
+        assert_eq!(cycles, reads.len() + 1);
+
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_read_after_write() -> Result<(), Whatever> {
+        let runtime = make_runtime()?;
+        let mut dut = Dut::new(runtime.create_model_simple::<OccupancyGridWrapper>()?)?;
+
+        dut.reset();
+
+        let mut model = GridModel::new(16, 16);
+
+        // Straight after a write, to the same cell and to the rest of its word
+        run_accesses(
+            &mut dut,
+            &[
+                GridAccess::write(3, 1, true),
+                GridAccess::read(3, 1),
+                GridAccess::read(2, 1),
+                GridAccess::write(2, 1, true),
+                GridAccess::write(3, 1, false),
+                GridAccess::read(3, 1),
+                GridAccess::read(2, 1),
+            ],
+            &mut model,
+        );
+
+        // Writes to one word don't need to wait for each other
+        let same_word: Vec<_> = (0..8).map(|x| GridAccess::write(x, 5, true)).collect();
+        assert_eq!(
+            run_accesses(&mut dut, &same_word, &mut model),
+            same_word.len()
+        );
+
+        // Writes to different words do, but still have to all land
+        let different_words: Vec<_> = (0..16).map(|y| GridAccess::write(7, y, true)).collect();
+        run_accesses(&mut dut, &different_words, &mut model);
+        let reads: Vec<_> = (0..16).map(|y| GridAccess::read(7, y)).collect();
+        run_accesses(&mut dut, &reads, &mut model);
+
+        // And anything else, crammed into a few words so there are lots of hazards
+        let mut rng = rand::rng();
+        let mixed: Vec<_> = (0..2000)
+            .map(|_| {
+                let x = rng.random_range(0..16);
+                let y = rng.random_range(0..3);
+                if rng.random_bool(0.5) {
+                    GridAccess::write(x, y, rng.random_bool(0.5))
+                } else {
+                    GridAccess::read(x, y)
+                }
+            })
+            .collect();
+        run_accesses(&mut dut, &mixed, &mut model);
+
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_contended_write_back() -> Result<(), Whatever> {
+        let runtime = make_shared_runtime()?;
+        let mut dut = Dut::new(runtime.create_model_simple::<SharedOccupancyGridWrapper>()?)?;
+
+        dut.reset();
+
+        let mut model = GridModel::new(16, 16);
+
+        // The first write gets in, then its write back is held off for a while, with a write to
+        // another word waiting behind it. The write back has to stay up until it gets through,
+        // which `run_contended` checks, and so does the protocol checker on the grid's memory
+        // port.
+        let denied = run_contended(
+            &mut dut,
+            &[
+                GridAccess::write(0, 0, true),
+                GridAccess::write(0, 1, true),
+                GridAccess::read(0, 0),
+                GridAccess::read(0, 1),
+            ],
+            &mut model,
+            |cycle| (1..10).contains(&cycle),
+        );
+        assert!(
+            denied >= 8,
+            "write back only held off for {} cycles",
+            denied
+        );
+
+        // Writes landing in the stage while the buffer waits, crammed into a few words so they
+        // keep hitting the buffered one
+        let mut rng = rand::rng();
+        let mixed: Vec<_> = (0..2000)
+            .map(|_| {
+                let x = rng.random_range(0..16);
+                let y = rng.random_range(0..3);
+                if rng.random_bool(0.5) {
+                    GridAccess::write(x, y, rng.random_bool(0.5))
+                } else {
+                    GridAccess::read(x, y)
+                }
+            })
+            .collect();
+        let denied = run_contended(&mut dut, &mixed, &mut model, |_| rng.random_bool(0.5));
+        assert!(denied > 0, "the write back never had to wait");
+
+        // And with the memory to itself again, it all reads back
+        let reads: Vec<_> = (0..3)
+            .flat_map(|y| (0..16).map(move |x| GridAccess::read(x, y)))
+            .collect();
+        run_contended(&mut dut, &reads, &mut model, |_| false);
+
+        Ok(())
+    }
+
+    // Returns () since it has to panic, which a failed assertion in the model does on the edge
+    #[test]
+    #[should_panic(expected = "protocol violation")]
+    fn test_dropped_request_fails() {
+        let runtime = make_runtime().unwrap();
+        let mut dut = Dut::new(
+            runtime
+                .create_model_simple::<OccupancyGridWrapper>()
+                .unwrap(),
+        )
+        .unwrap();
+
+        dut.reset();
+
+        // A write to another word has to wait for the first one, and giving up on it instead of
+        // holding it is against the rules
+        dut.write_cell(0, 0, true);
+        let (accepted, _) = dut.grid_step(Some(GridAccess::write(0, 1, true)));
+        assert!(!accepted, "the second write should have been held off");
+        dut.grid_step(None);
+    }
 }
@@ -1,24 +1,105 @@
 #[cfg(test)]
 mod tests {
-    use std::path::Path;
-
-    use marlin::verilator::{VerilatorRuntime, VerilatorRuntimeOptions};
     use marlin::verilog::prelude::*;
     use snafu::Whatever;
 
+    use crate::fpga::verilog::test::bench::{self, Clocked, Dut, impl_clocked};
+    use crate::shared::prng::{Xorshift64, Xoshiro256StarStar};
+
     #[verilog(src = "src/fpga/verilog/src/prng.sv", name = "prng64")]
     pub struct PRNG64;
 
-    impl<'ctx> PRNG64<'ctx> {
-        fn tick(&mut self) {
-            self.clk = 1;
-            self.eval();
-            self.clk = 0;
-            self.eval();
+    #[verilog(
+        src = "src/fpga/verilog/src/prng.sv",
+        name = "xoshiro256ss",
+        params = { STREAM: 0 }
+    )]
+    pub struct Xoshiro;
+
+    #[verilog(
+        src = "src/fpga/verilog/src/prng.sv",
+        name = "xoshiro256ss",
+        params = { STREAM: 2 }
+    )]
+    pub struct XoshiroStream2;
+
+    trait Xoshiro256ss {
+        const STREAM: u32;
+
+        /// Resets with `seed`, then waits for the stream to be ready. Returns how many cycles that
+        /// took.
+        fn reset(&mut self, seed: u64) -> usize;
+
+        /// Returns the current word and steps.
+        fn next(&mut self) -> u64;
+    }
+
+    macro_rules! impl_xoshiro {
+        ($name:ident, $stream:expr) => {
+            impl_clocked!($name);
+
+            impl<'ctx> Xoshiro256ss for Dut<$name<'ctx>> {
+                const STREAM: u32 = $stream;
+
+                fn reset(&mut self, seed: u64) -> usize {
+                    self.rst_n = 0;
+                    self.seed = seed;
+                    self.enable = 0;
+                    self.clk = 0;
+                    self.eval();
+
+                    self.tick();
+                    self.rst_n = 1;
+                    // Enable should be ignored while jumping
+                    self.enable = 1;
+                    self.eval();
+
+                    let mut cycles = 0;
+                    while self.ready == 0 {
+                        self.tick();
+                        cycles += 1;
+                    }
+                    self.enable = 0;
+                    self.eval();
+
+                    cycles
+                }
+
+                fn next(&mut self) -> u64 {
+                    let out = self.out;
+                    self.enable = 1;
+                    self.tick();
+                    self.enable = 0;
+                    self.eval();
+                    out
+                }
+            }
+        };
+    }
+
+    impl_xoshiro!(Xoshiro, 0);
+    impl_xoshiro!(XoshiroStream2, 2);
+
+    fn check_xoshiro_matches_model<X: Xoshiro256ss>(dut: &mut X) {
+        for seed in [0, 1, 0xdead_beef, u64::MAX] {
+            let cycles = dut.reset(seed);
+            assert_eq!(cycles, 256 * X::STREAM as usize);
+
+            let mut model = Xoshiro256StarStar::with_stream(seed, X::STREAM);
+            for i in 0..100 {
+                assert_eq!(
+                    dut.next(),
+                    model.next_u64(),
+                    "seed {seed:#x}, stream {}, word {i}",
+                    X::STREAM
+                );
+            }
         }
     }
 
-    fn reset(dut: &mut PRNG64) {
+    impl_clocked!(PRNG64);
+
+    fn reset(dut: &mut Dut<PRNG64>) {
         dut.rst_n = 0;
         dut.seed = 123;
         dut.enable = 0;
@@ -34,15 +115,9 @@ mod tests {
     #[test]
     #[snafu::report]
     fn test_en_low() -> Result<(), Whatever> {
-        let runtime = VerilatorRuntime::new2(
-            "build",
-            &["src/fpga/verilog/src/prng.sv"],
-            &[] as &[&Path],
-            [],
-            VerilatorRuntimeOptions::default(),
-        )?;
+        let runtime = bench::runtime("src/fpga/verilog/src/prng.sv")?;
 
-        let mut dut = runtime.create_model_simple::<PRNG64>()?;
+        let mut dut = Dut::new(runtime.create_model_simple::<PRNG64>()?)?;
 
         reset(&mut dut);
 
@@ -60,15 +135,9 @@ mod tests {
     #[test]
     #[snafu::report]
     fn test_en_high_changes() -> Result<(), Whatever> {
-        let runtime = VerilatorRuntime::new2(
-            "build",
-            &["src/fpga/verilog/src/prng.sv"],
-            &[] as &[&Path],
-            [],
-            VerilatorRuntimeOptions::default(),
-        )?;
+        let runtime = bench::runtime("src/fpga/verilog/src/prng.sv")?;
 
-        let mut dut = runtime.create_model_simple::<PRNG64>()?;
+        let mut dut = Dut::new(runtime.create_model_simple::<PRNG64>()?)?;
 
         reset(&mut dut);
 
@@ -89,15 +158,9 @@ mod tests {
     #[test]
     #[snafu::report]
     fn test_reset_seed() -> Result<(), Whatever> {
-        let runtime = VerilatorRuntime::new2(
-            "build",
-            &["src/fpga/verilog/src/prng.sv"],
-            &[] as &[&Path],
-            [],
-            VerilatorRuntimeOptions::default(),
-        )?;
+        let runtime = bench::runtime("src/fpga/verilog/src/prng.sv")?;
 
-        let mut dut = runtime.create_model_simple::<PRNG64>()?;
+        let mut dut = Dut::new(runtime.create_model_simple::<PRNG64>()?)?;
 
         // Manual reset sequence to test specific seed behavior
         dut.rst_n = 1;
@@ -117,4 +180,60 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    #[snafu::report]
+    fn test_zero_seed() -> Result<(), Whatever> {
+        let runtime = bench::runtime("src/fpga/verilog/src/prng.sv")?;
+
+        let mut dut = Dut::new(runtime.create_model_simple::<PRNG64>()?)?;
+
+        dut.seed = 0;
+        dut.rst_n = 0;
+        dut.enable = 0;
+        dut.clk = 0;
+        dut.eval();
+        dut.tick();
+        dut.rst_n = 1;
+        dut.enable = 1;
+
+        let mut model = Xorshift64::new(0);
+        for _ in 0..10 {
+            assert_ne!(dut.out, 0);
+            assert_eq!(dut.out, model.next_u64());
+            dut.tick();
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_xoshiro_matches_model() -> Result<(), Whatever> {
+        let runtime = bench::runtime("src/fpga/verilog/src/prng.sv")?;
+
+        check_xoshiro_matches_model(&mut Dut::new(runtime.create_model_simple::<Xoshiro>()?)?);
+        check_xoshiro_matches_model(&mut Dut::new(
+            runtime.create_model_simple::<XoshiroStream2>()?,
+        )?);
+
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_xoshiro_streams_differ() -> Result<(), Whatever> {
+        let runtime = bench::runtime("src/fpga/verilog/src/prng.sv")?;
+
+        let mut stream0 = Dut::new(runtime.create_model_simple::<Xoshiro>()?)?;
+        let mut stream2 = Dut::new(runtime.create_model_simple::<XoshiroStream2>()?)?;
+        stream0.reset(42);
+        stream2.reset(42);
+
+        let words0: Vec<_> = (0..16).map(|_| stream0.next()).collect();
+        let words2: Vec<_> = (0..16).map(|_| stream2.next()).collect();
+        assert!(words0.iter().all(|w| !words2.contains(w)));
+
+        Ok(())
+    }
 }
@@ -0,0 +1,133 @@
+`ifndef PROTOCOL_CHECKERS_SV
+`define PROTOCOL_CHECKERS_SV
+
+// Assertions for the handshakes on occupancy_grid_bus and memory_bus, bound into every module that
+// sits on one end of them. The tests build this alongside each wrapper, so a module breaking the
+// protocol fails the test, even if what comes out at the top happens to be right.
+//
+// A failed assertion calls protocol_violation with one of the CHECK_* numbers below, which the test
+// bench turns into a panic on the next clock edge. Keep them in sync with bench.rs.
+
+`include "grid_loader.sv"
+`include "occupancy_grid.sv"
+`include "tree_readback.sv"
+
+`define CHECK_GRID_REQUEST_HELD 1
+`define CHECK_GRID_READ_RESPONSE 2
+`define CHECK_MEMORY_REQUEST_HELD 3
+
+import "DPI-C" function void protocol_violation(input int check);
+
+module occupancy_grid_bus_checker #(
+    parameter GRID_WIDTH_LOG2,
+    parameter GRID_HEIGHT_LOG2
+) (
+    input logic clk,
+    input logic rst_n,
+
+    input logic [GRID_WIDTH_LOG2-1:0] cell_x,
+    input logic [GRID_HEIGHT_LOG2-1:0] cell_y,
+    input logic input_valid,
+    input logic ready_for_input,
+    input logic write_enable,
+    input logic write_occupied,
+    input logic output_valid
+);
+    logic read_accepted;
+    assign read_accepted = input_valid && ready_for_input && !write_enable;
+
+    // A request that wasn't taken has to stay up, unchanged, until it is
+    request_held: assert property (
+        @(posedge clk) disable iff (!rst_n)
+        input_valid && !ready_for_input |=>
+            input_valid && $stable(cell_x) && $stable(cell_y) && $stable(write_enable)
+            && (!write_enable || $stable(write_occupied))
+    ) else protocol_violation(`CHECK_GRID_REQUEST_HELD);
+
+    // Every read gets exactly one response, two cycles after the one it was accepted in, and
+    // nothing else does
+    read_response: assert property (
+        @(posedge clk) disable iff (!rst_n)
+        output_valid == $past(read_accepted, 2)
+    ) else protocol_violation(`CHECK_GRID_READ_RESPONSE);
+endmodule
+
+module memory_bus_checker #(
+    parameter ADDR_WIDTH,
+    parameter DATA_WIDTH
+) (
+    input logic clk,
+    input logic rst_n,
+
+    input logic request,
+    input logic grant,
+    input logic [ADDR_WIDTH-1:0] address,
+    input logic [DATA_WIDTH-1:0] write_data,
+    input logic write_enable
+);
+    // Arbiters don't remember who asked, so a request that's dropped or changed before it's
+    // granted never happened, and a write that's dropped is lost
+    request_held: assert property (
+        @(posedge clk) disable iff (!rst_n)
+        request && !grant |=>
+            request && $stable(address) && $stable(write_enable)
+            && (!write_enable || $stable(write_data))
+    ) else protocol_violation(`CHECK_MEMORY_REQUEST_HELD);
+endmodule
+
+// occupancy_grid is the grid end of every occupancy_grid_bus, and a memory_bus client
+bind occupancy_grid occupancy_grid_bus_checker #(
+    .GRID_WIDTH_LOG2(GRID_WIDTH_LOG2),
+    .GRID_HEIGHT_LOG2(GRID_HEIGHT_LOG2)
+) bus_checker (
+    .clk(clk),
+    .rst_n(rst_n),
+    .cell_x(bus.cell_x),
+    .cell_y(bus.cell_y),
+    .input_valid(bus.input_valid),
+    .ready_for_input(bus.ready_for_input),
+    .write_enable(bus.write_enable),
+    .write_occupied(bus.write_occupied),
+    .output_valid(bus.output_valid)
+);
+
+bind occupancy_grid memory_bus_checker #(
+    .ADDR_WIDTH(ADDR_WIDTH),
+    .DATA_WIDTH(DATA_WIDTH)
+) mem_checker (
+    .clk(clk),
+    .rst_n(rst_n),
+    .request(mem.request),
+    .grant(mem.grant),
+    .address(mem.address),
+    .write_data(mem.write_data),
+    .write_enable(mem.write_enable)
+);
+
+bind grid_loader memory_bus_checker #(
+    .ADDR_WIDTH(ADDR_WIDTH),
+    .DATA_WIDTH(DATA_WIDTH)
+) mem_checker (
+    .clk(clk),
+    .rst_n(rst_n),
+    .request(mem.request),
+    .grant(mem.grant),
+    .address(mem.address),
+    .write_data(mem.write_data),
+    .write_enable(mem.write_enable)
+);
+
+bind tree_readback memory_bus_checker #(
+    .ADDR_WIDTH(TREE_ADDR_WIDTH),
+    .DATA_WIDTH(TREE_ADDR_WIDTH + 2 * `POINT_BITS)
+) mem_checker (
+    .clk(clk),
+    .rst_n(rst_n),
+    .request(tree_mem.request),
+    .grant(tree_mem.grant),
+    .address(tree_mem.address),
+    .write_data(tree_mem.write_data),
+    .write_enable(tree_mem.write_enable)
+);
+
+`endif
@@ -0,0 +1,334 @@
+#[cfg(test)]
+mod tests {
+    use marlin::verilator::VerilatorRuntime;
+    use marlin::verilog::prelude::*;
+    use na::vector;
+    use snafu::{Whatever, whatever};
+
+    use crate::fpga::fixed::{self, FixedPoint};
+    use crate::fpga::loader;
+    use crate::fpga::verilog::test::bench::{
+        self, AxiLiteMaster, AxiResp, Clocked, Dut, impl_axi_lite_master, impl_clocked,
+    };
+    use crate::shared::grid::OccupancyGrid;
+
+    const GRID_WIDTH_LOG2: u32 = 3;
+    const GRID_HEIGHT_LOG2: u32 = 3;
+    const GRID_DATA_WIDTH: usize = 8;
+    const TREE_ADDR_WIDTH: u32 = 7;
+    const AXI_ADDR_WIDTH: u32 = 13;
+
+    const CELL: u32 = 1 << (fixed::POINT_BITS - GRID_WIDTH_LOG2);
+    const MOVE_DIST: u32 = CELL / 2;
+
+    // Polling STATUS takes a few cycles each time, so this is a lot of cycles
+    const MAX_POLLS: usize = 1_000_000;
+
+    // The register map from rrt_axi_lite.sv
+    const CONTROL: u64 = 0x00;
+    const STATUS: u64 = 0x04;
+    const NODE_COUNT: u64 = 0x08;
+    const GOAL_NODE: u64 = 0x0c;
+    const START_X: u64 = 0x10;
+    const START_Y: u64 = 0x14;
+    const GOAL_X: u64 = 0x18;
+    const GOAL_Y: u64 = 0x1c;
+    const MIN_X: u64 = 0x20;
+    const MIN_Y: u64 = 0x24;
+    const MAX_X: u64 = 0x28;
+    const MAX_Y: u64 = 0x2c;
+    const MOVE_DIST_REG: u64 = 0x30;
+    const SQ_DIST_TOL_LO: u64 = 0x34;
+    const SQ_DIST_TOL_HI: u64 = 0x38;
+    const GOAL_BIAS: u64 = 0x3c;
+    const SEED_LO: u64 = 0x40;
+    const SEED_HI: u64 = 0x44;
+    const NUM_POINTS: u64 = 0x48;
+
+    const STATUS_BUSY: u32 = 1 << 0;
+    const STATUS_DONE: u32 = 1 << 1;
+    const STATUS_FOUND: u32 = 1 << 2;
+
+    const GRID_BASE: u64 = 1 << (AXI_ADDR_WIDTH - 2);
+    const TREE_BASE: u64 = 2 << (AXI_ADDR_WIDTH - 2);
+
+    #[verilog(
+        src = "src/fpga/verilog/src/rrt_axi_lite.sv",
+        name = "rrt_axi_lite",
+        params = {
+            GRID_WIDTH_LOG2: 3,
+            GRID_HEIGHT_LOG2: 3,
+            GRID_DATA_WIDTH: 8,
+            GRID_ADDR_WIDTH: 3,
+            TREE_ADDR_WIDTH: 7,
+            STEER_ITERATIONS: 24,
+            AXI_ADDR_WIDTH: 13
+        },
+        includes = ["src/fpga/verilog/src/"]
+    )]
+    pub struct RrtAxiLite;
+
+    impl_clocked!(RrtAxiLite);
+    impl_axi_lite_master!(RrtAxiLite);
+
+    struct Node {
+        point: FixedPoint,
+        parent: usize,
+    }
+
+    impl<'ctx> Dut<RrtAxiLite<'ctx>> {
+        fn reset(&mut self) {
+            self.put_axi_write(None, None);
+            self.put_axi_bready(false);
+            self.put_axi_read(None);
+            self.put_axi_rready(false);
+            self.pulse_reset();
+        }
+
+        fn write_ok(&mut self, address: u64, data: u32) {
+            assert_eq!(
+                self.axi_write(address, data),
+                AxiResp::Okay,
+                "write to {:#x}",
+                address
+            );
+        }
+
+        fn read_ok(&mut self, address: u64) -> u32 {
+            let (data, response) = self.axi_read(address);
+            assert_eq!(response, AxiResp::Okay, "read from {:#x}", address);
+            data
+        }
+
+        fn write_point(&mut self, x_register: u64, y_register: u64, p: FixedPoint) {
+            self.write_ok(x_register, p.x);
+            self.write_ok(y_register, p.y);
+        }
+
+        fn load_grid(&mut self, grid: &OccupancyGrid) {
+            let words = loader::pack_grid(grid, GRID_WIDTH_LOG2, GRID_HEIGHT_LOG2, GRID_DATA_WIDTH);
+            for (address, word) in words.into_iter().enumerate() {
+                self.write_ok(GRID_BASE + 4 * address as u64, word as u32);
+            }
+        }
+
+        fn configure(&mut self, start: FixedPoint, goal: FixedPoint, seed: u64) {
+            let sq_dist_tol = (MOVE_DIST as u64).pow(2);
+
+            self.write_point(START_X, START_Y, start);
+            self.write_point(GOAL_X, GOAL_Y, goal);
+            self.write_point(MIN_X, MIN_Y, FixedPoint::new(0, 0));
+            self.write_point(MAX_X, MAX_Y, FixedPoint::new(u32::MAX, u32::MAX));
+            self.write_ok(MOVE_DIST_REG, MOVE_DIST);
+            self.write_ok(SQ_DIST_TOL_LO, sq_dist_tol as u32);
+            self.write_ok(SQ_DIST_TOL_HI, (sq_dist_tol >> 32) as u32);
+            self.write_ok(GOAL_BIAS, 1 << 13);
+            self.write_ok(SEED_LO, seed as u32);
+            self.write_ok(SEED_HI, (seed >> 32) as u32);
+            self.write_ok(NUM_POINTS, 1 << TREE_ADDR_WIDTH);
+        }
+
+        /// Starts a run and polls until it's done, returning the last STATUS.
+        fn run(&mut self) -> Result<u32, Whatever> {
+            self.write_ok(CONTROL, 1);
+            self.wait_until_idle()
+        }
+
+        fn wait_until_idle(&mut self) -> Result<u32, Whatever> {
+            for _ in 0..MAX_POLLS {
+                let status = self.read_ok(STATUS);
+                if status & STATUS_BUSY == 0 {
+                    return Ok(status);
+                }
+            }
+
+            whatever!("run didn't finish in {} polls", MAX_POLLS)
+        }
+
+        fn read_tree(&mut self) -> Vec<Node> {
+            let node_count = self.read_ok(NODE_COUNT) as u64;
+            (0..node_count)
+                .map(|i| {
+                    let base = TREE_BASE + 16 * i;
+                    let y = self.read_ok(base);
+                    let x = self.read_ok(base + 4);
+                    Node {
+                        point: FixedPoint::new(x, y),
+                        parent: self.read_ok(base + 8) as usize,
+                    }
+                })
+                .collect()
+        }
+    }
+
+    fn make_runtime() -> Result<VerilatorRuntime, Whatever> {
+        bench::runtime("src/fpga/verilog/src/rrt_axi_lite.sv")
+    }
+
+    fn cell_center(x: u32, y: u32) -> FixedPoint {
+        FixedPoint::new(x * CELL + CELL / 2, y * CELL + CELL / 2)
+    }
+
+    fn empty_grid() -> OccupancyGrid {
+        OccupancyGrid::new(
+            1 << GRID_WIDTH_LOG2,
+            1 << GRID_HEIGHT_LOG2,
+            vector![0.0, 0.0],
+            1.0,
+        )
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_registers() -> Result<(), Whatever> {
+        let runtime = make_runtime()?;
+        let mut dut = Dut::new(runtime.create_model_simple::<RrtAxiLite>()?)?;
+
+        dut.reset();
+        assert_eq!(dut.read_ok(STATUS), 0);
+
+        let registers = [
+            START_X,
+            START_Y,
+            GOAL_X,
+            GOAL_Y,
+            MIN_X,
+            MIN_Y,
+            MAX_X,
+            MAX_Y,
+            MOVE_DIST_REG,
+            SQ_DIST_TOL_LO,
+            SQ_DIST_TOL_HI,
+            GOAL_BIAS,
+            SEED_LO,
+            SEED_HI,
+            NUM_POINTS,
+        ];
+        for (i, &register) in registers.iter().enumerate() {
+            dut.write_ok(register, 0x1234_5600 + i as u32);
+        }
+        for (i, &register) in registers.iter().enumerate() {
+            assert_eq!(
+                dut.read_ok(register),
+                0x1234_5600 + i as u32,
+                "register {:#x}",
+                register
+            );
+        }
+
+        // Only the bytes with their strobe set get written
+        dut.write_ok(SEED_LO, 0xaabb_ccdd);
+        assert_eq!(
+            dut.axi_write_strobed(SEED_LO, 0x1111_1111, 0b0010),
+            AxiResp::Okay
+        );
+        assert_eq!(dut.read_ok(SEED_LO), 0xaabb_11dd);
+
+        // Read only registers stay put, and CONTROL doesn't hold onto anything
+        dut.write_ok(STATUS, 0xffff_ffff);
+        dut.write_ok(NODE_COUNT, 0xffff_ffff);
+        dut.write_ok(CONTROL, 0xffff_fffe);
+        assert_eq!(dut.read_ok(STATUS), 0);
+        assert_eq!(dut.read_ok(NODE_COUNT), 0);
+        assert_eq!(dut.read_ok(CONTROL), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_grid_window() -> Result<(), Whatever> {
+        let runtime = make_runtime()?;
+        let mut dut = Dut::new(runtime.create_model_simple::<RrtAxiLite>()?)?;
+
+        dut.reset();
+
+        let words = (1 << (GRID_WIDTH_LOG2 + GRID_HEIGHT_LOG2)) / GRID_DATA_WIDTH as u64;
+        for i in 0..words {
+            dut.write_ok(GRID_BASE + 4 * i, (0x5a ^ i as u32) & 0xff);
+        }
+        for i in 0..words {
+            assert_eq!(dut.read_ok(GRID_BASE + 4 * i), (0x5a ^ i as u32) & 0xff);
+        }
+
+        // The tree is the core's to write
+        assert_eq!(dut.axi_write(TREE_BASE, 1), AxiResp::SlvErr);
+
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_run_finds_goal() -> Result<(), Whatever> {
+        let runtime = make_runtime()?;
+        let mut dut = Dut::new(runtime.create_model_simple::<RrtAxiLite>()?)?;
+
+        let mut grid = empty_grid();
+        for y in 0..6 {
+            *grid.cell_mut(4, y) = true;
+        }
+        let start = cell_center(1, 1);
+        let goal = cell_center(6, 1);
+
+        dut.reset();
+        dut.load_grid(&grid);
+        dut.configure(start, goal, 0x5eed);
+        let status = dut.run()?;
+
+        assert_ne!(status & STATUS_DONE, 0);
+        assert_ne!(status & STATUS_FOUND, 0, "goal not found");
+
+        let nodes = dut.read_tree();
+        assert_eq!(nodes[0].point, start);
+        assert_eq!(nodes[0].parent, 0);
+        for (i, node) in nodes.iter().enumerate().skip(1) {
+            assert!(node.parent < i, "node {} has parent {}", i, node.parent);
+            assert!(
+                !fixed::is_segment_occupied(&nodes[node.parent].point, &node.point, &grid),
+                "edge into node {} goes through the wall",
+                i
+            );
+        }
+
+        let goal_node = &nodes[dut.read_ok(GOAL_NODE) as usize];
+        assert!(goal_node.point.sq_dist(&goal) < (MOVE_DIST as u128).pow(2));
+
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_memories_locked_while_busy() -> Result<(), Whatever> {
+        let runtime = make_runtime()?;
+        let mut dut = Dut::new(runtime.create_model_simple::<RrtAxiLite>()?)?;
+
+        dut.reset();
+        dut.load_grid(&empty_grid());
+        // Nowhere near the goal, so it runs until the tree is full
+        dut.configure(cell_center(1, 1), FixedPoint::new(u32::MAX, u32::MAX), 1);
+        dut.write_ok(SQ_DIST_TOL_LO, 0);
+        dut.write_ok(SQ_DIST_TOL_HI, 0);
+        dut.write_ok(GRID_BASE, 0);
+
+        dut.write_ok(CONTROL, 1);
+        assert_ne!(dut.read_ok(STATUS) & STATUS_BUSY, 0);
+
+        assert_eq!(dut.axi_read(GRID_BASE), (0, AxiResp::SlvErr));
+        assert_eq!(dut.axi_read(TREE_BASE), (0, AxiResp::SlvErr));
+        assert_eq!(dut.axi_write(GRID_BASE, 0xff), AxiResp::SlvErr);
+        assert_eq!(dut.axi_write(CONTROL, 1), AxiResp::SlvErr);
+
+        // Registers still work
+        dut.write_ok(GOAL_BIAS, 7);
+        assert_eq!(dut.read_ok(GOAL_BIAS), 7);
+
+        let status = dut.wait_until_idle()?;
+        assert_eq!(status & STATUS_FOUND, 0);
+        assert_eq!(dut.read_ok(NODE_COUNT), 1 << TREE_ADDR_WIDTH);
+
+        // The write while busy didn't land
+        assert_eq!(dut.read_ok(GRID_BASE), 0);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,343 @@
+#[cfg(test)]
+mod tests {
+    use marlin::verilator::VerilatorRuntime;
+    use marlin::verilog::prelude::*;
+    use na::vector;
+    use snafu::{Whatever, whatever};
+
+    use crate::fpga::fixed::{self, FixedPoint, GridMapping};
+    use crate::fpga::loader;
+    use crate::fpga::perf::{PerfModel, PipelineConfig, Problem};
+    use crate::fpga::protocol::RunParams;
+    use crate::fpga::verilog::test::bench::{
+        self, Clocked, Dut, MemoryRequester, impl_clocked, impl_memory_requester,
+    };
+    use crate::shared::grid::OccupancyGrid;
+
+    const GRID_WIDTH_LOG2: u32 = 3;
+    const GRID_HEIGHT_LOG2: u32 = 3;
+    const GRID_DATA_WIDTH: usize = 8;
+    const TREE_ADDR_WIDTH: u32 = 7;
+
+    const CELL: u32 = 1 << (fixed::POINT_BITS - GRID_WIDTH_LOG2);
+    const MOVE_DIST: u32 = CELL / 2;
+
+    const MAX_CYCLES: usize = 5_000_000;
+
+    #[verilog(
+        src = "src/fpga/verilog/src/rrt_host_top.sv",
+        name = "rrt_host_top",
+        params = {
+            GRID_WIDTH_LOG2: 3,
+            GRID_HEIGHT_LOG2: 3,
+            GRID_DATA_WIDTH: 8,
+            GRID_ADDR_WIDTH: 3,
+            TREE_ADDR_WIDTH: 7,
+            STEER_ITERATIONS: 24
+        },
+        includes = ["src/fpga/verilog/src/"]
+    )]
+    pub struct RrtHostTop;
+
+    struct Node {
+        point: FixedPoint,
+        parent: usize,
+    }
+
+    impl_clocked!(RrtHostTop);
+    impl_memory_requester!(RrtHostTop {
+        address: host_grid_address,
+        write_data: host_grid_write_data,
+        write_enable: host_grid_write_enable,
+    });
+
+    impl<'ctx> Dut<RrtHostTop<'ctx>> {
+        fn reset(&mut self) {
+            self.input_valid = 0;
+            self.host_select = 0;
+            self.put_memory_access(None);
+            self.pulse_reset();
+        }
+
+        fn load_grid(&mut self, grid: &OccupancyGrid) {
+            let (x_cells, y_cells) = grid.size();
+
+            self.host_select = 1;
+            for address in 0..(x_cells * y_cells / GRID_DATA_WIDTH) {
+                let mut word = 0;
+                for bit in 0..GRID_DATA_WIDTH {
+                    let linear = address * GRID_DATA_WIDTH + bit;
+                    if *grid.cell(linear % x_cells, linear / x_cells) {
+                        word |= 1 << bit;
+                    }
+                }
+
+                self.write_word(address as u64, word);
+            }
+            self.host_select = 0;
+        }
+
+        /// Runs until done, returning how many cycles that took, counting the one the start was
+        /// taken in.
+        fn run(&mut self) -> Result<usize, Whatever> {
+            self.input_valid = 1;
+            self.tick();
+            self.input_valid = 0;
+
+            for cycles in 1..MAX_CYCLES {
+                if self.done != 0 {
+                    return Ok(cycles);
+                }
+                self.tick();
+            }
+
+            whatever!("rrt_top didn't finish in {} cycles", MAX_CYCLES)
+        }
+
+        fn read_tree(&mut self) -> Vec<Node> {
+            self.host_select = 1;
+            let nodes = (0..self.node_count)
+                .map(|i| {
+                    self.host_tree_address = i;
+                    self.tick();
+                    Node {
+                        point: FixedPoint::from_bits(self.host_tree_point),
+                        parent: self.host_tree_parent as usize,
+                    }
+                })
+                .collect();
+            self.host_select = 0;
+            nodes
+        }
+    }
+
+    fn make_runtime() -> Result<VerilatorRuntime, Whatever> {
+        bench::runtime("src/fpga/verilog/src/rrt_host_top.sv")
+    }
+
+    fn cell_center(x: u32, y: u32) -> FixedPoint {
+        FixedPoint::new(x * CELL + CELL / 2, y * CELL + CELL / 2)
+    }
+
+    fn empty_grid() -> OccupancyGrid {
+        OccupancyGrid::new(
+            1 << GRID_WIDTH_LOG2,
+            1 << GRID_HEIGHT_LOG2,
+            vector![0.0, 0.0],
+            1.0,
+        )
+    }
+
+    fn configure(dut: &mut RrtHostTop, start: FixedPoint, goal: FixedPoint, seed: u64) {
+        dut.start = start.to_bits();
+        dut.goal = goal.to_bits();
+        dut.min_bound = FixedPoint::new(0, 0).to_bits();
+        dut.max_bound = FixedPoint::new(u32::MAX, u32::MAX).to_bits();
+        dut.move_dist = MOVE_DIST;
+        dut.sq_dist_tol = (MOVE_DIST as u64).pow(2);
+        dut.goal_bias = 1 << 13;
+        dut.seed = seed;
+        dut.num_points = (1 << TREE_ADDR_WIDTH) as u8;
+    }
+
+    fn check_tree(nodes: &[Node], start: FixedPoint, grid: &OccupancyGrid) {
+        assert_eq!(nodes[0].point, start);
+        assert_eq!(nodes[0].parent, 0);
+
+        // Allow for the steering error on each axis
+        let max_error = 2.0 * ((MOVE_DIST >> (24 - 2)) + 2) as f64;
+
+        for (i, node) in nodes.iter().enumerate().skip(1) {
+            assert!(node.parent < i, "node {} has parent {}", i, node.parent);
+
+            let parent = nodes[node.parent].point;
+            assert!(
+                !fixed::is_segment_occupied(&parent, &node.point, grid),
+                "edge {:?} -> {:?} goes through an obstacle",
+                parent,
+                node.point
+            );
+
+            let dist = (parent.sq_dist(&node.point) as f64).sqrt();
+            assert!(
+                (dist - MOVE_DIST as f64).abs() <= max_error,
+                "edge {:?} -> {:?} has length {}",
+                parent,
+                node.point,
+                dist
+            );
+        }
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_open_grid_finds_goal() -> Result<(), Whatever> {
+        let runtime = make_runtime()?;
+        let mut dut = Dut::new(runtime.create_model_simple::<RrtHostTop>()?)?;
+
+        let grid = empty_grid();
+        let start = cell_center(1, 1);
+        let goal = cell_center(6, 5);
+
+        dut.reset();
+        dut.load_grid(&grid);
+        configure(&mut dut, start, goal, 0x5eed);
+        dut.run()?;
+
+        assert_eq!(dut.found, 1, "goal not found on an empty grid");
+
+        let nodes = dut.read_tree();
+        check_tree(&nodes, start, &grid);
+
+        let goal_node = &nodes[dut.goal_node as usize];
+        assert!(goal_node.point.sq_dist(&goal) < (MOVE_DIST as u128).pow(2));
+
+        // Following the parents from the goal node has to get back to the start
+        let mut current = dut.goal_node as usize;
+        let mut steps = 0;
+        while current != 0 {
+            current = nodes[current].parent;
+            steps += 1;
+            assert!(steps < nodes.len(), "parent links have a cycle");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_tree_avoids_wall() -> Result<(), Whatever> {
+        let runtime = make_runtime()?;
+        let mut dut = Dut::new(runtime.create_model_simple::<RrtHostTop>()?)?;
+
+        let mut grid = empty_grid();
+        for y in 0..6 {
+            *grid.cell_mut(4, y) = true;
+        }
+        *grid.cell_mut(2, 4) = true;
+        *grid.cell_mut(6, 6) = true;
+
+        let start = cell_center(1, 1);
+        let goal = cell_center(6, 1);
+
+        dut.reset();
+        dut.load_grid(&grid);
+
+        for seed in [1, 0xdead_beef, 0x1234_5678_9abc_def0] {
+            configure(&mut dut, start, goal, seed);
+            dut.run()?;
+
+            let nodes = dut.read_tree();
+            assert!(nodes.len() > 1);
+            check_tree(&nodes, start, &grid);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_map_smaller_than_grid() -> Result<(), Whatever> {
+        let runtime = make_runtime()?;
+        let mut dut = Dut::new(runtime.create_model_simple::<RrtHostTop>()?)?;
+
+        // 6x3, with a wall that only leaves the top row open
+        let mut map = OccupancyGrid::new(6, 3, vector![0.0, 0.0], 1.0);
+        for y in 0..2 {
+            *map.cell_mut(3, y) = true;
+        }
+        let mapping = GridMapping::new(&map, GRID_WIDTH_LOG2);
+
+        // The padding is left free in memory, so only the bounds keep the tree out of it
+        let mut grid = empty_grid();
+        let mut padded = empty_grid();
+        for y in 0..1 << GRID_HEIGHT_LOG2 {
+            for x in 0..1 << GRID_WIDTH_LOG2 {
+                let on_map = x < 6 && y < 3;
+                *grid.cell_mut(x, y) = on_map && *map.cell(x, y);
+                *padded.cell_mut(x, y) = !on_map || *map.cell(x, y);
+            }
+        }
+
+        let start = mapping.to_fixed(&vector![0.5, 0.5]);
+        let goal = mapping.to_fixed(&vector![5.5, 0.5]);
+        let (min_bound, max_bound) =
+            mapping.bounds_to_fixed(&vector![-10.0, -10.0], &vector![10.0, 10.0]);
+
+        dut.reset();
+        dut.load_grid(&grid);
+
+        for seed in [7, 0xfeed_f00d] {
+            configure(&mut dut, start, goal, seed);
+            dut.min_bound = min_bound.to_bits();
+            dut.max_bound = max_bound.to_bits();
+            dut.run()?;
+
+            let nodes = dut.read_tree();
+            assert!(nodes.len() > 1);
+            check_tree(&nodes, start, &padded);
+
+            for node in &nodes {
+                assert!(
+                    node.point.x < mapping.extent().x && node.point.y < mapping.extent().y,
+                    "{:?} is off the map",
+                    node.point
+                );
+            }
+        }
+
+        // The host side pads with occupied cells, which is what check_tree saw
+        assert_eq!(
+            loader::pack_grid(&map, GRID_WIDTH_LOG2, GRID_HEIGHT_LOG2, GRID_DATA_WIDTH),
+            loader::pack_grid(&padded, GRID_WIDTH_LOG2, GRID_HEIGHT_LOG2, GRID_DATA_WIDTH)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_perf_model_cycles() -> Result<(), Whatever> {
+        let runtime = make_runtime()?;
+        let mut dut = Dut::new(runtime.create_model_simple::<RrtHostTop>()?)?;
+
+        // Nothing to hit, and no way to get close enough to the goal, so every iteration adds a
+        // node, and the model's tree ends up the same size as the hardware's
+        let grid = empty_grid();
+        let problem = Problem {
+            start: cell_center(1, 1),
+            goal: cell_center(6, 5),
+            min_bound: FixedPoint::new(0, 0),
+            max_bound: FixedPoint::new(u32::MAX, u32::MAX),
+            seed: 0x5eed,
+        };
+        let params = RunParams {
+            move_dist: MOVE_DIST,
+            sq_dist_tol: 0,
+            goal_bias: 1 << 13,
+            num_points: 64,
+        };
+
+        dut.reset();
+        dut.load_grid(&grid);
+        configure(&mut dut, problem.start, problem.goal, problem.seed);
+        dut.sq_dist_tol = params.sq_dist_tol;
+        dut.num_points = params.num_points as u8;
+        let cycles = dut.run()?;
+
+        let model = PerfModel::new(PipelineConfig::rrt_top(GRID_DATA_WIDTH, 24));
+        let estimate = model.estimate(&grid, &problem, &params);
+        assert_eq!(estimate.node_count, dut.node_count as usize);
+
+        // Steering isn't exact in the hardware, so the segments can walk slightly different cells
+        let error = (estimate.cycles() - cycles as f64).abs() / cycles as f64;
+        assert!(
+            error < 0.1,
+            "model says {} cycles, hardware took {}",
+            estimate.cycles(),
+            cycles
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,177 @@
+#[cfg(test)]
+mod tests {
+    use marlin::verilator::VerilatorRuntime;
+    use marlin::verilog::prelude::*;
+    use snafu::Whatever;
+
+    use crate::fpga::fixed::FixedPoint;
+    use crate::fpga::verilog::test::bench::{self, Clocked, Dut, impl_clocked};
+
+    const NUM_SAMPLES: usize = 4000;
+    const NUM_BINS: usize = 8;
+
+    // Chi-squared critical value for 7 degrees of freedom at p = 0.001. A correct sampler fails
+    // this about once in a thousand runs.
+    const CHI_SQUARED_LIMIT: f64 = 24.32;
+
+    #[verilog(
+        src = "src/fpga/verilog/test/wrappers/sampler_wrapper.sv",
+        name = "sampler_wrapper",
+        includes = ["src/fpga/verilog/src/"]
+    )]
+    pub struct SamplerWrapper;
+
+    impl_clocked!(SamplerWrapper);
+
+    impl<'ctx> Dut<SamplerWrapper<'ctx>> {
+        fn reset(&mut self, seed: u64) {
+            self.seed = seed;
+            self.input_valid = 0;
+            self.pulse_reset();
+        }
+
+        fn configure(&mut self, min: FixedPoint, max: FixedPoint, goal: FixedPoint, bias: u16) {
+            self.min_bound = min.to_bits();
+            self.max_bound = max.to_bits();
+            self.goal = goal.to_bits();
+            self.goal_bias = bias;
+        }
+
+        fn sample(&mut self) -> FixedPoint {
+            self.input_valid = 1;
+            self.tick();
+            self.input_valid = 0;
+
+            while self.done == 0 {
+                self.tick();
+            }
+
+            FixedPoint::from_bits(self.sample)
+        }
+    }
+
+    fn make_runtime() -> Result<VerilatorRuntime, Whatever> {
+        bench::runtime("src/fpga/verilog/test/wrappers/sampler_wrapper.sv")
+    }
+
+    fn chi_squared(bins: &[usize], total: usize) -> f64 {
+        let expected = total as f64 / bins.len() as f64;
+        bins.iter()
+            .map(|&count| (count as f64 - expected).powi(2) / expected)
+            .sum()
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_uniform_within_bounds() -> Result<(), Whatever> {
+        let runtime = make_runtime()?;
+        let mut dut = Dut::new(runtime.create_model_simple::<SamplerWrapper>()?)?;
+
+        let min = FixedPoint::new(1000, 1 << 31);
+        let max = FixedPoint::new(1000 + (1 << 20), (1 << 31) + (3 << 16));
+        let bin_width_x = (max.x - min.x) as u64 / NUM_BINS as u64;
+        let bin_width_y = (max.y - min.y) as u64 / NUM_BINS as u64;
+
+        dut.reset(0x1234_5678_9abc_def0);
+        dut.configure(min, max, FixedPoint::new(0, 0), 0);
+
+        let mut x_bins = [0; NUM_BINS];
+        let mut y_bins = [0; NUM_BINS];
+
+        for _ in 0..NUM_SAMPLES {
+            let p = dut.sample();
+            assert!(
+                min.x <= p.x && p.x < max.x && min.y <= p.y && p.y < max.y,
+                "{:?} outside of [{:?}, {:?})",
+                p,
+                min,
+                max
+            );
+
+            x_bins[((p.x - min.x) as u64 / bin_width_x) as usize] += 1;
+            y_bins[((p.y - min.y) as u64 / bin_width_y) as usize] += 1;
+        }
+
+        let x_stat = chi_squared(&x_bins, NUM_SAMPLES);
+        let y_stat = chi_squared(&y_bins, NUM_SAMPLES);
+        assert!(x_stat < CHI_SQUARED_LIMIT, "x not uniform: {:?}", x_bins);
+        assert!(y_stat < CHI_SQUARED_LIMIT, "y not uniform: {:?}", y_bins);
+
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_full_and_degenerate_range() -> Result<(), Whatever> {
+        let runtime = make_runtime()?;
+        let mut dut = Dut::new(runtime.create_model_simple::<SamplerWrapper>()?)?;
+
+        dut.reset(42);
+
+        // min == max is an empty range, so it always gives back min
+        let p = FixedPoint::new(1234, 5678);
+        dut.configure(p, p, FixedPoint::new(0, 0), 0);
+        for _ in 0..20 {
+            assert_eq!(dut.sample(), p);
+        }
+
+        // The whole point range shouldn't overflow, and should reach both halves of it
+        dut.configure(
+            FixedPoint::new(0, 0),
+            FixedPoint::new(u32::MAX, u32::MAX),
+            FixedPoint::new(0, 0),
+            0,
+        );
+        let mut seen_high = false;
+        let mut seen_low = false;
+        for _ in 0..100 {
+            let p = dut.sample();
+            seen_high |= p.x >= 1 << 31;
+            seen_low |= p.x < 1 << 31;
+        }
+        assert!(seen_high && seen_low);
+
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_goal_bias() -> Result<(), Whatever> {
+        let runtime = make_runtime()?;
+        let mut dut = Dut::new(runtime.create_model_simple::<SamplerWrapper>()?)?;
+
+        let min = FixedPoint::new(0, 0);
+        let max = FixedPoint::new(1 << 20, 1 << 20);
+        // Outside of the bounds so uniform samples can never hit it
+        let goal = FixedPoint::new(1 << 30, 1 << 30);
+
+        dut.reset(7);
+
+        dut.configure(min, max, goal, 0);
+        for _ in 0..NUM_SAMPLES / 4 {
+            assert_ne!(dut.sample(), goal, "sampled goal with no bias");
+        }
+
+        // 1/4 of samples should be the goal. The standard deviation of the count is about 27, so
+        // allow 7 of them either way.
+        dut.configure(min, max, goal, 1 << 14);
+        let mut goal_count: usize = 0;
+        for _ in 0..NUM_SAMPLES {
+            let p = dut.sample();
+            if p == goal {
+                goal_count += 1;
+            } else {
+                assert!(p.x < max.x && p.y < max.y);
+            }
+        }
+        let expected = NUM_SAMPLES / 4;
+        assert!(
+            goal_count.abs_diff(expected) < 200,
+            "expected about {} goal samples, got {}",
+            expected,
+            goal_count
+        );
+
+        Ok(())
+    }
+}
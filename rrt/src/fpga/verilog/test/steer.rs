@@ -0,0 +1,163 @@
+#[cfg(test)]
+mod tests {
+    use marlin::verilator::VerilatorRuntime;
+    use marlin::verilog::prelude::*;
+    use rand::RngExt;
+    use snafu::Whatever;
+
+    use crate::fpga::fixed::{self, FixedPoint};
+    use crate::fpga::verilog::test::bench::{self, Clocked, Dut, impl_clocked};
+
+    const ITERATIONS: u32 = 24;
+
+    #[verilog(
+        src = "src/fpga/verilog/test/wrappers/steer_wrapper.sv",
+        name = "steer_wrapper",
+        params = { ITERATIONS: 24 },
+        includes = ["src/fpga/verilog/src/"]
+    )]
+    pub struct SteerWrapper;
+
+    impl_clocked!(SteerWrapper);
+
+    impl<'ctx> Dut<SteerWrapper<'ctx>> {
+        fn reset(&mut self) {
+            self.from = 0;
+            self.toward = 0;
+            self.move_dist = 0;
+            self.input_valid = 0;
+            self.pulse_reset();
+        }
+
+        fn steer(
+            &mut self,
+            from: &FixedPoint,
+            toward: &FixedPoint,
+            move_dist: u32,
+        ) -> Option<FixedPoint> {
+            self.from = from.to_bits();
+            self.toward = toward.to_bits();
+            self.move_dist = move_dist;
+            self.input_valid = 1;
+            self.tick();
+            self.input_valid = 0;
+
+            while self.done == 0 {
+                self.tick();
+            }
+
+            if self.out_of_bounds != 0 {
+                None
+            } else {
+                Some(FixedPoint::from_bits(self.result))
+            }
+        }
+    }
+
+    fn make_runtime() -> Result<VerilatorRuntime, Whatever> {
+        bench::runtime("src/fpga/verilog/test/wrappers/steer_wrapper.sv")
+    }
+
+    /// The error bound stated in `steer.sv`, for each coordinate.
+    fn max_error(move_dist: u32) -> u32 {
+        (move_dist >> (ITERATIONS - 2)) + 2
+    }
+
+    fn assert_close(from: &FixedPoint, toward: &FixedPoint, move_dist: u32, got: FixedPoint) {
+        let expected = fixed::steer(from, toward, move_dist).unwrap();
+        let tol = max_error(move_dist);
+        assert!(
+            got.x.abs_diff(expected.x) <= tol && got.y.abs_diff(expected.y) <= tol,
+            "steer({:?}, {:?}, {}): expected {:?} within {}, got {:?}",
+            from,
+            toward,
+            move_dist,
+            expected,
+            tol,
+            got
+        );
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_axis_aligned() -> Result<(), Whatever> {
+        let runtime = make_runtime()?;
+        let mut dut = Dut::new(runtime.create_model_simple::<SteerWrapper>()?)?;
+
+        dut.reset();
+
+        let from = FixedPoint::new(1 << 31, 1 << 31);
+        let move_dist = 1 << 20;
+
+        for toward in [
+            FixedPoint::new(u32::MAX, 1 << 31),
+            FixedPoint::new(0, 1 << 31),
+            FixedPoint::new(1 << 31, u32::MAX),
+            FixedPoint::new(1 << 31, 0),
+        ] {
+            let got = dut.steer(&from, &toward, move_dist).unwrap();
+            assert_close(&from, &toward, move_dist, got);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_random() -> Result<(), Whatever> {
+        let runtime = make_runtime()?;
+        let mut dut = Dut::new(runtime.create_model_simple::<SteerWrapper>()?)?;
+
+        dut.reset();
+
+        let mut rng = rand::rng();
+
+        for _ in 0..500 {
+            // Stay away from the edges so rounding can't push the result out of bounds
+            let from = FixedPoint::new(
+                rng.random_range((1 << 30)..(3 << 30)),
+                rng.random_range((1 << 30)..(3 << 30)),
+            );
+
+            // Try both tiny and huge deltas, since the CORDIC normalizes them differently
+            let spread: u32 = 1 << rng.random_range(0..30);
+            let toward = FixedPoint::new(
+                from.x - spread / 2 + rng.random_range(0..=spread),
+                from.y - spread / 2 + rng.random_range(0..=spread),
+            );
+            if toward == from {
+                continue;
+            }
+
+            let move_dist = rng.random_range(1..(1 << 29));
+
+            let got = dut.steer(&from, &toward, move_dist).unwrap();
+            assert_close(&from, &toward, move_dist, got);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_out_of_bounds() -> Result<(), Whatever> {
+        let runtime = make_runtime()?;
+        let mut dut = Dut::new(runtime.create_model_simple::<SteerWrapper>()?)?;
+
+        dut.reset();
+
+        let p = FixedPoint::new(1000, 1000);
+        assert_eq!(dut.steer(&p, &p, 100), None, "no direction to move in");
+
+        assert_eq!(dut.steer(&p, &FixedPoint::new(0, 1000), 2000), None);
+        assert_eq!(dut.steer(&p, &FixedPoint::new(1000, 0), 2000), None);
+
+        let edge = FixedPoint::new(u32::MAX - 10, u32::MAX - 10);
+        assert_eq!(
+            dut.steer(&edge, &FixedPoint::new(u32::MAX, u32::MAX), 1000),
+            None
+        );
+
+        Ok(())
+    }
+}
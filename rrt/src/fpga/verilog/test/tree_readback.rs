@@ -0,0 +1,206 @@
+#[cfg(test)]
+mod tests {
+    use marlin::verilator::VerilatorRuntime;
+    use marlin::verilog::prelude::*;
+    use na::vector;
+    use rand::RngExt;
+    use snafu::{Whatever, whatever};
+
+    use crate::fpga::fixed::{FixedPoint, GridMapping};
+    use crate::fpga::readback::{self, ReadbackNode};
+    use crate::fpga::verilog::test::bench::{self, Clocked, Dut, impl_clocked};
+    use crate::shared::grid::OccupancyGrid;
+
+    const TREE_ADDR_WIDTH: u32 = 5;
+    const MAX_CYCLES: usize = 10_000;
+
+    #[verilog(
+        src = "src/fpga/verilog/test/wrappers/tree_readback_wrapper.sv",
+        name = "tree_readback_wrapper",
+        params = { TREE_ADDR_WIDTH: 5 },
+        includes = ["src/fpga/verilog/src/"]
+    )]
+    pub struct TreeReadbackWrapper;
+
+    impl_clocked!(TreeReadbackWrapper);
+
+    impl<'ctx> Dut<TreeReadbackWrapper<'ctx>> {
+        fn reset(&mut self) {
+            self.input_valid = 0;
+            self.out_ready = 0;
+            self.host_request = 0;
+            self.host_write_enable = 0;
+            self.pulse_reset();
+        }
+
+        /// Writes the tree in through the host port, which always gets the memory first.
+        fn load_tree(&mut self, nodes: &[ReadbackNode]) {
+            for node in nodes {
+                self.host_request = 1;
+                self.host_address = node.index as u8;
+                self.host_point = node.point.to_bits();
+                self.host_parent = node.parent as u8;
+                self.host_write_enable = 1;
+                self.tick();
+            }
+            self.host_request = 0;
+            self.host_write_enable = 0;
+        }
+
+        fn start(&mut self, full_tree: bool, goal_node: usize, node_count: usize) {
+            while self.ready_for_input == 0 {
+                self.tick();
+            }
+
+            self.full_tree = full_tree as u8;
+            self.goal_node = goal_node as u8;
+            self.node_count = node_count as u8;
+            self.input_valid = 1;
+            self.tick();
+            self.input_valid = 0;
+        }
+
+        /// Takes nodes off the output until the last one, with `ready` deciding whether to take
+        /// one on each cycle, and `host_busy` whether the host holds the memory.
+        fn collect(
+            &mut self,
+            mut ready: impl FnMut() -> bool,
+            mut host_busy: impl FnMut() -> bool,
+        ) -> Result<Vec<ReadbackNode>, Whatever> {
+            let mut nodes = Vec::new();
+
+            for _ in 0..MAX_CYCLES {
+                // A read the readback has to wait behind
+                self.host_request = host_busy() as u8;
+                self.host_write_enable = 0;
+                self.out_ready = ready() as u8;
+                self.settle();
+
+                let taken = self.out_valid != 0 && self.out_ready != 0;
+                let last = self.out_last != 0;
+                if taken {
+                    nodes.push(ReadbackNode {
+                        index: self.out_index as usize,
+                        point: FixedPoint::from_bits(self.out_point),
+                        parent: self.out_parent as usize,
+                    });
+                }
+
+                self.tick();
+                if taken && last {
+                    self.host_request = 0;
+                    self.out_ready = 0;
+                    return Ok(nodes);
+                }
+            }
+
+            whatever!("readback didn't finish in {} cycles", MAX_CYCLES)
+        }
+    }
+
+    fn make_runtime() -> Result<VerilatorRuntime, Whatever> {
+        bench::runtime("src/fpga/verilog/test/wrappers/tree_readback_wrapper.sv")
+    }
+
+    /// A tree of `count` nodes shaped like the ones `rrt_top` builds, with every parent before
+    /// its child.
+    fn random_tree(count: usize) -> Vec<ReadbackNode> {
+        let mut rng = rand::rng();
+        (0..count)
+            .map(|index| ReadbackNode {
+                index,
+                point: FixedPoint::new(rng.random(), rng.random()),
+                parent: if index == 0 {
+                    0
+                } else {
+                    rng.random_range(0..index)
+                },
+            })
+            .collect()
+    }
+
+    /// The path the readback should send, goal first.
+    fn expected_path(tree: &[ReadbackNode], goal_node: usize) -> Vec<ReadbackNode> {
+        let mut path = vec![tree[goal_node]];
+        while path.last().unwrap().index != 0 {
+            path.push(tree[path.last().unwrap().parent]);
+        }
+        path
+    }
+
+    fn mapping() -> GridMapping {
+        GridMapping::new(&OccupancyGrid::new(4, 4, vector![0.0, 0.0], 1.0), 2)
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_path() -> Result<(), Whatever> {
+        let runtime = make_runtime()?;
+        let mut dut = Dut::new(runtime.create_model_simple::<TreeReadbackWrapper>()?)?;
+
+        let tree = random_tree(1 << TREE_ADDR_WIDTH);
+
+        dut.reset();
+        dut.load_tree(&tree);
+
+        for goal_node in [tree.len() - 1, 7, 1, 0] {
+            dut.start(false, goal_node, tree.len());
+            let nodes = dut.collect(|| true, || false)?;
+            assert_eq!(nodes, expected_path(&tree, goal_node));
+
+            let result = readback::decode_path(&nodes, &mapping()).unwrap();
+            assert_eq!(result.path.unwrap().len(), nodes.len());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_full_tree() -> Result<(), Whatever> {
+        let runtime = make_runtime()?;
+        let mut dut = Dut::new(runtime.create_model_simple::<TreeReadbackWrapper>()?)?;
+
+        dut.reset();
+
+        // A partly filled tree only sends the nodes it has
+        for count in [1 << TREE_ADDR_WIDTH, 13, 1] {
+            let tree = random_tree(count);
+            dut.load_tree(&tree);
+
+            dut.start(true, 0, count);
+            let nodes = dut.collect(|| true, || false)?;
+            assert_eq!(nodes, tree);
+
+            let result = readback::decode_tree(&nodes, Some(count - 1), &mapping()).unwrap();
+            assert_eq!(result.points.len(), count);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    #[snafu::report]
+    fn test_backpressure_and_shared_memory() -> Result<(), Whatever> {
+        let runtime = make_runtime()?;
+        let mut dut = Dut::new(runtime.create_model_simple::<TreeReadbackWrapper>()?)?;
+
+        let tree = random_tree(1 << TREE_ADDR_WIDTH);
+        let mut rng = rand::rng();
+        let mut host_rng = rand::rng();
+
+        dut.reset();
+        dut.load_tree(&tree);
+
+        dut.start(true, 0, tree.len());
+        let nodes = dut.collect(|| rng.random_bool(0.3), || host_rng.random_bool(0.5))?;
+        assert_eq!(nodes, tree);
+
+        let goal_node = tree.len() - 2;
+        dut.start(false, goal_node, tree.len());
+        let nodes = dut.collect(|| rng.random_bool(0.3), || host_rng.random_bool(0.5))?;
+        assert_eq!(nodes, expected_path(&tree, goal_node));
+
+        Ok(())
+    }
+}
@@ -29,5 +29,6 @@ module bram_wrapper #(
         bus_read_data = bus.read_data;
         bus.write_data = bus_write_data;
         bus.write_enable = bus_write_enable;
+        bus.request = '1;
     end
 endmodule
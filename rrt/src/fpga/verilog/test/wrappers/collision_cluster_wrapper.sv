@@ -0,0 +1,58 @@
+`include "collision_cluster.sv"
+
+module collision_cluster_wrapper #(
+    parameter NUM_UNITS,
+    parameter GRID_WIDTH_LOG2,
+    parameter GRID_HEIGHT_LOG2,
+    parameter POINT_BITS,
+    parameter TAG_WIDTH,
+    parameter ADDR_WIDTH,
+    parameter DATA_WIDTH
+) (
+    input logic clk,
+    input logic rst_n,
+
+    input logic [2*POINT_BITS-1:0] a,
+    input logic [2*POINT_BITS-1:0] b,
+    input logic [TAG_WIDTH-1:0] tag,
+    input logic input_valid,
+    output logic ready_for_input,
+
+    output logic result_valid,
+    output logic [TAG_WIDTH-1:0] result_tag,
+    output logic result_occupied,
+
+    input logic load_request,
+    input logic [ADDR_WIDTH-1:0] load_address,
+    input logic [DATA_WIDTH-1:0] load_write_data,
+    input logic load_write_enable,
+    output logic load_grant
+);
+    memory_bus #(.ADDR_WIDTH(ADDR_WIDTH), .DATA_WIDTH(DATA_WIDTH)) load ();
+
+    assign load.request = load_request;
+    assign load.address = load_address;
+    assign load.write_data = load_write_data;
+    assign load.write_enable = load_write_enable;
+    assign load_grant = load.grant;
+
+    collision_cluster #(
+        .NUM_UNITS(NUM_UNITS),
+        .GRID_WIDTH_LOG2(GRID_WIDTH_LOG2),
+        .GRID_HEIGHT_LOG2(GRID_HEIGHT_LOG2),
+        .POINT_BITS(POINT_BITS),
+        .TAG_WIDTH(TAG_WIDTH)
+    ) uut (
+        .clk(clk),
+        .rst_n(rst_n),
+        .a(a),
+        .b(b),
+        .tag(tag),
+        .input_valid(input_valid),
+        .ready_for_input(ready_for_input),
+        .result_valid(result_valid),
+        .result_tag(result_tag),
+        .result_occupied(result_occupied),
+        .load(load.memory)
+    );
+endmodule
@@ -0,0 +1,86 @@
+`include "bram.sv"
+`include "directed_energy_weapon.sv"
+`include "memory_arbiter.sv"
+`include "occupancy_grid.sv"
+
+// A directed_energy_weapon on a real occupancy_grid and bram, instead of a grid answered from the
+// test. The host port shares the bram with the grid, ahead of it, to load the grid and read back
+// what got marked.
+module directed_energy_weapon_grid_wrapper #(
+    parameter GRID_WIDTH_LOG2,
+    parameter GRID_HEIGHT_LOG2,
+    parameter POINT_BITS,
+    parameter ADDR_WIDTH,
+    parameter DATA_WIDTH
+) (
+    input logic clk,
+    input logic rst_n,
+
+    input logic [2*POINT_BITS-1:0] a,
+    input logic [2*POINT_BITS-1:0] b,
+    input logic mark,
+
+    output logic occupied,
+
+    input logic input_valid,
+    output logic done,
+
+    input logic host_request,
+    input logic [ADDR_WIDTH-1:0] host_address,
+    input logic [DATA_WIDTH-1:0] host_write_data,
+    input logic host_write_enable,
+    output logic host_grant,
+    output logic [DATA_WIDTH-1:0] host_read_data,
+
+    // Nothing the grid was told to write is still on its way to the bram
+    output logic grid_idle
+);
+    memory_bus #(.ADDR_WIDTH(ADDR_WIDTH), .DATA_WIDTH(DATA_WIDTH)) mem ();
+    memory_bus #(.ADDR_WIDTH(ADDR_WIDTH), .DATA_WIDTH(DATA_WIDTH)) clients [2] ();
+    occupancy_grid_bus #(.GRID_WIDTH_LOG2(GRID_WIDTH_LOG2), .GRID_HEIGHT_LOG2(GRID_HEIGHT_LOG2)) grid_bus ();
+
+    assign clients[0].request = host_request;
+    assign clients[0].address = host_address;
+    assign clients[0].write_data = host_write_data;
+    assign clients[0].write_enable = host_write_enable;
+    assign host_grant = clients[0].grant;
+    assign host_read_data = clients[0].read_data;
+
+    bram #(.ADDR_WIDTH(ADDR_WIDTH), .DATA_WIDTH(DATA_WIDTH)) bram_inst (
+        .clk(clk),
+        .bus(mem.memory)
+    );
+
+    memory_arbiter #(.NUM_CLIENTS(2), .ROUND_ROBIN(0)) arbiter (
+        .clk(clk),
+        .rst_n(rst_n),
+        .clients(clients),
+        .mem(mem.client)
+    );
+
+    // A write is in the stage for a cycle, and then in the buffer until it's written back
+    assign grid_idle = !(grid.stage_valid && grid.stage_write_enable) && !grid.pending_valid;
+
+    occupancy_grid #(.GRID_WIDTH_LOG2(GRID_WIDTH_LOG2), .GRID_HEIGHT_LOG2(GRID_HEIGHT_LOG2)) grid (
+        .clk(clk),
+        .rst_n(rst_n),
+        .bus(grid_bus.grid),
+        .mem(clients[1])
+    );
+
+    directed_energy_weapon #(
+        .GRID_WIDTH_LOG2(GRID_WIDTH_LOG2),
+        .GRID_HEIGHT_LOG2(GRID_HEIGHT_LOG2),
+        .POINT_BITS(POINT_BITS)
+    ) uut (
+        .clk(clk),
+        .rst_n(rst_n),
+        .a(a),
+        .b(b),
+        .mark(mark),
+        .occupied(occupied),
+        .input_valid(input_valid),
+        .done(done),
+        .grid_bus(grid_bus.client)
+    );
+endmodule
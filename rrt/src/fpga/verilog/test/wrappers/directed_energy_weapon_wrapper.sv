@@ -2,13 +2,15 @@
 
 module directed_energy_weapon_wrapper #(
     parameter GRID_WIDTH_LOG2,
-    parameter GRID_HEIGHT_LOG2
+    parameter GRID_HEIGHT_LOG2,
+    parameter POINT_BITS
 ) (
     input logic clk,
     input logic rst_n,
 
-    input logic [63:0] a,
-    input logic [63:0] b,
+    input logic [2*POINT_BITS-1:0] a,
+    input logic [2*POINT_BITS-1:0] b,
+    input logic mark,
 
     output logic occupied,
 
@@ -36,11 +38,16 @@ module directed_energy_weapon_wrapper #(
     assign grid_write_occupied = grid_bus.write_occupied;
     assign grid_bus.read_occupied = grid_read_occupied;
  
-    directed_energy_weapon #(.GRID_WIDTH_LOG2(GRID_WIDTH_LOG2), .GRID_HEIGHT_LOG2(GRID_HEIGHT_LOG2)) uut (
+    directed_energy_weapon #(
+        .GRID_WIDTH_LOG2(GRID_WIDTH_LOG2),
+        .GRID_HEIGHT_LOG2(GRID_HEIGHT_LOG2),
+        .POINT_BITS(POINT_BITS)
+    ) uut (
         .clk(clk),
         .rst_n(rst_n),
         .a(a),
         .b(b),
+        .mark(mark),
         .occupied(occupied),
         .input_valid(input_valid),
         .done(done),
@@ -0,0 +1,87 @@
+`include "bram.sv"
+`include "grid_loader.sv"
+`include "memory_arbiter.sv"
+`include "occupancy_grid.sv"
+`include "uart_rx.sv"
+
+// The loader and occupancy_grid share the grid memory, so the grid can be read back after loading
+module grid_loader_wrapper #(
+    parameter CLKS_PER_BIT,
+    parameter GRID_WIDTH_LOG2,
+    parameter GRID_HEIGHT_LOG2,
+    parameter DATA_WIDTH,
+    parameter ADDR_WIDTH
+) (
+    input logic clk,
+    input logic rst_n,
+
+    input logic rx,
+    output logic framing_error,
+
+    input logic load_start,
+    output logic load_done,
+
+    input logic [GRID_WIDTH_LOG2-1:0] cell_x,
+    input logic [GRID_HEIGHT_LOG2-1:0] cell_y,
+    input logic input_valid,
+    output logic output_valid,
+    output logic ready_for_input,
+    input logic write_enable,
+    input logic write_occupied,
+    output logic read_occupied
+);
+    memory_bus #(.ADDR_WIDTH(ADDR_WIDTH), .DATA_WIDTH(DATA_WIDTH)) mem ();
+    memory_bus #(.ADDR_WIDTH(ADDR_WIDTH), .DATA_WIDTH(DATA_WIDTH)) clients [2] ();
+
+    bram #(.ADDR_WIDTH(ADDR_WIDTH), .DATA_WIDTH(DATA_WIDTH)) bram_inst (
+        .clk(clk),
+        .bus(mem.memory)
+    );
+
+    memory_arbiter #(.NUM_CLIENTS(2), .ROUND_ROBIN(1)) arbiter (
+        .clk(clk),
+        .rst_n(rst_n),
+        .clients(clients),
+        .mem(mem.client)
+    );
+
+    logic [7:0] rx_data;
+    logic rx_valid;
+
+    uart_rx #(.CLKS_PER_BIT(CLKS_PER_BIT)) uart (
+        .clk(clk),
+        .rst_n(rst_n),
+        .rx(rx),
+        .data(rx_data),
+        .data_valid(rx_valid),
+        .framing_error(framing_error)
+    );
+
+    grid_loader #(.GRID_WIDTH_LOG2(GRID_WIDTH_LOG2), .GRID_HEIGHT_LOG2(GRID_HEIGHT_LOG2)) uut (
+        .clk(clk),
+        .rst_n(rst_n),
+        .start(load_start),
+        .done(load_done),
+        .data(rx_data),
+        .data_valid(rx_valid),
+        .mem(clients[0])
+    );
+
+    occupancy_grid_bus #(.GRID_WIDTH_LOG2(GRID_WIDTH_LOG2), .GRID_HEIGHT_LOG2(GRID_HEIGHT_LOG2)) grid_bus ();
+
+    assign grid_bus.cell_x = cell_x;
+    assign grid_bus.cell_y = cell_y;
+    assign grid_bus.input_valid = input_valid;
+    assign output_valid = grid_bus.output_valid;
+    assign ready_for_input = grid_bus.ready_for_input;
+    assign grid_bus.write_enable = write_enable;
+    assign grid_bus.write_occupied = write_occupied;
+    assign read_occupied = grid_bus.read_occupied;
+
+    occupancy_grid #(.GRID_WIDTH_LOG2(GRID_WIDTH_LOG2), .GRID_HEIGHT_LOG2(GRID_HEIGHT_LOG2)) grid (
+        .clk(clk),
+        .rst_n(rst_n),
+        .bus(grid_bus.grid),
+        .mem(clients[1])
+    );
+endmodule
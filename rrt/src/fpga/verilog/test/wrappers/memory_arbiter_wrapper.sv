@@ -0,0 +1,50 @@
+`include "bram.sv"
+`include "memory_arbiter.sv"
+
+module memory_arbiter_wrapper #(
+    parameter NUM_CLIENTS,
+    parameter ROUND_ROBIN,
+    parameter ADDR_WIDTH,
+    parameter DATA_WIDTH
+) (
+    input logic clk,
+    input logic rst_n,
+
+    // Client i's signals are at [i], [i*ADDR_WIDTH +: ADDR_WIDTH] and [i*DATA_WIDTH +: DATA_WIDTH]
+    input logic [NUM_CLIENTS-1:0] request,
+    input logic [NUM_CLIENTS*ADDR_WIDTH-1:0] address,
+    input logic [NUM_CLIENTS*DATA_WIDTH-1:0] write_data,
+    input logic [NUM_CLIENTS-1:0] write_enable,
+    output logic [NUM_CLIENTS-1:0] grant,
+
+    // The same for every client
+    output logic [DATA_WIDTH-1:0] read_data
+);
+    memory_bus #(.ADDR_WIDTH(ADDR_WIDTH), .DATA_WIDTH(DATA_WIDTH)) clients [NUM_CLIENTS] ();
+    memory_bus #(.ADDR_WIDTH(ADDR_WIDTH), .DATA_WIDTH(DATA_WIDTH)) mem ();
+
+    genvar i;
+    generate
+        for (i = 0; i < NUM_CLIENTS; i++) begin : g_clients
+            assign clients[i].request = request[i];
+            assign clients[i].address = address[i*ADDR_WIDTH +: ADDR_WIDTH];
+            assign clients[i].write_data = write_data[i*DATA_WIDTH +: DATA_WIDTH];
+            assign clients[i].write_enable = write_enable[i];
+            assign grant[i] = clients[i].grant;
+        end
+    endgenerate
+
+    assign read_data = clients[0].read_data;
+
+    bram #(.ADDR_WIDTH(ADDR_WIDTH), .DATA_WIDTH(DATA_WIDTH)) bram_inst (
+        .clk(clk),
+        .bus(mem.memory)
+    );
+
+    memory_arbiter #(.NUM_CLIENTS(NUM_CLIENTS), .ROUND_ROBIN(ROUND_ROBIN)) uut (
+        .clk(clk),
+        .rst_n(rst_n),
+        .clients(clients),
+        .mem(mem.client)
+    );
+endmodule
@@ -0,0 +1,75 @@
+`include "bram.sv"
+`include "memory_arbiter.sv"
+`include "occupancy_grid.sv"
+
+// An occupancy_grid that shares its bram with another client, behind it on a fixed priority
+// arbiter. While hog is up, the other client reads the last word, which is past the grid, so the
+// grid doesn't get the memory at all.
+module occupancy_grid_shared_wrapper #(
+    parameter GRID_WIDTH_LOG2,
+    parameter GRID_HEIGHT_LOG2,
+    parameter DATA_WIDTH,
+    parameter ADDR_WIDTH
+) (
+    input logic clk,
+    input logic rst_n,
+    input logic [GRID_WIDTH_LOG2-1:0] cell_x,
+    input logic [GRID_HEIGHT_LOG2-1:0] cell_y,
+    input logic input_valid,
+    output logic output_valid,
+    output logic ready_for_input,
+    input logic write_enable,
+    input logic write_occupied,
+    output logic read_occupied,
+
+    input logic hog,
+    // The grid's side of the arbiter
+    output logic grid_mem_request,
+    output logic grid_mem_grant,
+    output logic [ADDR_WIDTH-1:0] grid_mem_address,
+    output logic [DATA_WIDTH-1:0] grid_mem_write_data,
+    output logic grid_mem_write_enable
+);
+    memory_bus #(.ADDR_WIDTH(ADDR_WIDTH), .DATA_WIDTH(DATA_WIDTH)) mem ();
+    memory_bus #(.ADDR_WIDTH(ADDR_WIDTH), .DATA_WIDTH(DATA_WIDTH)) clients [2] ();
+    occupancy_grid_bus #(.GRID_WIDTH_LOG2(GRID_WIDTH_LOG2), .GRID_HEIGHT_LOG2(GRID_HEIGHT_LOG2)) grid_bus ();
+
+    assign clients[0].request = hog;
+    assign clients[0].address = '1;
+    assign clients[0].write_data = '0;
+    assign clients[0].write_enable = '0;
+
+    assign grid_mem_request = clients[1].request;
+    assign grid_mem_grant = clients[1].grant;
+    assign grid_mem_address = clients[1].address;
+    assign grid_mem_write_data = clients[1].write_data;
+    assign grid_mem_write_enable = clients[1].write_enable;
+
+    assign grid_bus.cell_x = cell_x;
+    assign grid_bus.cell_y = cell_y;
+    assign grid_bus.input_valid = input_valid;
+    assign output_valid = grid_bus.output_valid;
+    assign ready_for_input = grid_bus.ready_for_input;
+    assign grid_bus.write_enable = write_enable;
+    assign grid_bus.write_occupied = write_occupied;
+    assign read_occupied = grid_bus.read_occupied;
+
+    bram #(.ADDR_WIDTH(ADDR_WIDTH), .DATA_WIDTH(DATA_WIDTH)) bram_inst (
+        .clk(clk),
+        .bus(mem.memory)
+    );
+
+    memory_arbiter #(.NUM_CLIENTS(2), .ROUND_ROBIN(0)) arbiter (
+        .clk(clk),
+        .rst_n(rst_n),
+        .clients(clients),
+        .mem(mem.client)
+    );
+
+    occupancy_grid #(.GRID_WIDTH_LOG2(GRID_WIDTH_LOG2), .GRID_HEIGHT_LOG2(GRID_HEIGHT_LOG2)) uut (
+        .clk(clk),
+        .rst_n(rst_n),
+        .bus(grid_bus.grid),
+        .mem(clients[1])
+    );
+endmodule
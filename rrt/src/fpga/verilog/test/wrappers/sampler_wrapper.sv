@@ -0,0 +1,47 @@
+`include "prng.sv"
+`include "sampler.sv"
+
+module sampler_wrapper (
+    input logic clk,
+    input logic rst_n,
+
+    input logic [63:0] seed,
+
+    input logic [63:0] min_bound,
+    input logic [63:0] max_bound,
+    input logic [63:0] goal,
+    input logic [15:0] goal_bias,
+
+    input logic input_valid,
+    output logic done,
+
+    output logic [63:0] sample
+);
+    logic [63:0] random;
+    logic random_enable;
+
+    xoshiro256ss #(
+        .STREAM(0)
+    ) rng (
+        .clk(clk),
+        .rst_n(rst_n),
+        .enable(random_enable),
+        .seed(seed),
+        .out(random),
+        .ready()
+    );
+
+    sampler uut (
+        .clk(clk),
+        .rst_n(rst_n),
+        .min_bound(min_bound),
+        .max_bound(max_bound),
+        .goal(goal),
+        .goal_bias(goal_bias),
+        .random(random),
+        .random_enable(random_enable),
+        .input_valid(input_valid),
+        .done(done),
+        .sample(sample)
+    );
+endmodule
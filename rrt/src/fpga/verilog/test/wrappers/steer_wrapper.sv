@@ -0,0 +1,30 @@
+`include "steer.sv"
+
+module steer_wrapper #(
+    parameter ITERATIONS
+) (
+    input logic clk,
+    input logic rst_n,
+
+    input logic [63:0] from,
+    input logic [63:0] toward,
+    input logic [31:0] move_dist,
+
+    input logic input_valid,
+    output logic done,
+
+    output logic [63:0] result,
+    output logic out_of_bounds
+);
+    steer #(.ITERATIONS(ITERATIONS)) uut (
+        .clk(clk),
+        .rst_n(rst_n),
+        .from(from),
+        .toward(toward),
+        .move_dist(move_dist),
+        .input_valid(input_valid),
+        .done(done),
+        .result(result),
+        .out_of_bounds(out_of_bounds)
+    );
+endmodule
@@ -0,0 +1,73 @@
+`include "bram.sv"
+`include "memory_arbiter.sv"
+`include "tree_readback.sv"
+
+// tree_readback on a bram shared with the host, which has priority. The host writes the tree in, and
+// can hold the memory with a read to make the readback wait.
+module tree_readback_wrapper #(
+    parameter TREE_ADDR_WIDTH
+) (
+    input logic clk,
+    input logic rst_n,
+
+    input logic full_tree,
+    input logic [TREE_ADDR_WIDTH-1:0] goal_node,
+    input logic [TREE_ADDR_WIDTH:0] node_count,
+
+    input logic input_valid,
+    output logic ready_for_input,
+
+    output logic [TREE_ADDR_WIDTH-1:0] out_index,
+    output logic [63:0] out_point,
+    output logic [TREE_ADDR_WIDTH-1:0] out_parent,
+    output logic out_last,
+    output logic out_valid,
+    input logic out_ready,
+
+    input logic host_request,
+    input logic [TREE_ADDR_WIDTH-1:0] host_address,
+    input logic [63:0] host_point,
+    input logic [TREE_ADDR_WIDTH-1:0] host_parent,
+    input logic host_write_enable,
+    output logic host_grant
+);
+    localparam TREE_DATA_WIDTH = TREE_ADDR_WIDTH + 64;
+
+    memory_bus #(.ADDR_WIDTH(TREE_ADDR_WIDTH), .DATA_WIDTH(TREE_DATA_WIDTH)) mem ();
+    memory_bus #(.ADDR_WIDTH(TREE_ADDR_WIDTH), .DATA_WIDTH(TREE_DATA_WIDTH)) clients [2] ();
+
+    assign clients[0].request = host_request;
+    assign clients[0].address = host_address;
+    assign clients[0].write_data = {host_parent, host_point};
+    assign clients[0].write_enable = host_write_enable;
+    assign host_grant = clients[0].grant;
+
+    bram #(.ADDR_WIDTH(TREE_ADDR_WIDTH), .DATA_WIDTH(TREE_DATA_WIDTH)) bram_inst (
+        .clk(clk),
+        .bus(mem.memory)
+    );
+
+    memory_arbiter #(.NUM_CLIENTS(2), .ROUND_ROBIN(0)) arbiter (
+        .clk(clk),
+        .rst_n(rst_n),
+        .clients(clients),
+        .mem(mem.client)
+    );
+
+    tree_readback #(.TREE_ADDR_WIDTH(TREE_ADDR_WIDTH)) uut (
+        .clk(clk),
+        .rst_n(rst_n),
+        .full_tree(full_tree),
+        .goal_node(goal_node),
+        .node_count(node_count),
+        .input_valid(input_valid),
+        .ready_for_input(ready_for_input),
+        .out_index(out_index),
+        .out_point(out_point),
+        .out_parent(out_parent),
+        .out_last(out_last),
+        .out_valid(out_valid),
+        .out_ready(out_ready),
+        .tree_mem(clients[1])
+    );
+endmodule
@@ -1,7 +1,60 @@
+use std::ops::DerefMut;
+
+use bitvec::boxed::BitBox;
+use bitvec::order::Lsb0;
+use bitvec::ptr::Mut;
+use bitvec::slice::BitSlice;
+use bitvec::vec::BitVec;
 use na::Vector2;
 
-pub struct OccupancyGrid {
-    storage: Box<[bool]>,
+/// What an `OccupancyGrid` keeps its cells in, row after row.
+///
+/// The default is one bit per cell, packed into `u64`s like the hardware's memory, and
+/// `Box<[bool]>` is the old one byte per cell layout, kept around to compare against.
+pub trait CellStorage {
+    type CellMut<'a>: DerefMut<Target = bool>
+    where
+        Self: 'a;
+
+    fn empty(len: usize) -> Self;
+    fn get(&self, index: usize) -> &bool;
+    fn get_mut(&mut self, index: usize) -> Self::CellMut<'_>;
+}
+
+impl CellStorage for BitBox<u64, Lsb0> {
+    type CellMut<'a> = bitvec::ptr::BitRef<'a, Mut, u64, Lsb0>;
+
+    fn empty(len: usize) -> Self {
+        BitVec::repeat(false, len).into_boxed_bitslice()
+    }
+
+    fn get(&self, index: usize) -> &bool {
+        &self[index]
+    }
+
+    fn get_mut(&mut self, index: usize) -> Self::CellMut<'_> {
+        BitSlice::get_mut(self, index).unwrap()
+    }
+}
+
+impl CellStorage for Box<[bool]> {
+    type CellMut<'a> = &'a mut bool;
+
+    fn empty(len: usize) -> Self {
+        vec![false; len].into_boxed_slice()
+    }
+
+    fn get(&self, index: usize) -> &bool {
+        &self[index]
+    }
+
+    fn get_mut(&mut self, index: usize) -> Self::CellMut<'_> {
+        &mut self[index]
+    }
+}
+
+pub struct OccupancyGrid<S: CellStorage = BitBox<u64, Lsb0>> {
+    storage: S,
     x_cells: usize,
     y_cells: usize,
     origin: Vector2<f32>,
@@ -15,8 +68,59 @@ impl OccupancyGrid {
         origin: Vector2<f32>,
         resolution: f32,
     ) -> OccupancyGrid {
+        OccupancyGrid::with_storage(x_cells, y_cells, origin, resolution)
+    }
+
+    /// The packed cells, where cell `(x, y)` is bit `(y * x_cells + x) % 64` of word
+    /// `(y * x_cells + x) / 64`. Bits past the last cell are 0.
+    pub fn words(&self) -> &[u64] {
+        self.storage.as_raw_slice()
+    }
+
+    /// Each row of cells, from `y = 0` up.
+    pub fn rows(&self) -> impl Iterator<Item = &BitSlice<u64, Lsb0>> {
+        self.storage.chunks_exact(self.x_cells.max(1))
+    }
+
+    pub fn row(&self, y: usize) -> &BitSlice<u64, Lsb0> {
+        assert!(y < self.y_cells);
+        &self.storage[y * self.x_cells..(y + 1) * self.x_cells]
+    }
+
+    pub fn row_mut(&mut self, y: usize) -> &mut BitSlice<u64, Lsb0> {
+        assert!(y < self.y_cells);
+        &mut self.storage[y * self.x_cells..(y + 1) * self.x_cells]
+    }
+
+    /// Sets every cell to `value`.
+    pub fn fill(&mut self, value: bool) {
+        self.storage.fill(value);
+    }
+
+    /// Sets every cell in the `width` by `height` block with `(x, y)` as its lowest corner to
+    /// `value`, a row slice at a time.
+    pub fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, value: bool) {
+        assert!(x + width <= self.x_cells && y + height <= self.y_cells);
+        for row in y..y + height {
+            self.row_mut(row)[x..x + width].fill(value);
+        }
+    }
+
+    pub fn count_occupied(&self) -> usize {
+        self.storage.count_ones()
+    }
+}
+
+impl<S: CellStorage> OccupancyGrid<S> {
+    /// An empty grid in whichever storage `S` is. `OccupancyGrid::new` is this with the default.
+    pub fn with_storage(
+        x_cells: usize,
+        y_cells: usize,
+        origin: Vector2<f32>,
+        resolution: f32,
+    ) -> OccupancyGrid<S> {
         OccupancyGrid {
-            storage: vec![false; x_cells * y_cells].into_boxed_slice(),
+            storage: S::empty(x_cells * y_cells),
             x_cells,
             y_cells,
             origin,
@@ -24,14 +128,30 @@ impl OccupancyGrid {
         }
     }
 
+    /// A copy of this grid in another storage.
+    pub fn to_storage<T: CellStorage>(&self) -> OccupancyGrid<T> {
+        let mut grid = OccupancyGrid::<T>::with_storage(
+            self.x_cells,
+            self.y_cells,
+            self.origin,
+            self.resolution,
+        );
+        for y in 0..self.y_cells {
+            for x in 0..self.x_cells {
+                *grid.cell_mut(x, y) = *self.cell(x, y);
+            }
+        }
+        grid
+    }
+
     pub fn cell(&self, x: usize, y: usize) -> &bool {
         assert!(x < self.x_cells && y < self.y_cells);
-        &self.storage[y * self.x_cells + x]
+        self.storage.get(y * self.x_cells + x)
     }
 
-    pub fn cell_mut(&mut self, x: usize, y: usize) -> &mut bool {
+    pub fn cell_mut(&mut self, x: usize, y: usize) -> S::CellMut<'_> {
         assert!(x < self.x_cells && y < self.y_cells);
-        &mut self.storage[y * self.x_cells + x]
+        self.storage.get_mut(y * self.x_cells + x)
     }
 
     pub fn position_to_cell(&self, pos: &Vector2<f32>) -> (usize, usize) {
@@ -43,6 +163,60 @@ impl OccupancyGrid {
         (cell_f.x as usize, cell_f.y as usize)
     }
 
+    /// Updates the grid with a range measurement that went from `from` and hit something at `to`,
+    /// like a lidar beam: every cell the beam passes through is cleared, and the cell it ends in is
+    /// marked occupied. A position on a cell boundary belongs to the cell above it, and a beam that
+    /// goes exactly through a corner passes through the corner's x neighbour.
+    ///
+    /// This is what the hardware does in mark mode (see `fpga::fixed::apply_ray`).
+    pub fn apply_ray(&mut self, from: &Vector2<f32>, to: &Vector2<f32>) {
+        // The far edges belong to the last cells rather than ones past the end
+        let to_cell = |pos| {
+            let (x, y) = self.position_to_cell(pos);
+            (x.min(self.x_cells - 1), y.min(self.y_cells - 1))
+        };
+        let (mut cell_x, mut cell_y) = to_cell(from);
+        let (end_x, end_y) = to_cell(to);
+
+        let from = from.cast::<f64>();
+        let delta = to.cast::<f64>() - from;
+        let origin = self.origin.cast::<f64>();
+        let resolution = self.resolution as f64;
+
+        while (cell_x, cell_y) != (end_x, end_y) {
+            *self.cell_mut(cell_x, cell_y) = false;
+
+            let step_x = if cell_x == end_x {
+                false
+            } else if cell_y == end_y {
+                true
+            } else {
+                // Compare the t to each boundary without dividing, so corners are found exactly
+                let next_x = (if delta.x > 0.0 { cell_x + 1 } else { cell_x }) as f64 * resolution
+                    + origin.x;
+                let next_y = (if delta.y > 0.0 { cell_y + 1 } else { cell_y }) as f64 * resolution
+                    + origin.y;
+                (next_x - from.x).abs() * delta.y.abs() <= (next_y - from.y).abs() * delta.x.abs()
+            };
+
+            if step_x {
+                cell_x = if delta.x > 0.0 {
+                    cell_x + 1
+                } else {
+                    cell_x - 1
+                };
+            } else {
+                cell_y = if delta.y > 0.0 {
+                    cell_y + 1
+                } else {
+                    cell_y - 1
+                };
+            }
+        }
+
+        *self.cell_mut(end_x, end_y) = true;
+    }
+
     pub fn size(&self) -> (usize, usize) {
         (self.x_cells, self.y_cells)
     }
@@ -62,3 +236,65 @@ impl OccupancyGrid {
         self.resolution
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use na::vector;
+
+    use super::*;
+
+    #[test]
+    fn test_words_and_rows() {
+        let mut grid = OccupancyGrid::new(10, 8, vector![0.0, 0.0], 1.0);
+        *grid.cell_mut(3, 0) = true;
+        *grid.cell_mut(0, 7) = true;
+        *grid.cell_mut(9, 7) = true;
+
+        // 80 cells is two words, with the last 48 bits of the second unused
+        assert_eq!(grid.words(), &[1 << 3, (1 << 6) | (1 << 15)]);
+        assert_eq!(grid.rows().count(), 8);
+        assert_eq!(grid.row(7).count_ones(), 2);
+        assert!(grid.rows().nth(7).unwrap()[9]);
+        assert_eq!(grid.count_occupied(), 3);
+
+        // Writes through a BitRef only land when it's dropped
+        *grid.cell_mut(3, 0) = false;
+        assert!(!*grid.cell(3, 0));
+    }
+
+    #[test]
+    fn test_fill() {
+        let mut grid = OccupancyGrid::new(70, 5, vector![0.0, 0.0], 1.0);
+        grid.fill(true);
+        assert_eq!(grid.count_occupied(), 350);
+
+        grid.fill(false);
+        grid.fill_rect(60, 1, 8, 3, true);
+        assert_eq!(grid.count_occupied(), 24);
+        for y in 0..5 {
+            for x in 0..70 {
+                let inside = (60..68).contains(&x) && (1..4).contains(&y);
+                assert_eq!(*grid.cell(x, y), inside, "cell ({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_byte_storage_matches() {
+        let mut grid = OccupancyGrid::new(13, 7, vector![1.0, 2.0], 0.5);
+        grid.fill_rect(2, 3, 5, 2, true);
+        *grid.cell_mut(12, 6) = true;
+
+        let bytes: OccupancyGrid<Box<[bool]>> = grid.to_storage();
+        assert_eq!(bytes.size(), grid.size());
+        assert_eq!(bytes.origin(), grid.origin());
+        for y in 0..7 {
+            for x in 0..13 {
+                assert_eq!(bytes.cell(x, y), grid.cell(x, y));
+            }
+        }
+
+        let back: OccupancyGrid = bytes.to_storage();
+        assert_eq!(back.words(), grid.words());
+    }
+}
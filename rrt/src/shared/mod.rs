@@ -1,2 +1,3 @@
 pub mod dfs;
 pub mod grid;
+pub mod prng;
@@ -0,0 +1,155 @@
+//! Software models of the generators in `prng.sv`, so the CPU side can get exactly the same random
+//! words as the hardware out of the same seed.
+
+/// Stands in for a seed of 0, which would otherwise get stuck at 0 forever.
+pub const ZERO_SEED_REPLACEMENT: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Model of `prng64`, a xorshift64.
+pub struct Xorshift64 {
+    state: u64,
+}
+
+impl Xorshift64 {
+    pub fn new(seed: u64) -> Xorshift64 {
+        Xorshift64 {
+            state: if seed == 0 {
+                ZERO_SEED_REPLACEMENT
+            } else {
+                seed
+            },
+        }
+    }
+
+    /// Returns the word `out` has right now, then steps, like a cycle with `enable` high.
+    pub fn next_u64(&mut self) -> u64 {
+        let out = self.state;
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.state = x;
+        out
+    }
+}
+
+/// Model of `xoshiro256ss`, a xoshiro256** seeded by running the seed through splitmix64.
+///
+/// See <https://prng.di.unimi.it/xoshiro256starstar.c>.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Xoshiro256StarStar {
+    s: [u64; 4],
+}
+
+impl Xoshiro256StarStar {
+    const JUMP: [u64; 4] = [
+        0x180e_c6d3_3cfd_0aba,
+        0xd5a6_1266_f0c9_392c,
+        0xa958_2618_e03f_c9aa,
+        0x39ab_dc45_29b1_661c,
+    ];
+
+    pub fn new(seed: u64) -> Xoshiro256StarStar {
+        // splitmix64 never gives four zeros in a row, so the state can't be all zero
+        let mut counter = seed;
+        let s = [(); 4].map(|_| {
+            counter = counter.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = counter;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^ (z >> 31)
+        });
+        Xoshiro256StarStar { s }
+    }
+
+    /// Gets the generator for stream number `stream` out of `seed`, the same as `xoshiro256ss`
+    /// with `STREAM` set to `stream`. Streams are 2^128 words apart, so they never overlap in
+    /// practice.
+    pub fn with_stream(seed: u64, stream: u32) -> Xoshiro256StarStar {
+        let mut rng = Xoshiro256StarStar::new(seed);
+        for _ in 0..stream {
+            rng.jump();
+        }
+        rng
+    }
+
+    /// Skips ahead 2^128 words.
+    pub fn jump(&mut self) {
+        let mut t = [0; 4];
+        for word in Self::JUMP {
+            for bit in 0..64 {
+                if word & (1 << bit) != 0 {
+                    for (t, s) in t.iter_mut().zip(self.s) {
+                        *t ^= s;
+                    }
+                }
+                self.next_u64();
+            }
+        }
+        self.s = t;
+    }
+
+    /// Returns the word `out` has right now, then steps, like a cycle with `enable` high.
+    pub fn next_u64(&mut self) -> u64 {
+        let s = &mut self.s;
+        let out = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xorshift_zero_seed() {
+        let mut rng = Xorshift64::new(0);
+        assert_eq!(rng.next_u64(), ZERO_SEED_REPLACEMENT);
+        assert_ne!(rng.next_u64(), 0);
+    }
+
+    #[test]
+    fn test_xoshiro_reference() {
+        // Same outputs as the C reference, from a state of [1, 2, 3, 4]
+        let mut rng = Xoshiro256StarStar { s: [1, 2, 3, 4] };
+        let out: Vec<_> = (0..4).map(|_| rng.next_u64()).collect();
+        assert_eq!(out, [0x2d00, 0, 0x5a00_7080, 0x10e0_0000_0000_9d80]);
+
+        rng = Xoshiro256StarStar { s: [1, 2, 3, 4] };
+        rng.jump();
+        assert_eq!(
+            rng.s,
+            [
+                0x8c7a_1539_56b5_f3d1,
+                0x701f_1a71_3401_d85e,
+                0x6527_f66a_6546_9085,
+                0x8386_b786_c440_8050
+            ]
+        );
+    }
+
+    #[test]
+    fn test_xoshiro_seeding() {
+        // The first splitmix64 output for 0 is a well known one
+        let mut rng = Xoshiro256StarStar::new(0);
+        assert_eq!(rng.s[0], 0xe220_a839_7b1d_cdaf);
+        assert_eq!(rng.next_u64(), 0x99ec_5f36_cb75_f2b4);
+
+        assert_eq!(
+            Xoshiro256StarStar::with_stream(5, 0),
+            Xoshiro256StarStar::new(5)
+        );
+        let mut jumped = Xoshiro256StarStar::new(5);
+        jumped.jump();
+        jumped.jump();
+        assert_eq!(Xoshiro256StarStar::with_stream(5, 2), jumped);
+    }
+}